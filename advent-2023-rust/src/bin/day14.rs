@@ -1,3 +1,5 @@
+use advent_2023_rust::state_after;
+
 type Output = usize;
 
 fn part1(text: &str) -> Output {
@@ -26,6 +28,7 @@ fn part1(text: &str) -> Output {
     total_load
 }
 
+#[derive(Clone, PartialEq)]
 struct Grid {
     grid: Vec<char>,
     rows: usize,
@@ -142,38 +145,26 @@ fn count_load(grid: &str) -> usize {
         .sum();
 }
 
-fn part2(text: &str) -> Output {
-    let mut grid = Grid::new(text);
-    let mut history = vec![];
-    let mut non_repeat_length = 0;
-    let mut cycle_length = 0;
-    for step in 0.. {
-        roll_vertically(&mut grid, true);
-        roll_horizontally(&mut grid, true);
-        roll_vertically(&mut grid, false);
-        roll_horizontally(&mut grid, false);
-        if let Some(prev) =
-            history
-                .iter()
-                .enumerate()
-                .find_map(|(i, prev)| if prev == &grid.grid { Some(i) } else { None })
-        {
-            println!("==================== END {prev} ====================");
-            non_repeat_length = prev;
-            cycle_length = step - prev;
-            break;
-        }
-        history.push(grid.grid.to_owned());
-    }
+/// One spin cycle: north, west, south, then east
+fn spin(grid: &Grid) -> Grid {
+    let mut grid = grid.clone();
+    roll_vertically(&mut grid, true);
+    roll_horizontally(&mut grid, true);
+    roll_vertically(&mut grid, false);
+    roll_horizontally(&mut grid, false);
+    grid
+}
 
-    let index = (1_000_000_000 - non_repeat_length - 1) % cycle_length + non_repeat_length;
-    return count_load(
-        &history[index]
+fn part2(text: &str) -> Output {
+    let grid = state_after(&Grid::new(text), spin, 1_000_000_000);
+    count_load(
+        &grid
+            .grid
             .chunks(grid.cols)
             .map(String::from_iter)
             .collect::<Vec<_>>()
             .join("\n"),
-    );
+    )
 }
 
 fn main() -> std::io::Result<()> {
@@ -1,9 +1,7 @@
 use std::cmp::Ordering;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 
-use itertools::Itertools;
-use num_integer::{ExtendedGcd, Integer};
-use num_traits::Euclid;
+use num_integer::Integer;
 
 /// (row, column)
 #[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
@@ -161,74 +159,310 @@ impl Direc {
     }
 }
 
-pub fn chinese_remainder<T: Integer + Clone + Debug + Euclid>(
-    remainder_modulus: Vec<(T, T)>,
-    cast: impl Fn(isize) -> T,
-) -> T {
-    // println!("{:?}", (remainder_modulus));
-    let zero = cast(0);
-    let one = cast(1);
-    for (a, b) in remainder_modulus.iter().tuple_combinations() {
-        // moduli must be coprime
-        let gcd = &a.1.gcd(&b.1);
-        assert_eq!(
-            gcd,
-            &one,
-            "gcd({:?}, {:?})={gcd:?} ({gcd:?} * {:?}, {gcd:?} * {:?})",
-            &a.1,
-            &b.1,
-            a.1.to_owned() / gcd.to_owned(),
-            b.1.to_owned() / gcd.to_owned(),
-        );
+/// Why a system of congruences has no solution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrtError<T> {
+    /// Moduli have to be positive
+    NonPositiveModulus(T),
+    /// No number is both `first.0` modulo `first.1` and `second.0` modulo
+    /// `second.1`, as they differ modulo the GCD of the moduli. `first` is
+    /// the congruences before `second` merged together.
+    Inconsistent { first: (T, T), second: (T, T) },
+}
+
+impl<T: Display> Display for CrtError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CrtError::NonPositiveModulus(modulus) => {
+                write!(f, "modulus {} isn't positive", modulus)
+            }
+            CrtError::Inconsistent { first, second } => write!(
+                f,
+                "x = {} (mod {}) and x = {} (mod {}) can't both hold",
+                first.0, first.1, second.0, second.1
+            ),
+        }
+    }
+}
+
+impl<T: Debug + Display> std::error::Error for CrtError<T> {}
+
+/// `a + b (mod m)` for `a` and `b` already below `m`, without ever going
+/// past `m`, so machine integers can't overflow
+fn add_mod<T: Integer + Clone>(a: T, b: T, m: &T) -> T {
+    let room = m.clone() - b.clone();
+    if a >= room {
+        a - room
+    } else {
+        a + b
+    }
+}
+
+/// `a * b (mod m)` for `a` and `b` already below `m`, by doubling and adding
+/// so nothing goes past `m`
+fn mul_mod<T: Integer + Clone>(mut a: T, mut b: T, m: &T) -> T {
+    let two = T::one() + T::one();
+    let mut product = T::zero();
+    while !b.is_zero() {
+        if b.is_odd() {
+            product = add_mod(product, a.clone(), m);
+        }
+        a = add_mod(a.clone(), a, m);
+        b = b / two.clone();
+    }
+    product
+}
 
-        // I think the remainder has to be in its smallest form
-        assert!(&zero < &a.0 && &a.0 < &a.1);
-        assert!(&zero < &b.0 && &b.0 < &b.1);
+/// The inverse of `a` modulo `m`, which must be coprime. The extended GCD,
+/// but only tracking the coefficient of `a`, kept in `0..m` so it works for
+/// unsigned types too.
+fn mod_inverse<T: Integer + Clone>(a: T, m: &T) -> T {
+    // Each remainder is its coefficient times `a`, modulo `m`
+    let (mut r0, mut r1) = (m.clone(), a);
+    let (mut s0, mut s1) = (T::zero(), T::one().mod_floor(m));
+    while !r1.is_zero() {
+        let (quotient, r2) = r0.div_rem(&r1);
+        let taken = mul_mod(quotient.mod_floor(m), s1.clone(), m);
+        let s2 = add_mod(s0, m.clone() - taken, m).mod_floor(m);
+        (r0, r1) = (r1, r2);
+        (s0, s1) = (s1, s2);
     }
+    s0
+}
+
+/// The smallest non-negative `x` with `x = remainder (mod modulus)` for every
+/// pair, along with the LCM of the moduli, as every solution is `x` plus a
+/// multiple of it. The moduli don't need to be coprime, and remainders can be
+/// anything, even negative or bigger than their modulus. Congruences are
+/// merged one at a time. Nothing bigger than the final LCM is ever worked
+/// out, so machine integers are fine as long as that fits, and unsigned
+/// types like the `usize` cycle lengths of 2023 day 8 work too.
+pub fn chinese_remainder<T: Integer + Clone>(
+    remainder_modulus: impl IntoIterator<Item = (T, T)>,
+) -> std::result::Result<(T, T), CrtError<T>> {
+    let (mut x, mut lcm) = (T::zero(), T::one());
+    for (remainder, modulus) in remainder_modulus {
+        if modulus <= T::zero() {
+            return Err(CrtError::NonPositiveModulus(modulus));
+        }
+        let remainder = remainder.mod_floor(&modulus);
+        let gcd = lcm.gcd(&modulus);
+        if x.mod_floor(&gcd) != remainder.mod_floor(&gcd) {
+            return Err(CrtError::Inconsistent {
+                first: (x, lcm),
+                second: (remainder, modulus),
+            });
+        }
 
-    let mut x = zero.clone();
-    let cap_n = remainder_modulus
-        .iter()
-        .fold(one.to_owned(), |acc, (_, n_i)| acc * n_i.to_owned());
-    for (a_i, n_i) in remainder_modulus {
-        let y_i = cap_n.to_owned() / n_i.to_owned();
-        let ExtendedGcd { x: z_i, .. } = y_i.extended_gcd(&n_i);
-        // let z_i = z_i.rem_euclid(n_i);
-        // + Mul<&T, Output = T>
-        // let test = z_i * y_i;
-        // test.e
-        assert_eq!(&(z_i.to_owned() * y_i.to_owned()).rem_euclid(&n_i), &one);
-
-        x = x + a_i * y_i * z_i;
-        // x = x + a_i.to_owned() * y_i.to_owned() * z_i.to_owned();
-        // println!("{:?}", (a_i, y_i, z_i, a_i * y_i * z_i));
+        // x + lcm * k works for both when lcm * k = remainder - x
+        // (mod modulus), so k = (remainder - x) / gcd * (lcm / gcd)^-1
+        // (mod modulus / gcd)
+        let step = modulus.clone() / gcd.clone();
+        let x_mod = x.mod_floor(&modulus);
+        let difference = add_mod(remainder, modulus.clone() - x_mod, &modulus).mod_floor(&modulus);
+        let inverse = mod_inverse((lcm.clone() / gcd.clone()).mod_floor(&step), &step);
+        let k = mul_mod(difference / gcd, inverse, &step);
+        x = x + lcm.clone() * k;
+        lcm = lcm * step;
     }
-    // 0.rem_
-    let x = x.rem_euclid(&cap_n);
-    assert!(x >= zero);
-    x
+    Ok((x, lcm))
 }
 
 #[cfg(test)]
 mod test_chinese_remainder {
-    use crate::chinese_remainder;
+    use num_bigint::BigInt;
+
+    use crate::{chinese_remainder, CrtError};
 
     #[test]
     fn example1() {
-        let actual = chinese_remainder(vec![(1, 3), (4, 5), (6, 7)], |x| x);
-        assert_eq!(actual, 34);
+        let actual = chinese_remainder(vec![(1, 3), (4, 5), (6, 7)]);
+        assert_eq!(actual, Ok((34, 105)));
     }
 
     #[test]
     fn example2() {
-        let actual = chinese_remainder(vec![(2, 3), (3, 8)], |x| x);
-        assert_eq!(actual, 11);
+        let actual = chinese_remainder(vec![(2, 3), (3, 8)]);
+        assert_eq!(actual, Ok((11, 24)));
     }
 
     #[test]
     fn single_entry() {
-        let actual = chinese_remainder(vec![(2, 3)], |x| x);
-        assert_eq!(actual, 2);
+        let actual = chinese_remainder(vec![(2, 3)]);
+        assert_eq!(actual, Ok((2, 3)));
+    }
+
+    #[test]
+    fn nothing() {
+        assert_eq!(chinese_remainder(Vec::<(i32, i32)>::new()), Ok((0, 1)));
+    }
+
+    #[rstest::rstest]
+    #[case::shared_factor(vec![(3, 4), (1, 6)], Ok((7, 12)))]
+    #[case::zero_remainders(vec![(0, 4), (0, 6), (0, 10)], Ok((0, 60)))]
+    #[case::unreduced_remainders(vec![(-1, 3), (14, 5)], Ok((14, 15)))]
+    #[case::same_modulus(vec![(5, 7), (12, 7)], Ok((5, 7)))]
+    #[case::divides(vec![(1, 2), (3, 8), (11, 16)], Ok((11, 16)))]
+    #[case::inconsistent(
+        vec![(1, 3), (2, 4), (3, 6)],
+        Err(CrtError::Inconsistent { first: (10, 12), second: (3, 6) })
+    )]
+    #[case::zero_modulus(vec![(1, 3), (1, 0)], Err(CrtError::NonPositiveModulus(0)))]
+    #[case::negative_modulus(vec![(1, -3)], Err(CrtError::NonPositiveModulus(-3)))]
+    // The square of the second modulus is past i64, but the answer isn't
+    #[case::big_moduli(vec![(1, 11), (4_000_000_006, 4_000_000_007)], Ok((32_000_000_055, 44_000_000_077)))]
+    #[case::near_max(
+        vec![(1, 3_037_000_493), (2, 3_037_000_453)],
+        Ok((691_752_890_551_091_763, 9_223_371_873_002_223_329))
+    )]
+    fn generalized(
+        #[case] congruences: Vec<(i64, i64)>,
+        #[case] expected: Result<(i64, i64), CrtError<i64>>,
+    ) {
+        let actual = chinese_remainder(congruences.clone());
+        assert_eq!(actual, expected);
+        if let Ok((x, lcm)) = actual {
+            assert!((0..lcm).contains(&x));
+            for (remainder, modulus) in congruences {
+                assert_eq!((x - remainder).rem_euclid(modulus), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn unsigned() {
+        // Cycle lengths, as in 2023 day 8
+        let cycles = [(0_usize, 20_777), (0, 19_199), (0, 18_673)];
+        let (x, lcm) = chinese_remainder(cycles).unwrap();
+        assert_eq!((x, lcm), (0, 20_777 * 19_199 * 18_673 / 263 / 263));
+        assert_eq!(chinese_remainder([(2_u64, 3), (3, 5)]), Ok((8, 15)));
+        assert_eq!(
+            chinese_remainder([(1_u8, 3), (2, 4), (3, 6)]),
+            Err(CrtError::Inconsistent {
+                first: (10, 12),
+                second: (3, 6)
+            })
+        );
+    }
+
+    #[test]
+    fn big_int() {
+        // Primes near 2^61, whose product overflows a u128
+        let moduli = [
+            2305843009213693951_i64,
+            2305843009213693921,
+            2305843009213693907,
+        ];
+        let congruences = moduli
+            .iter()
+            .zip([5, -7, 11])
+            .map(|(modulus, remainder)| (BigInt::from(remainder), BigInt::from(*modulus)))
+            .collect::<Vec<_>>();
+        let (x, lcm) = chinese_remainder(congruences.clone()).unwrap();
+        assert_eq!(
+            lcm,
+            moduli
+                .iter()
+                .map(|modulus| BigInt::from(*modulus))
+                .product()
+        );
+        for (remainder, modulus) in congruences {
+            assert_eq!((&x - remainder) % modulus, BigInt::from(0));
+        }
+    }
+}
+
+/// Where the states made by applying `step` over and over to `initial` start
+/// repeating, as `(mu, lambda)`: the state after `mu` steps is the first one
+/// to come round again, every `lambda` steps. Uses Brent's algorithm, so
+/// nothing is remembered but a couple of states, and states only need
+/// comparing, not hashing. Never returns if the states don't repeat.
+pub fn find_cycle<T: Clone + PartialEq>(
+    initial: &T,
+    mut step: impl FnMut(&T) -> T,
+) -> (usize, usize) {
+    // The hare runs ahead, and the tortoise teleports to it at every power
+    // of two, until the hare catches it from behind
+    let (mut power, mut lambda) = (1, 1);
+    let mut tortoise = initial.clone();
+    let mut hare = step(initial);
+    while tortoise != hare {
+        if power == lambda {
+            tortoise = hare.clone();
+            power *= 2;
+            lambda = 0;
+        }
+        hare = step(&hare);
+        lambda += 1;
+    }
+
+    // With the hare a whole cycle ahead, they first meet where it starts
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    for _ in 0..lambda {
+        hare = step(&hare);
+    }
+    let mut mu = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        mu += 1;
+    }
+    (mu, lambda)
+}
+
+/// The state after `n` steps from `initial`, skipping every whole time round
+/// the cycle, so `n` can be far too many to simulate
+pub fn state_after<T: Clone + PartialEq>(
+    initial: &T,
+    mut step: impl FnMut(&T) -> T,
+    n: usize,
+) -> T {
+    let (mu, lambda) = find_cycle(initial, &mut step);
+    let n = if n < mu { n } else { mu + (n - mu) % lambda };
+    (0..n).fold(initial.clone(), |state, _| step(&state))
+}
+
+#[cfg(test)]
+mod test_find_cycle {
+    use crate::{find_cycle, state_after};
+
+    #[rstest::rstest]
+    #[case::fixed_point(5, |x: &u64| (*x).max(3) - 1, (3, 1))]
+    #[case::immediate(0, |x: &u64| (x + 1) % 7, (0, 7))]
+    #[case::tail(0, |x: &u64| if *x < 10 { x + 1 } else { 4 + (x - 4 + 1) % 7 }, (4, 7))]
+    // x^2 + 1 (mod 255): the classic Pollard rho sequence from 3
+    #[case::rho(3, |x: &u64| (x * x + 1) % 255, (2, 6))]
+    fn cycles(
+        #[case] initial: u64,
+        #[case] step: fn(&u64) -> u64,
+        #[case] expected: (usize, usize),
+    ) {
+        assert_eq!(find_cycle(&initial, step), expected);
+
+        // Against every state so far
+        let mut states = vec![initial];
+        let first_repeat = loop {
+            let next = step(states.last().unwrap());
+            if let Some(mu) = states.iter().position(|state| *state == next) {
+                break (mu, states.len() - mu);
+            }
+            states.push(next);
+        };
+        assert_eq!(first_repeat, expected);
+        for n in 0..50 {
+            let simulated = (0..n).fold(initial, |state, _| step(&state));
+            assert_eq!(state_after(&initial, step, n), simulated);
+        }
+    }
+
+    #[test]
+    fn billions_of_steps() {
+        let step = |x: &u64| (x * 31 + 7) % 1_000;
+        let (mu, lambda) = find_cycle(&1, step);
+        let after = state_after(&1, step, 1_000_000_000);
+        let n = mu + (1_000_000_000 - mu) % lambda;
+        assert_eq!(after, (0..n).fold(1, |state, _| step(&state)));
     }
 }
 
@@ -1,11 +1,9 @@
-use std::collections::HashSet;
 use std::str::FromStr;
 
-use advent_2024_rust::{CoinChange, Point};
+use advent_2024_rust::{CoinChange, CoinChangePair, Point};
 use anyhow::{Context, Result};
 use itertools::Itertools;
 use lazy_static::lazy_static;
-use num_integer::Integer;
 use regex::Regex;
 
 type Output = usize;
@@ -111,6 +109,31 @@ fn part1(text: &str) -> Output {
     total
 }
 
+/// The fewest tokens to win the prize, at 3 per press of A and 1 per press of
+/// B, with no limit on presses. Every way to reach the prize's x is a step
+/// along from the first, and along those steps y changes linearly, so at most
+/// one of them (or all of them) also reaches the prize's y.
+fn cheapest(a: Point<usize>, b: Point<usize>, prize: Point<usize>) -> Option<usize> {
+    let xs = CoinChangePair::try_new(a.x, b.x, prize.x)?;
+    let cost = |(a_presses, b_presses): (usize, usize)| a_presses * 3 + b_presses;
+    let last = xs.len() - 1;
+    let (first, step) = (xs.solution(0), xs.step());
+
+    // The y after the k-th way is y + k * dy
+    let y = (a.y * first.0 + b.y * first.1) as i128;
+    let dy = (b.y * step.1) as i128 - (a.y * step.0) as i128;
+    let missing = prize.y as i128 - y;
+    if dy == 0 {
+        // The cost also changes linearly, so one end is the cheapest
+        return (missing == 0).then(|| cost(xs.solution(0)).min(cost(xs.solution(last))));
+    }
+    if missing % dy != 0 {
+        return None;
+    }
+    let k = usize::try_from(missing / dy).ok().filter(|k| *k <= last)?;
+    Some(cost(xs.solution(k)))
+}
+
 fn part2(text: &str) -> Output {
     let mut total = 0;
 
@@ -126,63 +149,9 @@ fn part2(text: &str) -> Output {
             })
             .unwrap();
 
-        // let prize = prize.add(&UsizePoint(10_000_000_000_000, 10_000_000_000_000));
-        // let x_lcd = a.0 * b.0 / a.0.gcd(&b.0);
-
-        // let [mut iter_xs, mut iter_ys] =
-        let [xs, ys] = [(a.0, b.0, prize.0), (a.1, b.1, prize.1)].map(|(a, b, prize)| {
-            let prize = prize + 10_000_000_000_000;
-            let lcd = a * b; // / a.gcd(&b);
-            let remain = prize % lcd;
-            let div = prize / lcd;
-
-            let set = CoinChange::new(&[b, a], remain)
-                .map(move |ba| {
-                    if let [b_count, a_count] = ba[0..2] {
-                        return (b_count, a_count);
-                    } else {
-                        panic!("duplication glitch found!");
-                    }
-                })
-                .collect::<HashSet<_>>();
-            return (set, prize, lcd);
-            // .cartesian_product(0..div)
-            // .map(move |((b, a), extra)| (b + (div - extra), a + extra))
-
-            // CoinChange::new(&[b, a], prize).map(|ba| {
-            //     if let [b, a] = ba[0..2] {
-            //         return (b, a);
-            //     } else {
-            //         panic!("duplication glitch found!");
-            //     }
-            // })
-        });
-
-        println!("x, y: {:?}", (&xs.0, &ys.0));
-        if xs.0.intersection(&ys.0).count() == 0 {
-            continue;
-        } else {
-            println!("valid? {:?}", (&a, &b));
-        }
-
-        //         let (mut xs, mut ys) = match (iter_xs.next(), iter_ys.next()) {
-        //             (Some(xs), Some(ys)) => (xs, ys),
-        //             _ => continue,
-        //         };
-        //
-        //         while xs != ys {
-        //             if xs.0 > ys.0 {
-        //                 let Some(new_xs) = iter_xs.next() else { break };
-        //                 xs = new_xs;
-        //             } else {
-        //                 let Some(new_ys) = iter_ys.next() else { break };
-        //                 ys = new_ys;
-        //             }
-        //         }
-        //         if xs == ys {
-        //             // xs = (b, a)
-        //             total += xs.0 + xs.1 * 3;
-        //         }
+        let prize =
+            Point::<usize>::new_xy(prize.x + 10_000_000_000_000, prize.y + 10_000_000_000_000);
+        total += cheapest(a, b, prize).unwrap_or(0);
     }
 
     total
@@ -227,6 +196,6 @@ mod tests {
 
     #[test]
     fn part2_given_example() {
-        assert_eq!(part2(TEXT), 480);
+        assert_eq!(part2(TEXT), 875_318_608_908);
     }
 }
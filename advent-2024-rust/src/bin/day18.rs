@@ -1,5 +1,4 @@
-#![feature(assert_matches)]
-use std::assert_matches::assert_matches;
+use std::assert_matches;
 use std::cmp::Ordering;
 use std::collections::{HashSet, VecDeque};
 
@@ -1,12 +1,14 @@
-#![feature(assert_matches)]
 #![feature(box_patterns)] // TODO: Replace with deref patterns when implemented for boxes
 mod libs;
 
-pub use libs::coin_change::CoinChange;
+pub use libs::coin_change::{CoinChange, CoinChangePair};
+pub use libs::constraints;
 pub use libs::direction::Direc;
 pub use libs::disjoint_set::{
     Count, DisjointSet, DisjointSetWithCount, Eve, EveAsIndex, EveOrNode,
 };
+pub use libs::linear_system;
+pub use libs::number_theory;
 pub use libs::point::Point;
 pub use libs::zipper::{Zipper, ZipperTrait};
 
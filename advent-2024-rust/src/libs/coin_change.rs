@@ -1,11 +1,17 @@
+use num_integer::{ExtendedGcd, Integer};
+
 pub struct CoinChange {
     coins: PerCoin,
     goal: usize,
+    face_values: Vec<usize>,
+    /// How many of each coin there are, `usize::MAX` for as many as needed
+    supply: Vec<usize>,
 }
 
 struct PerCoin {
     coin_value: usize,
     coin_count: usize,
+    max_count: usize,
     next: Option<Box<PerCoin>>,
 }
 
@@ -14,6 +20,11 @@ impl PerCoin {
     /// coins that sums to the goal
     fn next_trailing_counts(&mut self, goal: usize) -> Option<Vec<usize>> {
         loop {
+            // We've run out of this coin
+            if self.coin_count > self.max_count {
+                self.reset_trailing_counts();
+                return None;
+            }
             match goal.checked_sub(self.coin_value * self.coin_count) {
                 // We've exceeded the sum
                 None => {
@@ -58,18 +69,113 @@ impl PerCoin {
 
 impl CoinChange {
     pub fn new(face_values: &[usize], sum: usize) -> Self {
+        Self::with_supply(face_values, &vec![usize::MAX; face_values.len()], sum)
+    }
+
+    /// Only `supply[i]` of the coin `face_values[i]` can be used
+    pub fn with_supply(face_values: &[usize], supply: &[usize], sum: usize) -> Self {
+        assert_eq!(face_values.len(), supply.len(), "Every coin needs a supply");
+        assert!(
+            face_values.iter().all(|coin| *coin > 0),
+            "Coins must be worth something"
+        );
         let mut per_coin = None;
-        for coin in face_values {
+        for (coin, max_count) in face_values.iter().zip(supply) {
             per_coin = Some(PerCoin {
                 coin_count: 0,
                 coin_value: *coin,
+                max_count: *max_count,
                 next: per_coin.and_then(|per_coin| Some(Box::new(per_coin))),
             });
         }
         Self {
             coins: per_coin.expect("To have at least one coin"),
             goal: sum,
+            face_values: face_values.to_vec(),
+            supply: supply.to_vec(),
+        }
+    }
+
+    /// How many ways there are to make the sum, without going through them
+    pub fn count_ways(&self) -> u128 {
+        let goal = self.goal;
+        // The number of ways to make each value with the coins so far
+        let mut ways = vec![0_u128; goal + 1];
+        ways[0] = 1;
+        for (&coin, &supply) in self.face_values.iter().zip(&self.supply) {
+            let before = ways.clone();
+            for value in coin..=goal {
+                // Up to `supply` of this coin on top of each way with the
+                // coins before, which is one more than the ways for `value -
+                // coin`, minus those that already used all of them
+                ways[value] += ways[value - coin];
+                let too_many = supply
+                    .checked_add(1)
+                    .and_then(|count| count.checked_mul(coin))
+                    .filter(|too_many| *too_many <= value);
+                if let Some(too_many) = too_many {
+                    ways[value] -= before[value - too_many];
+                }
+            }
         }
+        ways[goal]
+    }
+
+    /// The counts of each coin in a way to make the sum with as few coins as
+    /// possible, or `None` if there's no way
+    pub fn min_coins(&self) -> Option<Vec<usize>> {
+        self.best_counts(|a, b| a < b)
+    }
+
+    /// The counts of each coin in a way to make the sum with as many coins as
+    /// possible, or `None` if there's no way
+    pub fn max_coins(&self) -> Option<Vec<usize>> {
+        self.best_counts(|a, b| a > b)
+    }
+
+    /// Dynamic programming over the coins, remembering how many of each coin
+    /// the best way to every value used, to walk back through at the end
+    fn best_counts(&self, better: fn(usize, usize) -> bool) -> Option<Vec<usize>> {
+        let goal = self.goal;
+        // The best number of coins making each value with the coins so far
+        let mut best = vec![None; goal + 1];
+        best[0] = Some(0);
+        let mut used = vec![];
+        for (&coin, &supply) in self.face_values.iter().zip(&self.supply) {
+            let mut next = best.clone();
+            let mut counts = vec![0; goal + 1];
+            for value in coin..=goal {
+                let candidates = if supply >= goal / coin {
+                    // Unlimited, so one more than the best for `value - coin`
+                    vec![(
+                        counts[value - coin] + 1,
+                        next[value - coin].map(|coins| coins + 1),
+                    )]
+                } else {
+                    (1..=supply.min(value / coin))
+                        .map(|count| (count, best[value - count * coin].map(|coins| coins + count)))
+                        .collect()
+                };
+                for (count, coins) in candidates {
+                    let Some(coins) = coins else { continue };
+                    if next[value].is_none_or(|current| better(coins, current)) {
+                        next[value] = Some(coins);
+                        counts[value] = count;
+                    }
+                }
+            }
+            best = next;
+            used.push(counts);
+        }
+
+        best[goal]?;
+        let mut value = goal;
+        let mut counts = vec![0; self.face_values.len()];
+        for (i, coin) in self.face_values.iter().enumerate().rev() {
+            counts[i] = used[i][value];
+            value -= counts[i] * coin;
+        }
+        Some(counts)
     }
 }
 
@@ -81,41 +187,108 @@ impl Iterator for CoinChange {
     }
 }
 
+/// Every way to make the goal from two coins, in the same order as
+/// `CoinChange`, but found directly rather than searched for. With `g` the
+/// GCD of the coins, swapping `b / g` of coin A for `a / g` of coin B keeps
+/// the sum, so the ways are one solution from the extended GCD stepped along
+/// by that swap. Works for goals far too big to search.
+#[derive(Debug, Clone)]
 pub struct CoinChangePair {
-    goal: usize,
-    coin_a: usize,
-    coin_b: usize,
-    num_a: usize,
+    /// The counts of coin A and coin B in the way with the most of coin A
+    first: (usize, usize),
+    step: (usize, usize),
+    /// Ways `front..back` are left
+    front: usize,
+    back: usize,
 }
 
 impl CoinChangePair {
-    pub fn try_new(coin_a: usize, coin_b: usize, goal: usize) -> Self {
-        let num_a = coin_a * (goal / coin_a);
-        Self {
-            goal,
-            coin_a,
-            coin_b,
-            num_a,
+    /// `None` if there's no way to make the goal
+    pub fn try_new(coin_a: usize, coin_b: usize, goal: usize) -> Option<Self> {
+        assert!(coin_a > 0 && coin_b > 0, "Coins must be worth something");
+        let (a, b) = (coin_a as i128, coin_b as i128);
+        // a * x + b * y = gcd
+        let ExtendedGcd { gcd, y, .. } = a.extended_gcd(&b);
+        if goal as i128 % gcd != 0 {
+            return None;
         }
+        let step = ((b / gcd) as usize, (a / gcd) as usize);
+
+        // The fewest of coin B is y * goal / gcd, modulo the step. Reducing
+        // both first keeps the product within a u128
+        let modulo = step.1 as u128;
+        let y = y.rem_euclid(step.1 as i128) as u128;
+        let count_b = (y * ((goal as i128 / gcd) as u128 % modulo) % modulo) as usize;
+        let rest = goal.checked_sub(count_b.checked_mul(coin_b)?)?;
+        let count_a = rest / coin_a;
+
+        Some(Self {
+            first: (count_a, count_b),
+            step,
+            front: 0,
+            back: count_a / step.0 + 1,
+        })
+    }
+
+    /// The counts of coin A and coin B in the `k`th way, regardless of how
+    /// far through the iterator is
+    pub fn solution(&self, k: usize) -> (usize, usize) {
+        (
+            self.first.0 - k * self.step.0,
+            self.first.1 + k * self.step.1,
+        )
+    }
+
+    /// How many of coin A are swapped for how many of coin B between one way
+    /// and the next
+    pub fn step(&self) -> (usize, usize) {
+        self.step
     }
 }
 
 impl Iterator for CoinChangePair {
-    type Item = Vec<usize>;
+    type Item = (usize, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
-        todo!()
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.solution(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for CoinChangePair {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.solution(self.back))
     }
 }
 
+impl ExactSizeIterator for CoinChangePair {}
+
 #[cfg(test)]
 mod test_coin_change {
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
     use std::time::Duration;
 
     use itertools::Itertools;
     use rstest::rstest;
 
+    use super::CoinChangePair;
     use crate::CoinChange;
 
     #[rstest]
@@ -184,4 +357,93 @@ mod test_coin_change {
             .count();
         assert_eq!(actual, n_sequences);
     }
+
+    #[rstest]
+    #[case::nim(9, vec![1, 2], vec![usize::MAX; 2])]
+    #[case::quarter_dime_penny(66, vec![25, 10, 1], vec![usize::MAX; 3])]
+    #[case::impossible(11, vec![4, 5], vec![usize::MAX; 2])]
+    #[case::limited(66, vec![25, 10, 1], vec![2, 3, 20])]
+    #[case::none_left(30, vec![25, 10, 5], vec![1, 0, 0])]
+    #[case::zero(0, vec![3, 7], vec![0, 0])]
+    fn count_and_extremes(
+        #[case] goal: usize,
+        #[case] coins: Vec<usize>,
+        #[case] supply: Vec<usize>,
+    ) {
+        let change = CoinChange::with_supply(&coins, &supply, goal);
+        let every_way = CoinChange::with_supply(&coins, &supply, goal).collect_vec();
+        assert!(every_way
+            .iter()
+            .all(|counts| counts.iter().zip(&supply).all(|(count, max)| count <= max)));
+        assert_eq!(change.count_ways(), every_way.len() as u128);
+
+        let total = |counts: &Vec<usize>| counts.iter().sum::<usize>();
+        let fewest = every_way.iter().map(total).min();
+        let most = every_way.iter().map(total).max();
+        let min_coins = change.min_coins();
+        let max_coins = change.max_coins();
+        assert_eq!(min_coins.as_ref().map(total), fewest);
+        assert_eq!(max_coins.as_ref().map(total), most);
+        // They're actual ways to make the goal
+        for counts in min_coins.iter().chain(&max_coins) {
+            assert!(every_way.contains(counts));
+        }
+    }
+
+    #[test]
+    fn count_without_enumerating() {
+        // Far too many to go through one by one
+        let change = CoinChange::new(&[1, 2, 5, 10, 20, 50, 100, 200], 10_000);
+        assert_eq!(change.count_ways(), 1_133_873_304_647_601);
+        assert_eq!(change.min_coins().unwrap(), vec![0, 0, 0, 0, 0, 0, 0, 50]);
+        assert_eq!(
+            change.max_coins().unwrap(),
+            vec![10_000, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[rstest]
+    #[case(3, 5, 0)]
+    #[case(3, 5, 7)]
+    #[case(3, 5, 30)]
+    #[case(4, 6, 30)]
+    #[case(4, 6, 31)]
+    #[case(6, 4, 100)]
+    #[case(7, 7, 49)]
+    #[case(1, 1, 5)]
+    fn pair_matches_search(#[case] a: usize, #[case] b: usize, #[case] goal: usize) {
+        let searched = CoinChange::new(&[a, b], goal).collect_vec();
+        let pair = CoinChangePair::try_new(a, b, goal);
+        assert_eq!(pair.is_none(), searched.is_empty());
+
+        assert_eq!(pair.as_ref().map_or(0, |pair| pair.len()), searched.len());
+        let pair = pair.into_iter().flatten();
+        assert_eq!(
+            pair.clone().map(|(a, b)| vec![a, b]).collect_vec(),
+            searched
+        );
+        assert_eq!(
+            pair.clone().rev().map(|(a, b)| vec![a, b]).collect_vec(),
+            searched.iter().rev().cloned().collect_vec()
+        );
+        assert_eq!(
+            pair.clone().nth(2).map(|(a, b)| vec![a, b]),
+            searched.get(2).cloned()
+        );
+    }
+
+    #[test]
+    fn pair_with_huge_goal() {
+        let goal = 10_000_000_008_400;
+        let mut pair = CoinChangePair::try_new(94, 22, goal).unwrap();
+        assert_eq!(pair.step(), (11, 47));
+        assert_eq!(pair.len(), 9_671_179_892);
+        let (a, b) = pair.next_back().unwrap();
+        assert_eq!(a * 94 + b * 22, goal);
+        assert!(a < 11);
+        assert_eq!(
+            CoinChangePair::try_new(4, 6, 10_000_000_000_001).map(|pair| pair.len()),
+            None
+        );
+    }
 }
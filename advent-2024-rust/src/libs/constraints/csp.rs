@@ -0,0 +1,387 @@
+//! Finite-domain constraint satisfaction: every variable takes one of a set
+//! of integers, and the constraints prune those sets before and during a
+//! backtracking search.
+//!
+//! Propagation runs every constraint until none of them removes anything.
+//! `all_different` removes values already taken, `linear` keeps values that
+//! the bounds of the other variables still allow, and `predicate` checks the
+//! last unassigned variable's values once the rest are assigned.
+use std::collections::BTreeSet;
+use std::ops::Index;
+
+use super::Relation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Variable(usize);
+
+/// A value for every variable
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Assignment(Vec<i64>);
+
+impl Assignment {
+    pub fn values(&self) -> &[i64] {
+        &self.0
+    }
+}
+
+impl Index<Variable> for Assignment {
+    type Output = i64;
+
+    fn index(&self, variable: Variable) -> &i64 {
+        &self.0[variable.0]
+    }
+}
+
+type Domains = Vec<BTreeSet<i64>>;
+type Holds = Box<dyn Fn(&[i64]) -> bool>;
+
+enum Constraint {
+    AllDifferent(Vec<Variable>),
+    Linear {
+        terms: Vec<(i64, Variable)>,
+        relation: Relation,
+        bound: i64,
+    },
+    Predicate {
+        variables: Vec<Variable>,
+        holds: Holds,
+    },
+}
+
+impl Constraint {
+    /// Removes values that can't be part of a solution, returning whether
+    /// any were, or `None` if a variable has no values left
+    fn propagate(&self, domains: &mut Domains) -> Option<bool> {
+        let mut changed = false;
+        match self {
+            Constraint::AllDifferent(variables) => {
+                let mut taken = BTreeSet::new();
+                for variable in variables {
+                    if let [value] = domains[variable.0].iter().collect::<Vec<_>>()[..] {
+                        if !taken.insert(*value) {
+                            return None;
+                        }
+                    }
+                }
+                for variable in variables {
+                    let domain = &mut domains[variable.0];
+                    if domain.len() > 1 {
+                        let before = domain.len();
+                        domain.retain(|value| !taken.contains(value));
+                        changed |= domain.len() != before;
+                    }
+                }
+                // Pigeonhole: too few values between them
+                let all = variables
+                    .iter()
+                    .flat_map(|variable| &domains[variable.0])
+                    .collect::<BTreeSet<_>>();
+                if all.len() < variables.len() {
+                    return None;
+                }
+            }
+            Constraint::Linear {
+                terms,
+                relation,
+                bound,
+            } => {
+                let range = |(coefficient, variable): &(i64, Variable), domains: &Domains| {
+                    let domain = &domains[variable.0];
+                    let (first, last) = (domain.first()?, domain.last()?);
+                    let (a, b) = (coefficient * first, coefficient * last);
+                    Some((a.min(b), a.max(b)))
+                };
+                for (i, term) in terms.iter().enumerate() {
+                    // The most and least the other terms can add up to
+                    let (mut low, mut high) = (0, 0);
+                    for (j, other) in terms.iter().enumerate() {
+                        if i != j {
+                            let (min, max) = range(other, domains)?;
+                            low += min;
+                            high += max;
+                        }
+                    }
+                    let (coefficient, variable) = term;
+                    let domain = &mut domains[variable.0];
+                    let before = domain.len();
+                    domain.retain(|value| {
+                        let total = coefficient * value;
+                        match relation {
+                            Relation::LessEqual => total + low <= *bound,
+                            Relation::Equal => total + low <= *bound && total + high >= *bound,
+                            Relation::GreaterEqual => total + high >= *bound,
+                        }
+                    });
+                    changed |= domain.len() != before;
+                    if domain.is_empty() {
+                        return None;
+                    }
+                }
+            }
+            Constraint::Predicate { variables, holds } => {
+                let unassigned = variables
+                    .iter()
+                    .filter(|variable| domains[variable.0].len() != 1)
+                    .collect::<BTreeSet<_>>();
+                match unassigned.iter().collect::<Vec<_>>()[..] {
+                    [] => {
+                        let values = variables
+                            .iter()
+                            .map(|variable| *domains[variable.0].first().unwrap())
+                            .collect::<Vec<_>>();
+                        if !holds(&values) {
+                            return None;
+                        }
+                    }
+                    [last] => {
+                        let domain = domains[last.0].clone();
+                        let kept = domain
+                            .into_iter()
+                            .filter(|&value| {
+                                let values = variables
+                                    .iter()
+                                    .map(|variable| {
+                                        if variable == *last {
+                                            value
+                                        } else {
+                                            *domains[variable.0].first().unwrap()
+                                        }
+                                    })
+                                    .collect::<Vec<_>>();
+                                holds(&values)
+                            })
+                            .collect::<BTreeSet<_>>();
+                        changed = kept.len() != domains[last.0].len();
+                        if kept.is_empty() {
+                            return None;
+                        }
+                        domains[last.0] = kept;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Some(changed)
+    }
+}
+
+#[derive(Default)]
+pub struct Csp {
+    domains: Domains,
+    constraints: Vec<Constraint>,
+}
+
+impl Csp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A new variable, which can take any of `domain`
+    pub fn variable(&mut self, domain: impl IntoIterator<Item = i64>) -> Variable {
+        self.domains.push(domain.into_iter().collect());
+        Variable(self.domains.len() - 1)
+    }
+
+    /// No two of `variables` take the same value
+    pub fn all_different(&mut self, variables: &[Variable]) -> &mut Self {
+        self.constraints
+            .push(Constraint::AllDifferent(variables.to_vec()));
+        self
+    }
+
+    /// The sum of each coefficient times its variable compared to `bound`
+    pub fn linear(
+        &mut self,
+        terms: &[(i64, Variable)],
+        relation: Relation,
+        bound: i64,
+    ) -> &mut Self {
+        self.constraints.push(Constraint::Linear {
+            terms: terms.to_vec(),
+            relation,
+            bound,
+        });
+        self
+    }
+
+    /// `holds` is given the values of `variables`, in order
+    pub fn predicate(
+        &mut self,
+        variables: &[Variable],
+        holds: impl Fn(&[i64]) -> bool + 'static,
+    ) -> &mut Self {
+        self.constraints.push(Constraint::Predicate {
+            variables: variables.to_vec(),
+            holds: Box::new(holds),
+        });
+        self
+    }
+
+    /// The first solution, trying smaller values first
+    pub fn solve(&self) -> Option<Assignment> {
+        let mut solution = None;
+        self.search(self.domains.clone(), &mut |assignment| {
+            solution = Some(assignment);
+            false
+        });
+        solution
+    }
+
+    /// Every solution, in lexicographic order of the variables as they were
+    /// branched on
+    pub fn solutions(&self) -> Vec<Assignment> {
+        let mut solutions = vec![];
+        self.search(self.domains.clone(), &mut |assignment| {
+            solutions.push(assignment);
+            true
+        });
+        solutions
+    }
+
+    fn propagate(&self, domains: &mut Domains) -> bool {
+        if domains.iter().any(BTreeSet::is_empty) {
+            return false;
+        }
+        loop {
+            let mut changed = false;
+            for constraint in &self.constraints {
+                match constraint.propagate(domains) {
+                    None => return false,
+                    Some(true) => changed = true,
+                    Some(false) => {}
+                }
+            }
+            if !changed {
+                return true;
+            }
+        }
+    }
+
+    /// Passes each solution to `found` until it returns false, and returns
+    /// false if it did
+    fn search(&self, mut domains: Domains, found: &mut dyn FnMut(Assignment) -> bool) -> bool {
+        if !self.propagate(&mut domains) {
+            return true;
+        }
+        // The variable with the fewest values left fails soonest
+        let branch = (0..domains.len())
+            .filter(|&i| domains[i].len() > 1)
+            .min_by_key(|&i| domains[i].len());
+        let Some(i) = branch else {
+            let values = domains.iter().map(|domain| *domain.first().unwrap());
+            return found(Assignment(values.collect()));
+        };
+        for value in domains[i].clone() {
+            let mut next = domains.clone();
+            next[i] = BTreeSet::from([value]);
+            if !self.search(next, found) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test_csp {
+    use rstest::rstest;
+
+    use super::Csp;
+    use crate::libs::constraints::Relation;
+
+    #[test]
+    fn send_more_money() {
+        let mut csp = Csp::new();
+        let [s, e, n, d, m, o, r, y] = [1, 0, 0, 0, 1, 0, 0, 0].map(|low| csp.variable(low..=9));
+        csp.all_different(&[s, e, n, d, m, o, r, y]);
+        // SEND + MORE - MONEY = 0
+        csp.linear(
+            &[
+                (1000, s),
+                (100, e),
+                (10, n),
+                (1, d),
+                (1000, m),
+                (100, o),
+                (10, r),
+                (1, e),
+                (-10000, m),
+                (-1000, o),
+                (-100, n),
+                (-10, e),
+                (-1, y),
+            ],
+            Relation::Equal,
+            0,
+        );
+        let solutions = csp.solutions();
+        assert_eq!(solutions.len(), 1);
+        let solution = &solutions[0];
+        let word = |letters: &[_]| {
+            letters
+                .iter()
+                .fold(0, |word, &letter| word * 10 + solution[letter])
+        };
+        assert_eq!(word(&[s, e, n, d]), 9567);
+        assert_eq!(word(&[m, o, r, e]), 1085);
+        assert_eq!(word(&[m, o, n, e, y]), 10652);
+    }
+
+    #[rstest]
+    #[case(1, 1)]
+    #[case(3, 0)]
+    #[case(4, 2)]
+    #[case(6, 4)]
+    #[case(8, 92)]
+    fn queens(#[case] size: i64, #[case] count: usize) {
+        // The column of the queen on each row
+        let mut csp = Csp::new();
+        let rows = (0..size).map(|_| csp.variable(0..size)).collect::<Vec<_>>();
+        csp.all_different(&rows);
+        for (i, a) in rows.iter().enumerate() {
+            for (j, b) in rows.iter().enumerate().skip(i + 1) {
+                let apart = (j - i) as i64;
+                csp.predicate(&[*a, *b], move |columns| {
+                    (columns[0] - columns[1]).abs() != apart
+                });
+            }
+        }
+        let solutions = csp.solutions();
+        assert_eq!(solutions.len(), count);
+        assert_eq!(csp.solve(), solutions.first().cloned());
+    }
+
+    #[test]
+    fn coloring() {
+        // A triangle needs three colours
+        let triangle = |colors: i64| {
+            let mut csp = Csp::new();
+            let corners = [(); 3].map(|_| csp.variable(0..colors));
+            for (i, j) in [(0, 1), (1, 2), (0, 2)] {
+                csp.predicate(&[corners[i], corners[j]], |colors| colors[0] != colors[1]);
+            }
+            csp.solutions().len()
+        };
+        assert_eq!(triangle(2), 0);
+        assert_eq!(triangle(3), 6);
+    }
+
+    #[test]
+    fn linear_bounds() {
+        let mut csp = Csp::new();
+        let x = csp.variable(0..=10);
+        let y = csp.variable(0..=10);
+        csp.linear(&[(2, x), (3, y)], Relation::LessEqual, 12)
+            .linear(&[(1, x), (-1, y)], Relation::GreaterEqual, 3);
+        let solutions = csp.solutions();
+        assert!(solutions
+            .iter()
+            .all(|s| 2 * s[x] + 3 * s[y] <= 12 && s[x] - s[y] >= 3));
+        let brute_force = (0..=10)
+            .flat_map(|x| (0..=10).map(move |y| (x, y)))
+            .filter(|(x, y)| 2 * x + 3 * y <= 12 && x - y >= 3)
+            .count();
+        assert_eq!(solutions.len(), brute_force);
+        assert_eq!(solutions[0].values(), &[3, 0]);
+    }
+}
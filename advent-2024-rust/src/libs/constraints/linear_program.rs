@@ -0,0 +1,517 @@
+//! Linear programs by the two-phase simplex method, and integer programs by
+//! branch and bound on top of it.
+//!
+//! Every variable is non-negative. The arithmetic is exact, so there are no
+//! tolerances to tune, and Bland's rule picks the pivots, so degenerate
+//! programs can't cycle. It's meant for puzzle-sized programs, not speed.
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+use super::Relation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    Maximize,
+    Minimize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solution {
+    Infeasible,
+    /// The objective can get as good as you like. For integer programs, this
+    /// is reported when the relaxation is unbounded.
+    Unbounded,
+    Optimal {
+        value: BigRational,
+        values: Vec<BigRational>,
+    },
+}
+
+/// Branch and bound gave up. Without bounds on the variables it can go on
+/// forever, e.g. when the feasible region goes on forever but has no integer
+/// point in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BranchLimit(pub usize);
+
+impl fmt::Display for BranchLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "gave up after {} branches", self.0)
+    }
+}
+
+impl std::error::Error for BranchLimit {}
+
+#[derive(Debug, Clone)]
+struct Constraint {
+    coefficients: Vec<BigRational>,
+    relation: Relation,
+    bound: BigRational,
+}
+
+fn rational<T: Into<BigInt> + Clone>(x: &T) -> BigRational {
+    BigRational::from(x.clone().into())
+}
+
+#[derive(Debug, Clone)]
+pub struct LinearProgram {
+    goal: Goal,
+    objective: Vec<BigRational>,
+    constraints: Vec<Constraint>,
+    integers: Vec<bool>,
+    branch_limit: usize,
+}
+
+impl LinearProgram {
+    /// There's a variable for each coefficient in the objective
+    pub fn new<T: Into<BigInt> + Clone>(goal: Goal, objective: &[T]) -> Self {
+        Self {
+            goal,
+            objective: objective.iter().map(rational).collect(),
+            constraints: vec![],
+            integers: vec![false; objective.len()],
+            branch_limit: 10_000,
+        }
+    }
+
+    pub fn maximize<T: Into<BigInt> + Clone>(objective: &[T]) -> Self {
+        Self::new(Goal::Maximize, objective)
+    }
+
+    pub fn minimize<T: Into<BigInt> + Clone>(objective: &[T]) -> Self {
+        Self::new(Goal::Minimize, objective)
+    }
+
+    /// `coefficients . x` compared to `bound`
+    pub fn constrain<T: Into<BigInt> + Clone>(
+        &mut self,
+        coefficients: &[T],
+        relation: Relation,
+        bound: T,
+    ) -> &mut Self {
+        assert_eq!(
+            coefficients.len(),
+            self.objective.len(),
+            "Every variable needs a coefficient"
+        );
+        self.constraints.push(Constraint {
+            coefficients: coefficients.iter().map(rational).collect(),
+            relation,
+            bound: rational(&bound),
+        });
+        self
+    }
+
+    /// Only allow whole values for `variable`
+    pub fn integer(&mut self, variable: usize) -> &mut Self {
+        self.integers[variable] = true;
+        self
+    }
+
+    /// Only allow whole values for every variable
+    pub fn all_integers(&mut self) -> &mut Self {
+        self.integers.fill(true);
+        self
+    }
+
+    /// How many relaxations branch and bound may solve before giving up,
+    /// 10,000 by default
+    pub fn branch_limit(&mut self, limit: usize) -> &mut Self {
+        self.branch_limit = limit;
+        self
+    }
+
+    pub fn solve(&self) -> Result<Solution, BranchLimit> {
+        // Everything is maximized internally
+        let sign = match self.goal {
+            Goal::Maximize => BigRational::one(),
+            Goal::Minimize => -BigRational::one(),
+        };
+        let objective = self.objective.iter().map(|c| c * &sign).collect::<Vec<_>>();
+
+        // Depth first, each branch with the bounds it added
+        let mut best: Option<(BigRational, Vec<BigRational>)> = None;
+        let mut branches = vec![vec![]];
+        let mut solved = 0;
+        while let Some(bounds) = branches.pop() {
+            if solved == self.branch_limit {
+                return Err(BranchLimit(solved));
+            }
+            solved += 1;
+            let constraints = self
+                .constraints
+                .iter()
+                .chain(&bounds)
+                .cloned()
+                .collect::<Vec<_>>();
+            let (value, values) = match simplex(&objective, &constraints) {
+                Solution::Infeasible => continue,
+                Solution::Unbounded => return Ok(Solution::Unbounded),
+                Solution::Optimal { value, values } => (value, values),
+            };
+            // The relaxation is as good as the branch can get
+            if best.as_ref().is_some_and(|(best, _)| value <= *best) {
+                continue;
+            }
+            let fractional =
+                (0..values.len()).find(|&i| self.integers[i] && !values[i].is_integer());
+            let Some(i) = fractional else {
+                best = Some((value, values));
+                continue;
+            };
+
+            let floor = values[i].floor();
+            let mut unit = vec![BigRational::zero(); values.len()];
+            unit[i] = BigRational::one();
+            for (relation, bound) in [
+                (Relation::LessEqual, floor.clone()),
+                (Relation::GreaterEqual, floor + BigRational::one()),
+            ] {
+                // Replacing the bound it tightens keeps the tableau small
+                let mut bounds = bounds.clone();
+                bounds.retain(|bound| bound.coefficients != unit || bound.relation != relation);
+                bounds.push(Constraint {
+                    coefficients: unit.clone(),
+                    relation,
+                    bound,
+                });
+                branches.push(bounds);
+            }
+        }
+        Ok(match best {
+            Some((value, values)) => Solution::Optimal {
+                value: value * sign,
+                values,
+            },
+            None => Solution::Infeasible,
+        })
+    }
+}
+
+/// A dictionary of the constraints, with one basic variable per row, each
+/// row ending with its value
+struct Tableau {
+    rows: Vec<Vec<BigRational>>,
+    basis: Vec<usize>,
+}
+
+impl Tableau {
+    fn pivot(&mut self, row: usize, column: usize) {
+        let scale = self.rows[row][column].recip();
+        for value in self.rows[row].iter_mut() {
+            *value *= &scale;
+        }
+        let pivot_row = self.rows[row].clone();
+        for (i, other) in self.rows.iter_mut().enumerate() {
+            if i == row || other[column].is_zero() {
+                continue;
+            }
+            let factor = other[column].clone();
+            for (value, pivot) in other.iter_mut().zip(&pivot_row) {
+                *value -= &factor * pivot;
+            }
+        }
+        self.basis[row] = column;
+    }
+
+    fn value(&self, costs: &[BigRational]) -> BigRational {
+        self.rows
+            .iter()
+            .zip(&self.basis)
+            .map(|(row, &basic)| &costs[basic] * row.last().unwrap())
+            .sum()
+    }
+
+    /// Maximize `costs . x`, only letting the first `allowed` columns into
+    /// the basis. Returns false if it's unbounded.
+    fn optimize(&mut self, costs: &[BigRational], allowed: usize) -> bool {
+        loop {
+            // Bland's rule: the first column that would improve things, and
+            // the first basic variable out of the rows that limit it most
+            let reduced_cost = |column: usize| {
+                self.rows
+                    .iter()
+                    .zip(&self.basis)
+                    .fold(costs[column].clone(), |cost, (row, &basic)| {
+                        cost - &costs[basic] * &row[column]
+                    })
+            };
+            let Some(entering) = (0..allowed).find(|&column| reduced_cost(column).is_positive())
+            else {
+                return true;
+            };
+            let leaving = (0..self.rows.len())
+                .filter(|&row| self.rows[row][entering].is_positive())
+                .map(|row| {
+                    let ratio = self.rows[row].last().unwrap() / &self.rows[row][entering];
+                    (ratio, self.basis[row], row)
+                })
+                .min();
+            let Some((_, _, leaving)) = leaving else {
+                return false;
+            };
+            self.pivot(leaving, entering);
+        }
+    }
+}
+
+/// Maximize `objective . x` for `x >= 0`
+fn simplex(objective: &[BigRational], constraints: &[Constraint]) -> Solution {
+    let n = objective.len();
+    let slacks = constraints
+        .iter()
+        .filter(|constraint| constraint.relation != Relation::Equal)
+        .count();
+
+    // Non-negative bounds first, so that every row starts out feasible
+    let constraints = constraints
+        .iter()
+        .map(|constraint| {
+            if constraint.bound.is_negative() {
+                Constraint {
+                    coefficients: constraint.coefficients.iter().map(|c| -c).collect(),
+                    relation: constraint.relation.flip(),
+                    bound: -&constraint.bound,
+                }
+            } else {
+                constraint.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+    let artificials = constraints
+        .iter()
+        .filter(|constraint| constraint.relation != Relation::LessEqual)
+        .count();
+
+    // Columns are the variables, then slacks, then artificial variables,
+    // which start out basic wherever a slack can't
+    let first_artificial = n + slacks;
+    let width = first_artificial + artificials;
+    let mut tableau = Tableau {
+        rows: vec![],
+        basis: vec![],
+    };
+    let (mut slack, mut artificial) = (n, first_artificial);
+    for constraint in constraints {
+        let mut row = constraint.coefficients;
+        row.resize(width + 1, BigRational::zero());
+        row[width] = constraint.bound;
+        match constraint.relation {
+            Relation::LessEqual => {
+                row[slack] = BigRational::one();
+                tableau.basis.push(slack);
+                slack += 1;
+            }
+            Relation::GreaterEqual => {
+                row[slack] = -BigRational::one();
+                row[artificial] = BigRational::one();
+                tableau.basis.push(artificial);
+                slack += 1;
+                artificial += 1;
+            }
+            Relation::Equal => {
+                row[artificial] = BigRational::one();
+                tableau.basis.push(artificial);
+                artificial += 1;
+            }
+        }
+        tableau.rows.push(row);
+    }
+
+    if artificials > 0 {
+        // Phase one: get the artificial variables to 0, which is a feasible
+        // solution without them
+        let mut costs = vec![BigRational::zero(); width];
+        costs[first_artificial..].fill(-BigRational::one());
+        tableau.optimize(&costs, width);
+        if !tableau.value(&costs).is_zero() {
+            return Solution::Infeasible;
+        }
+        // Any left in the basis are 0, and can be swapped for a real column,
+        // unless their row is redundant
+        for row in (0..tableau.rows.len()).rev() {
+            if tableau.basis[row] < first_artificial {
+                continue;
+            }
+            match (0..first_artificial).find(|&column| !tableau.rows[row][column].is_zero()) {
+                Some(column) => tableau.pivot(row, column),
+                None => {
+                    tableau.rows.remove(row);
+                    tableau.basis.remove(row);
+                }
+            }
+        }
+    }
+
+    let mut costs = objective.to_vec();
+    costs.resize(width, BigRational::zero());
+    if !tableau.optimize(&costs, first_artificial) {
+        return Solution::Unbounded;
+    }
+    let mut values = vec![BigRational::zero(); n];
+    for (row, &basic) in tableau.rows.iter().zip(&tableau.basis) {
+        if basic < n {
+            values[basic] = row[width].clone();
+        }
+    }
+    Solution::Optimal {
+        value: tableau.value(&costs),
+        values,
+    }
+}
+
+#[cfg(test)]
+mod test_linear_program {
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+    use rstest::rstest;
+
+    use super::Goal::{self, Maximize, Minimize};
+    use super::{BranchLimit, LinearProgram, Solution};
+    use crate::libs::constraints::Relation::{Equal, GreaterEqual, LessEqual};
+
+    fn ratio(numerator: i64, denominator: i64) -> BigRational {
+        BigRational::new(numerator.into(), denominator.into())
+    }
+
+    fn optimal(value: BigRational, values: &[BigRational]) -> Solution {
+        Solution::Optimal {
+            value,
+            values: values.to_vec(),
+        }
+    }
+
+    fn whole(values: &[i64]) -> Vec<BigRational> {
+        values.iter().map(|&x| ratio(x, 1)).collect()
+    }
+
+    #[test]
+    fn maximize() {
+        let mut program = LinearProgram::maximize(&[3, 5]);
+        program
+            .constrain(&[1, 0], LessEqual, 4)
+            .constrain(&[0, 2], LessEqual, 12)
+            .constrain(&[3, 2], LessEqual, 18);
+        assert_eq!(
+            program.solve().unwrap(),
+            optimal(ratio(36, 1), &whole(&[2, 6]))
+        );
+    }
+
+    #[test]
+    fn minimize_with_lower_bounds() {
+        // The cheapest diet with enough of two nutrients
+        let mut program = LinearProgram::minimize(&[2, 3]);
+        program
+            .constrain(&[1, 2], GreaterEqual, 8)
+            .constrain(&[3, 1], GreaterEqual, 9);
+        assert_eq!(
+            program.solve().unwrap(),
+            optimal(ratio(13, 1), &whole(&[2, 3]))
+        );
+    }
+
+    #[test]
+    fn fractional_optimum() {
+        let mut program = LinearProgram::maximize(&[1, 1]);
+        program
+            .constrain(&[2, 1], LessEqual, 4)
+            .constrain(&[1, 2], LessEqual, 3);
+        assert_eq!(
+            program.solve().unwrap(),
+            optimal(ratio(7, 3), &[ratio(5, 3), ratio(2, 3)])
+        );
+        // Both corners nearby are worth 2
+        program.all_integers();
+        let Solution::Optimal { value, .. } = program.solve().unwrap() else {
+            panic!("integer points are feasible");
+        };
+        assert_eq!(value, ratio(2, 1));
+    }
+
+    #[rstest]
+    #[case::infeasible(Maximize, vec![(vec![1, 1], LessEqual, 2), (vec![1, 1], GreaterEqual, 3)], Ok(Solution::Infeasible))]
+    #[case::negative_bound(Maximize, vec![(vec![1, -1], LessEqual, -5), (vec![1, 0], GreaterEqual, 1)], Ok(Solution::Unbounded))]
+    #[case::unbounded(Maximize, vec![(vec![1, -1], LessEqual, 1)], Ok(Solution::Unbounded))]
+    #[case::redundant_equalities(
+        Maximize,
+        vec![(vec![1, 1], Equal, 4), (vec![2, 2], Equal, 8), (vec![1, 0], LessEqual, 3)],
+        Ok(optimal(ratio(4, 1), &whole(&[0, 4])))
+    )]
+    #[case::degenerate(
+        Maximize,
+        vec![(vec![1, 1], LessEqual, 0), (vec![1, 0], LessEqual, 0), (vec![0, 1], LessEqual, 0)],
+        Ok(optimal(ratio(0, 1), &whole(&[0, 0])))
+    )]
+    // x - y is always 1/2, and every branch pushes x further out
+    #[case::no_integer_point(Minimize, vec![(vec![2, -2], Equal, 1)], Err(BranchLimit(100)))]
+    fn edge_cases(
+        #[case] goal: Goal,
+        #[case] constraints: Vec<(Vec<i64>, crate::libs::constraints::Relation, i64)>,
+        #[case] expected: Result<Solution, BranchLimit>,
+    ) {
+        // Every optimum here is whole, so being integer programs only
+        // matters when there's no integer point
+        let mut program = LinearProgram::new(goal, &[0, 1]);
+        for (coefficients, relation, bound) in constraints {
+            program.constrain(&coefficients, relation, bound);
+        }
+        program.all_integers().branch_limit(100);
+        assert_eq!(program.solve(), expected);
+    }
+
+    #[test]
+    fn knapsack() {
+        let (values, weights) = ([60, 100, 120, 30], [10, 20, 30, 5]);
+        let mut program = LinearProgram::maximize(&values);
+        program.constrain(&weights, LessEqual, 50);
+        for item in 0..4 {
+            let mut unit = [0; 4];
+            unit[item] = 1;
+            program.constrain(&unit, LessEqual, 1);
+        }
+        // Half of the last item that fits is the best relaxation
+        let relaxed = program.solve().unwrap();
+        assert_eq!(
+            relaxed,
+            optimal(
+                ratio(250, 1),
+                &[ratio(1, 1), ratio(1, 1), ratio(1, 2), ratio(1, 1)]
+            )
+        );
+        program.all_integers();
+        assert_eq!(
+            program.solve().unwrap(),
+            optimal(ratio(220, 1), &whole(&[0, 1, 1, 0]))
+        );
+    }
+
+    #[rstest]
+    #[case(94, 34, 22, 67, 8400, 5400, Some(280))]
+    #[case(26, 66, 67, 21, 12748, 12176, None)]
+    #[case(17, 86, 84, 37, 7870, 6450, Some(200))]
+    #[case(26, 66, 67, 21, 10000000012748, 10000000012176, Some(459236326669))]
+    fn claw_machines(
+        #[case] ax: i64,
+        #[case] ay: i64,
+        #[case] bx: i64,
+        #[case] by: i64,
+        #[case] x: i64,
+        #[case] y: i64,
+        #[case] tokens: Option<i64>,
+    ) {
+        let mut program = LinearProgram::minimize(&[3, 1]);
+        program
+            .constrain(&[ax, bx], Equal, x)
+            .constrain(&[ay, by], Equal, y)
+            .all_integers();
+        let expected = tokens.map(|tokens| BigRational::from(BigInt::from(tokens)));
+        let value = match program.solve().unwrap() {
+            Solution::Optimal { value, .. } => Some(value),
+            _ => None,
+        };
+        assert_eq!(value, expected);
+    }
+}
@@ -0,0 +1,23 @@
+//! Solvers for puzzles that are easier to state than to solve by hand: linear
+//! and integer programs, and finite-domain constraint satisfaction.
+pub mod csp;
+pub mod linear_program;
+
+/// How the left hand side of a linear constraint compares to its bound
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Relation {
+    LessEqual,
+    Equal,
+    GreaterEqual,
+}
+
+impl Relation {
+    /// The same constraint with both sides negated
+    pub fn flip(self) -> Self {
+        match self {
+            Relation::LessEqual => Relation::GreaterEqual,
+            Relation::Equal => Relation::Equal,
+            Relation::GreaterEqual => Relation::LessEqual,
+        }
+    }
+}
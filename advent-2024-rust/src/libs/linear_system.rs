@@ -0,0 +1,501 @@
+//! Exact solutions of `A x = b`, either over the rationals by Gauss–Jordan
+//! elimination, or over the integers through the Smith normal form.
+//!
+//! Matrices are lists of rows, and nothing is ever rounded, so the answers
+//! are exact however badly conditioned the system is.
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+pub type Matrix<T> = Vec<Vec<T>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solutions<T> {
+    None,
+    Unique(Vec<T>),
+    /// Every `particular + t_1 * directions[0] + t_2 * directions[1] + ...`,
+    /// where each `t_i` is any rational, or any integer for integer systems
+    Parametric {
+        particular: Vec<T>,
+        directions: Vec<Vec<T>>,
+    },
+}
+
+impl<T> Solutions<T> {
+    fn new(particular: Vec<T>, directions: Vec<Vec<T>>) -> Self {
+        if directions.is_empty() {
+            Solutions::Unique(particular)
+        } else {
+            Solutions::Parametric {
+                particular,
+                directions,
+            }
+        }
+    }
+}
+
+/// Converts a matrix of anything that fits in a `BigInt`, e.g. to solve
+/// `i64` systems
+pub fn big_matrix<T: Into<BigInt> + Clone>(matrix: &[Vec<T>]) -> Matrix<BigInt> {
+    matrix
+        .iter()
+        .map(|row| row.iter().cloned().map(Into::into).collect())
+        .collect()
+}
+
+fn columns<T>(a: &[Vec<T>]) -> usize {
+    a.first().map_or(0, Vec::len)
+}
+
+/// Every rational `x` with `a x = b`
+pub fn solve_rational(a: &[Vec<BigRational>], b: &[BigRational]) -> Solutions<BigRational> {
+    assert_eq!(a.len(), b.len(), "Every equation needs a right hand side");
+    let n = columns(a);
+    // Augmented with `b` as the last column
+    let mut rows = a
+        .iter()
+        .zip(b)
+        .map(|(row, b)| {
+            assert_eq!(row.len(), n, "Every equation needs the same unknowns");
+            row.iter().chain([b]).cloned().collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // Reduced row echelon form, with the column of each row's leading 1
+    let mut pivots = vec![];
+    for column in 0..n {
+        let rank = pivots.len();
+        let Some(pivot) = (rank..rows.len()).find(|&i| !rows[i][column].is_zero()) else {
+            continue;
+        };
+        rows.swap(rank, pivot);
+        let scale = rows[rank][column].recip();
+        for value in rows[rank].iter_mut() {
+            *value *= &scale;
+        }
+        let pivot_row = rows[rank].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i == rank || row[column].is_zero() {
+                continue;
+            }
+            let factor = row[column].clone();
+            for (value, pivot) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+                *value -= &factor * pivot;
+            }
+        }
+        pivots.push(column);
+    }
+
+    // Everything below the pivots is now 0 = b'
+    if rows[pivots.len()..].iter().any(|row| !row[n].is_zero()) {
+        return Solutions::None;
+    }
+    let mut particular = vec![BigRational::zero(); n];
+    for (row, &column) in rows.iter().zip(&pivots) {
+        particular[column] = row[n].clone();
+    }
+    // Each free unknown moves the pivot unknowns against its column
+    let directions = (0..n)
+        .filter(|column| !pivots.contains(column))
+        .map(|free| {
+            let mut direction = vec![BigRational::zero(); n];
+            direction[free] = BigRational::one();
+            for (row, &column) in rows.iter().zip(&pivots) {
+                direction[column] = -row[free].clone();
+            }
+            direction
+        })
+        .collect();
+    Solutions::new(particular, directions)
+}
+
+fn swap_columns<T>(matrix: &mut [Vec<T>], i: usize, j: usize) {
+    for row in matrix {
+        row.swap(i, j);
+    }
+}
+
+/// `to -= factor * from`, on rows
+fn subtract_row(matrix: &mut [Vec<BigInt>], to: usize, from: usize, factor: &BigInt) {
+    for j in 0..matrix[to].len() {
+        let subtract = factor * &matrix[from][j];
+        matrix[to][j] -= subtract;
+    }
+}
+
+/// `to -= factor * from`, on columns
+fn subtract_column(matrix: &mut [Vec<BigInt>], to: usize, from: usize, factor: &BigInt) {
+    for row in matrix {
+        let subtract = factor * &row[from];
+        row[to] -= subtract;
+    }
+}
+
+fn identity(n: usize) -> Matrix<BigInt> {
+    (0..n)
+        .map(|i| (0..n).map(|j| BigInt::from((i == j) as u8)).collect())
+        .collect()
+}
+
+/// `h = u a`, where `u` is unimodular (an integer matrix with an integer
+/// inverse) and `h` is in row echelon form, with each pivot positive and the
+/// entries above it in `0..pivot`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HermiteNormalForm {
+    pub h: Matrix<BigInt>,
+    pub u: Matrix<BigInt>,
+}
+
+pub fn hermite_normal_form(a: &[Vec<BigInt>]) -> HermiteNormalForm {
+    let mut h = a.to_vec();
+    let mut u = identity(h.len());
+    let mut rank = 0;
+    for column in 0..columns(a) {
+        if rank == h.len() {
+            break;
+        }
+        // Euclid's algorithm down the column, leaving its GCD in the pivot
+        while let Some(smallest) = (rank..h.len())
+            .filter(|&i| !h[i][column].is_zero())
+            .min_by_key(|&i| h[i][column].abs())
+        {
+            h.swap(rank, smallest);
+            u.swap(rank, smallest);
+            let mut reduced = true;
+            for i in rank + 1..h.len() {
+                let quotient = &h[i][column] / &h[rank][column];
+                subtract_row(&mut h, i, rank, &quotient);
+                subtract_row(&mut u, i, rank, &quotient);
+                reduced &= h[i][column].is_zero();
+            }
+            if reduced {
+                break;
+            }
+        }
+        if h[rank][column].is_zero() {
+            continue;
+        }
+        if h[rank][column].is_negative() {
+            h[rank].iter_mut().for_each(|value| *value = -&*value);
+            u[rank].iter_mut().for_each(|value| *value = -&*value);
+        }
+        for i in 0..rank {
+            let quotient = h[i][column].div_floor(&h[rank][column]);
+            subtract_row(&mut h, i, rank, &quotient);
+            subtract_row(&mut u, i, rank, &quotient);
+        }
+        rank += 1;
+    }
+    HermiteNormalForm { h, u }
+}
+
+/// `d = u a v`, where `u` and `v` are unimodular and `d` is diagonal, with
+/// each entry dividing the next and the zeroes last
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmithNormalForm {
+    pub d: Matrix<BigInt>,
+    pub u: Matrix<BigInt>,
+    pub v: Matrix<BigInt>,
+}
+
+impl SmithNormalForm {
+    /// The non-zero entries of the diagonal
+    pub fn invariant_factors(&self) -> Vec<BigInt> {
+        (0..self.d.len().min(columns(&self.d)))
+            .map(|i| self.d[i][i].clone())
+            .take_while(|factor| !factor.is_zero())
+            .collect()
+    }
+}
+
+pub fn smith_normal_form(a: &[Vec<BigInt>]) -> SmithNormalForm {
+    let (m, n) = (a.len(), columns(a));
+    let mut d = a.to_vec();
+    let mut u = identity(m);
+    let mut v = identity(n);
+    for t in 0..m.min(n) {
+        loop {
+            // The smallest entry left becomes the pivot, and everything in
+            // its row and column is reduced modulo it. Any remainder is
+            // smaller, so becomes the next pivot.
+            let Some((i, j)) = (t..m)
+                .flat_map(|i| (t..n).map(move |j| (i, j)))
+                .filter(|&(i, j)| !d[i][j].is_zero())
+                .min_by_key(|&(i, j)| d[i][j].abs())
+            else {
+                return SmithNormalForm { d, u, v };
+            };
+            d.swap(t, i);
+            u.swap(t, i);
+            swap_columns(&mut d, t, j);
+            swap_columns(&mut v, t, j);
+
+            let mut reduced = true;
+            for i in t + 1..m {
+                let quotient = &d[i][t] / &d[t][t];
+                subtract_row(&mut d, i, t, &quotient);
+                subtract_row(&mut u, i, t, &quotient);
+                reduced &= d[i][t].is_zero();
+            }
+            for j in t + 1..n {
+                let quotient = &d[t][j] / &d[t][t];
+                subtract_column(&mut d, j, t, &quotient);
+                subtract_column(&mut v, j, t, &quotient);
+                reduced &= d[t][j].is_zero();
+            }
+            if !reduced {
+                continue;
+            }
+
+            // The pivot has to divide everything after it too. If it
+            // doesn't, bringing that row in leaves a remainder in this one.
+            let indivisible =
+                (t + 1..m).find(|&i| (t + 1..n).any(|j| !d[i][j].is_multiple_of(&d[t][t])));
+            match indivisible {
+                Some(i) => {
+                    subtract_row(&mut d, t, i, &-BigInt::one());
+                    subtract_row(&mut u, t, i, &-BigInt::one());
+                }
+                None => break,
+            }
+        }
+        if d[t][t].is_negative() {
+            d[t].iter_mut().for_each(|value| *value = -&*value);
+            u[t].iter_mut().for_each(|value| *value = -&*value);
+        }
+    }
+    SmithNormalForm { d, u, v }
+}
+
+/// Every integer `x` with `a x = b`. With `d = u a v`, that's `d y = u b`
+/// where `x = v y`, and `d` is diagonal, so each `y_i` is found on its own.
+/// The directions are a basis, so every solution is reached by exactly one
+/// combination of integer multiples of them.
+pub fn solve_integer(a: &[Vec<BigInt>], b: &[BigInt]) -> Solutions<BigInt> {
+    assert_eq!(a.len(), b.len(), "Every equation needs a right hand side");
+    let smith = smith_normal_form(a);
+    let factors = smith.invariant_factors();
+    let ub = smith
+        .u
+        .iter()
+        .map(|row| row.iter().zip(b).map(|(u, b)| u * b).sum::<BigInt>())
+        .collect::<Vec<_>>();
+
+    let mut y = vec![BigInt::zero(); columns(a)];
+    for (i, ub) in ub.iter().enumerate() {
+        match factors.get(i) {
+            Some(factor) if ub.is_multiple_of(factor) => y[i] = ub / factor,
+            None if ub.is_zero() => {}
+            _ => return Solutions::None,
+        }
+    }
+
+    let v = &smith.v;
+    let particular = v
+        .iter()
+        .map(|row| row.iter().zip(&y).map(|(v, y)| v * y).sum())
+        .collect();
+    let directions = (factors.len()..columns(a))
+        .map(|j| v.iter().map(|row| row[j].clone()).collect())
+        .collect();
+    Solutions::new(particular, directions)
+}
+
+#[cfg(test)]
+mod test_linear_system {
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+    use num_traits::{One, Zero};
+    use rstest::rstest;
+
+    use super::{
+        big_matrix, hermite_normal_form, smith_normal_form, solve_integer, solve_rational, Matrix,
+        Solutions,
+    };
+
+    fn multiply(a: &[Vec<BigInt>], b: &[Vec<BigInt>]) -> Matrix<BigInt> {
+        a.iter()
+            .map(|row| {
+                (0..b[0].len())
+                    .map(|j| row.iter().zip(b).map(|(x, b)| x * &b[j]).sum())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn apply<T: Clone + Zero + std::ops::Mul<Output = T>>(a: &[Vec<T>], x: &[T]) -> Vec<T> {
+        a.iter()
+            .map(|row| {
+                row.iter()
+                    .zip(x)
+                    .fold(T::zero(), |sum, (a, x)| sum + a.clone() * x.clone())
+            })
+            .collect()
+    }
+
+    fn rational(matrix: &[Vec<i64>]) -> Matrix<BigRational> {
+        big_matrix(matrix)
+            .into_iter()
+            .map(|row| row.into_iter().map(BigRational::from).collect())
+            .collect()
+    }
+
+    /// Every solution found, stepped along each direction a bit, solves it
+    fn assert_solves<T>(a: &[Vec<T>], b: &[T], solutions: &Solutions<T>)
+    where
+        T: Clone + Zero + One + PartialEq + std::fmt::Debug + std::ops::Mul<Output = T>,
+    {
+        let (particular, directions) = match solutions {
+            Solutions::None => return,
+            Solutions::Unique(x) => (x, &vec![]),
+            Solutions::Parametric {
+                particular,
+                directions,
+            } => (particular, directions),
+        };
+        assert_eq!(apply(a, particular), b);
+        for direction in directions {
+            assert!(direction.iter().any(|x| !x.is_zero()));
+            let moved = particular
+                .iter()
+                .zip(direction)
+                .map(|(x, d)| x.clone() + (T::one() + T::one()) * d.clone())
+                .collect::<Vec<_>>();
+            assert_eq!(apply(a, &moved), b);
+        }
+    }
+
+    #[rstest]
+    #[case::unique(vec![vec![2, 1, -1], vec![-3, -1, 2], vec![-2, 1, 2]], vec![8, -11, -3], Some(0))]
+    #[case::fractional(vec![vec![2, 0], vec![0, 3]], vec![1, 1], Some(0))]
+    #[case::line(vec![vec![1, 2, 3], vec![2, 4, 7]], vec![1, 3], Some(1))]
+    #[case::plane(vec![vec![1, 1, 1]], vec![6], Some(2))]
+    #[case::overdetermined(vec![vec![1, 0], vec![0, 1], vec![1, 1]], vec![1, 2, 3], Some(0))]
+    #[case::inconsistent(vec![vec![1, 1], vec![2, 2]], vec![1, 3], None)]
+    fn rational_solutions(
+        #[case] a: Vec<Vec<i64>>,
+        #[case] b: Vec<i64>,
+        #[case] free: Option<usize>,
+    ) {
+        let (a, b) = (rational(&a), rational(&[b]).remove(0));
+        let solutions = solve_rational(&a, &b);
+        let found = match &solutions {
+            Solutions::None => None,
+            Solutions::Unique(_) => Some(0),
+            Solutions::Parametric { directions, .. } => Some(directions.len()),
+        };
+        assert_eq!(found, free);
+        assert_solves(&a, &b, &solutions);
+    }
+
+    #[test]
+    fn rational_unique_values() {
+        let a = rational(&[vec![2, 0], vec![0, 3]]);
+        let b = rational(&[vec![1, 1]]).remove(0);
+        let half = BigRational::new(1.into(), 2.into());
+        let third = BigRational::new(1.into(), 3.into());
+        assert_eq!(solve_rational(&a, &b), Solutions::Unique(vec![half, third]));
+    }
+
+    #[test]
+    fn hermite() {
+        let a = big_matrix(&[vec![2, 3, 6, 2], vec![5, 6, 1, 6], vec![8, 3, 1, 1]]);
+        let hermite = hermite_normal_form(&a);
+        assert_eq!(
+            hermite.h,
+            big_matrix(&[vec![1, 0, 50, -11], vec![0, 3, 28, -2], vec![0, 0, 61, -13]])
+        );
+        assert_eq!(multiply(&hermite.u, &a), hermite.h);
+    }
+
+    #[rstest]
+    #[case(vec![vec![2, 4, 4], vec![-6, 6, 12], vec![10, -4, -16]], vec![2, 6, 12])]
+    #[case(vec![vec![6, 4], vec![4, 6]], vec![2, 10])]
+    #[case(vec![vec![2, 4, 6], vec![4, 8, 12]], vec![2])]
+    #[case(vec![vec![0, 0], vec![0, 0]], vec![])]
+    #[case(vec![vec![3], vec![5], vec![7]], vec![1])]
+    fn smith(#[case] a: Vec<Vec<i64>>, #[case] factors: Vec<i64>) {
+        let a = big_matrix(&a);
+        let smith = smith_normal_form(&a);
+        assert_eq!(smith.invariant_factors(), big_matrix(&[factors]).remove(0));
+        assert_eq!(multiply(&multiply(&smith.u, &a), &smith.v), smith.d);
+        for (i, row) in smith.d.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                assert!(i == j || value.is_zero());
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::claw_machine(vec![vec![94, 22], vec![34, 67]], vec![8400, 5400], Some(0))]
+    #[case::no_integers(vec![vec![2, 4]], vec![7], None)]
+    #[case::diophantine(vec![vec![3, 5]], vec![7], Some(1))]
+    #[case::rational_only(vec![vec![2, 0], vec![0, 3]], vec![1, 1], None)]
+    #[case::lattice(vec![vec![1, 2, 3], vec![4, 5, 6]], vec![6, 15], Some(1))]
+    #[case::redundant(vec![vec![6, 4], vec![3, 2]], vec![10, 5], Some(1))]
+    #[case::inconsistent(vec![vec![6, 4], vec![3, 2]], vec![10, 6], None)]
+    fn integer_solutions(
+        #[case] a: Vec<Vec<i64>>,
+        #[case] b: Vec<i64>,
+        #[case] free: Option<usize>,
+    ) {
+        let (a, b) = (big_matrix(&a), big_matrix(&[b]).remove(0));
+        let solutions = solve_integer(&a, &b);
+        let found = match &solutions {
+            Solutions::None => None,
+            Solutions::Unique(_) => Some(0),
+            Solutions::Parametric { directions, .. } => Some(directions.len()),
+        };
+        assert_eq!(found, free);
+        assert_solves(&a, &b, &solutions);
+    }
+
+    #[test]
+    fn claw_machine_presses() {
+        let a = big_matrix(&[vec![94, 22], vec![34, 67]]);
+        let b = big_matrix(&[vec![8400, 5400]]).remove(0);
+        assert_eq!(
+            solve_integer(&a, &b),
+            Solutions::Unique(big_matrix(&[vec![80, 40]]).remove(0))
+        );
+    }
+
+    #[test]
+    fn hailstone_rock() {
+        // 2023 day 24: a rock at r moving at rv hits every hailstone. For
+        // each pair of axes, (p - r) x (v - rv) = 0, and its only non-linear
+        // term is the same for every hailstone, so differences are linear.
+        let hailstones = [
+            ([19, 13, 30], [-2, 1, -2]),
+            ([18, 19, 22], [-1, -1, -2]),
+            ([20, 25, 34], [-2, -2, -4]),
+            ([12, 31, 28], [-1, -2, -1]),
+            ([20, 19, 15], [1, -5, -3]),
+        ];
+        let (mut a, mut b) = (vec![], vec![]);
+        let (pi, vi) = hailstones[0];
+        for (pj, vj) in &hailstones[1..] {
+            for (s, t) in [(0, 1), (0, 2), (1, 2)] {
+                let mut row = vec![0_i64; 6];
+                row[s] = vj[t] - vi[t];
+                row[t] = vi[s] - vj[s];
+                row[3 + s] = pi[t] - pj[t];
+                row[3 + t] = pj[s] - pi[s];
+                a.push(row);
+                b.push((pi[t] * vi[s] - pi[s] * vi[t]) - (pj[t] * vj[s] - pj[s] * vj[t]));
+            }
+        }
+        let (a, b) = (big_matrix(&a), big_matrix(&[b]).remove(0));
+        let rock = big_matrix(&[vec![24, 13, 10, -3, 1, 2]]).remove(0);
+        assert_eq!(solve_integer(&a, &b), Solutions::Unique(rock.clone()));
+
+        let a = a
+            .into_iter()
+            .map(|row| row.into_iter().map(BigRational::from).collect())
+            .collect::<Vec<_>>();
+        let b = b.into_iter().map(BigRational::from).collect::<Vec<_>>();
+        let rock = rock.into_iter().map(BigRational::from).collect();
+        assert_eq!(solve_rational(&a, &b), Solutions::Unique(rock));
+    }
+}
@@ -1,6 +1,9 @@
 pub mod coin_change;
+pub mod constraints;
 pub mod deprecated_points;
 pub mod direction;
 pub mod disjoint_set;
+pub mod linear_system;
+pub mod number_theory;
 pub mod point;
 pub mod zipper;
@@ -0,0 +1,343 @@
+//! Modular arithmetic, primes and factorization, for primitive integers as
+//! well as `BigInt` and `BigUint`.
+//!
+//! Products are calculated in the integer type itself before being reduced,
+//! so the modulus squared has to fit in it. E.g. use `u128` for moduli up to
+//! `u64::MAX`, or a big integer beyond that.
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use num_integer::{Integer, Roots};
+
+pub trait ModInteger: Integer + Clone + From<u8> {}
+impl<T: Integer + Clone + From<u8>> ModInteger for T {}
+
+/// Miller–Rabin with these as witnesses is exact below 3.3 * 10^24, and
+/// they're tried as factors before anything else
+const SMALL_PRIMES: [u8; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+/// `base` to the power of `exponent`, modulo `modulus`, by repeated squaring
+pub fn mod_pow<T: ModInteger>(base: &T, exponent: &T, modulus: &T) -> T {
+    assert!(exponent >= &T::zero(), "Exponents can't be negative");
+    let two = T::from(2);
+    let mut result = T::one().mod_floor(modulus);
+    let mut base = base.mod_floor(modulus);
+    let mut exponent = exponent.clone();
+    while !exponent.is_zero() {
+        if exponent.is_odd() {
+            result = (result * base.clone()).mod_floor(modulus);
+        }
+        base = (base.clone() * base).mod_floor(modulus);
+        exponent = exponent / two.clone();
+    }
+    result
+}
+
+/// `x` where `a * x = 1 (mod modulus)`, or `None` unless `a` and `modulus`
+/// are coprime
+pub fn mod_inverse<T: ModInteger>(a: &T, modulus: &T) -> Option<T> {
+    // Extended Euclid, where each remainder is some multiple `s` of `a`. Only
+    // `s` modulo `modulus` matters, so it's kept reduced, which also keeps it
+    // from going negative for unsigned types.
+    let (mut old_r, mut r) = (a.mod_floor(modulus), modulus.clone());
+    let (mut old_s, mut s) = (T::one(), T::zero());
+    while !r.is_zero() {
+        let (quotient, remainder) = old_r.div_rem(&r);
+        old_r = std::mem::replace(&mut r, remainder);
+        let product = (quotient * s.clone()).mod_floor(modulus);
+        let next_s = (old_s + modulus.clone() - product).mod_floor(modulus);
+        old_s = std::mem::replace(&mut s, next_s);
+    }
+    old_r.is_one().then(|| old_s.mod_floor(modulus))
+}
+
+/// Every prime up to and including `limit`, by the sieve of Eratosthenes
+pub fn sieve(limit: usize) -> Vec<usize> {
+    let mut composite = vec![false; limit + 1];
+    let mut primes = vec![];
+    for n in 2..=limit {
+        if composite[n] {
+            continue;
+        }
+        primes.push(n);
+        // Smaller multiples have a smaller prime factor, so are already out
+        for multiple in (n.saturating_mul(n)..=limit).step_by(n) {
+            composite[multiple] = true;
+        }
+    }
+    primes
+}
+
+/// Miller–Rabin, which is deterministic below 3.3 * 10^24 and almost
+/// certainly right above it
+pub fn is_prime<T: ModInteger>(n: &T) -> bool {
+    if n < &T::from(2) {
+        return false;
+    }
+    for prime in SMALL_PRIMES.map(T::from) {
+        if n == &prime {
+            return true;
+        }
+        if n.is_multiple_of(&prime) {
+            return false;
+        }
+    }
+
+    // n - 1 = d * 2^s, with d odd
+    let n_minus_one = n.clone() - T::one();
+    let mut d = n_minus_one.clone();
+    let mut s = 0;
+    while d.is_even() {
+        d = d / T::from(2);
+        s += 1;
+    }
+    'witnesses: for witness in SMALL_PRIMES.map(T::from) {
+        let mut x = mod_pow(&witness, &d, n);
+        if x.is_one() || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = (x.clone() * x).mod_floor(n);
+            if x == n_minus_one {
+                continue 'witnesses;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// A factor of the composite `n` other than 1 and `n`, by Pollard's rho
+fn pollard_rho<T: ModInteger>(n: &T) -> T {
+    if n.is_even() {
+        return T::from(2);
+    }
+    let mut c = T::one();
+    loop {
+        // Tortoise and hare on x^2 + c, which cycles sooner modulo a factor
+        // than modulo `n`
+        let step = |x: &T| (x.clone() * x.clone() + c.clone()).mod_floor(n);
+        let (mut tortoise, mut hare) = (T::from(2), T::from(2));
+        let mut factor = T::one();
+        while factor.is_one() {
+            tortoise = step(&tortoise);
+            hare = step(&step(&hare));
+            let distance = if tortoise > hare {
+                tortoise.clone() - hare.clone()
+            } else {
+                hare.clone() - tortoise.clone()
+            };
+            factor = distance.gcd(n);
+        }
+        if &factor != n {
+            return factor;
+        }
+        // Both cycled together, so try another polynomial
+        c = c + T::one();
+    }
+}
+
+/// The prime factors of `n`, smallest first and repeated as many times as
+/// they divide it. 1 has none.
+pub fn factorize<T: ModInteger>(n: &T) -> Vec<T> {
+    assert!(n > &T::zero(), "Only positive numbers can be factorized");
+    let mut factors = vec![];
+    let mut rest = n.clone();
+    for prime in SMALL_PRIMES.map(T::from) {
+        while rest.is_multiple_of(&prime) {
+            rest = rest / prime.clone();
+            factors.push(prime.clone());
+        }
+    }
+
+    let mut unfactored = if rest.is_one() { vec![] } else { vec![rest] };
+    while let Some(m) = unfactored.pop() {
+        if is_prime(&m) {
+            factors.push(m);
+        } else {
+            let factor = pollard_rho(&m);
+            unfactored.push(m / factor.clone());
+            unfactored.push(factor);
+        }
+    }
+    factors.sort();
+    factors
+}
+
+/// Euler's totient: how many of `1..=n` are coprime to `n`
+pub fn totient<T: ModInteger>(n: &T) -> T {
+    let mut primes = factorize(n);
+    primes.dedup();
+    primes.into_iter().fold(n.clone(), |result, prime| {
+        result / prime.clone() * (prime - T::one())
+    })
+}
+
+/// The smallest `x >= 0` where `base^x = target (mod modulus)`, by baby-step
+/// giant-step. `base` and `modulus` don't need to be coprime.
+pub fn discrete_log<T: ModInteger + Hash + Roots>(base: &T, target: &T, modulus: &T) -> Option<T> {
+    let mut modulus = modulus.clone();
+    let base = base.mod_floor(&modulus);
+    let mut target = target.mod_floor(&modulus);
+
+    // Whatever `base` shares with the modulus has to be divided out first, as
+    // it can't be inverted. That leaves `coefficient * base^y = target`,
+    // where x = y + `offset`
+    let mut coefficient = T::one().mod_floor(&modulus);
+    let mut offset = T::zero();
+    loop {
+        let gcd = base.gcd(&modulus);
+        if gcd.is_one() {
+            break;
+        }
+        if target == coefficient {
+            return Some(offset);
+        }
+        if !target.is_multiple_of(&gcd) {
+            return None;
+        }
+        target = target / gcd.clone();
+        modulus = modulus / gcd.clone();
+        coefficient = (coefficient * (base.clone() / gcd)).mod_floor(&modulus);
+        offset = offset + T::one();
+    }
+
+    // x = giant * `size` - baby. Later babies overwrite earlier ones, so
+    // each match is the smallest x for its giant step.
+    let size = modulus.sqrt() + T::one();
+    let mut babies = HashMap::new();
+    let mut baby = T::zero();
+    let mut value = target.mod_floor(&modulus);
+    while baby <= size {
+        babies.insert(value.clone(), baby.clone());
+        value = (value * base.clone()).mod_floor(&modulus);
+        baby = baby + T::one();
+    }
+
+    let giant_step = mod_pow(&base, &size, &modulus);
+    let mut value = coefficient;
+    let mut giant = T::one();
+    while giant <= size {
+        value = (value * giant_step.clone()).mod_floor(&modulus);
+        if let Some(baby) = babies.get(&value) {
+            return Some(giant * size.clone() - baby.clone() + offset);
+        }
+        giant = giant + T::one();
+    }
+    None
+}
+
+#[cfg(test)]
+mod test_number_theory {
+    use num_bigint::BigUint;
+    use rstest::rstest;
+
+    use super::{discrete_log, factorize, is_prime, mod_inverse, mod_pow, sieve, totient};
+
+    #[test]
+    fn powers() {
+        assert_eq!(mod_pow(&2, &10, &1000), 24);
+        assert_eq!(mod_pow(&-3_i64, &3, &10), 3);
+        assert_eq!(mod_pow(&5, &0, &1), 0);
+        // Fermat's little theorem for a prime too big to square in a u64
+        let prime = 18_446_744_073_709_551_557_u128;
+        assert_eq!(mod_pow(&123_456_789, &(prime - 1), &prime), 1);
+
+        let big = BigUint::from(2_u8).pow(521) - 1_u8;
+        assert_eq!(
+            mod_pow(&BigUint::from(3_u8), &(big.clone() - 1_u8), &big),
+            BigUint::from(1_u8)
+        );
+    }
+
+    #[rstest]
+    #[case(3, 11, Some(4))]
+    #[case(10, 17, Some(12))]
+    #[case(-1, 7, Some(6))]
+    #[case(6, 9, None)]
+    #[case(0, 5, None)]
+    fn inverses(#[case] a: i64, #[case] modulus: i64, #[case] expected: Option<i64>) {
+        assert_eq!(mod_inverse(&a, &modulus), expected);
+        if a >= 0 {
+            assert_eq!(
+                mod_inverse(&(a as u64), &(modulus as u64)),
+                expected.map(|x| x as u64)
+            );
+        }
+    }
+
+    #[test]
+    fn primes() {
+        assert_eq!(sieve(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert_eq!(sieve(1), Vec::<usize>::new());
+        assert_eq!(sieve(100_000).len(), 9592);
+
+        let primes = sieve(10_000);
+        for n in 0..=10_000_u64 {
+            assert_eq!(
+                is_prime(&n),
+                primes.binary_search(&(n as usize)).is_ok(),
+                "{n}"
+            );
+        }
+        // Carmichael numbers fool Fermat but not Miller–Rabin
+        for carmichael in [561_u64, 41041, 825265, 321197185] {
+            assert!(!is_prime(&carmichael));
+        }
+        assert!(is_prime(&1_000_000_007_u64));
+        assert!(is_prime(&((1_u128 << 61) - 1)));
+        assert!(is_prime(&(BigUint::from(2_u8).pow(127) - 1_u8)));
+        assert!(!is_prime(&(BigUint::from(2_u8).pow(128) + 1_u8)));
+    }
+
+    #[rstest]
+    #[case::one(1, vec![])]
+    #[case::prime(1_000_000_007, vec![1_000_000_007])]
+    #[case::project_euler(600_851_475_143, vec![71, 839, 1471, 6857])]
+    #[case::square(1_000_000_014_000_000_049, vec![1_000_000_007, 1_000_000_007])]
+    #[case::semiprime(
+        999_999_999_989 * 1_000_003,
+        vec![1_000_003, 999_999_999_989]
+    )]
+    #[case::smooth(2 * 2 * 3 * 41 * 41 * 43, vec![2, 2, 3, 41, 41, 43])]
+    fn factors(#[case] n: u128, #[case] expected: Vec<u128>) {
+        assert_eq!(factorize(&n), expected);
+    }
+
+    #[rstest]
+    #[case(1, 1)]
+    #[case(9, 6)]
+    #[case(36, 12)]
+    #[case(97, 96)]
+    #[case(1_000_000, 400_000)]
+    fn totients(#[case] n: u64, #[case] expected: u64) {
+        assert_eq!(totient(&n), expected);
+        if n < 100 {
+            let coprime = (1..=n).filter(|k| num_integer::gcd(*k, n) == 1).count();
+            assert_eq!(coprime as u64, expected);
+        }
+    }
+
+    #[rstest]
+    fn logs_match_brute_force(
+        #[values(2, 3, 6, 10)] base: u64,
+        #[values(1, 8, 17, 24, 100)] modulus: u64,
+    ) {
+        for target in 0..modulus {
+            // Powers cycle within `modulus` steps, after at most as many again
+            let expected = (0..2 * modulus).find(|x| mod_pow(&base, x, &modulus) == target);
+            assert_eq!(
+                discrete_log(&base, &target, &modulus),
+                expected,
+                "{base}^x = {target}"
+            );
+        }
+    }
+
+    #[test]
+    fn big_log() {
+        let modulus = 1_000_000_007_u128;
+        let x = discrete_log(&5, &123_456_789, &modulus).unwrap();
+        assert_eq!(mod_pow(&5, &x, &modulus), 123_456_789);
+    }
+}
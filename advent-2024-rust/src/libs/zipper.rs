@@ -76,7 +76,7 @@ where
 
 #[cfg(test)]
 mod test_zipper {
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
 
     use itertools::Either;
 
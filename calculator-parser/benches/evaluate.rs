@@ -0,0 +1,44 @@
+//! Evaluating one formula over many bindings, walking the tree against
+//! running the compiled bytecode
+use std::collections::HashMap;
+
+use calculator_parser::bytecode::{Program, Vm};
+use calculator_parser::{Parsed, TokenList};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const FORMULA: &str = "3 * x ^ 2 - (4 + 6) * x / y + 2 ^ 3";
+const POINTS: usize = 1000;
+
+fn evaluate(c: &mut Criterion) {
+    let parsed = Parsed::new(TokenList::new(FORMULA)).unwrap();
+    let program = Program::compile(parsed.tree());
+
+    let mut group = c.benchmark_group("evaluate");
+    group.bench_function("tree_walk", |b| {
+        let mut bindings = HashMap::from([("x", 0.0), ("y", 1.0)]);
+        b.iter(|| {
+            let mut sum = 0.0;
+            for i in 0..POINTS {
+                bindings.insert("x", i as f64);
+                sum += parsed.tree().evaluate(&bindings).unwrap();
+            }
+            black_box(sum)
+        })
+    });
+    group.bench_function("bytecode", |b| {
+        let mut vm = Vm::default();
+        let mut slots = [0.0, 1.0];
+        b.iter(|| {
+            let mut sum = 0.0;
+            for i in 0..POINTS {
+                slots[0] = i as f64;
+                sum += vm.run(&program, &slots).unwrap();
+            }
+            black_box(sum)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, evaluate);
+criterion_main!(benches);
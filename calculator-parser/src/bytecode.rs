@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Expression;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /// Push a constant onto the stack
+    Push(f64),
+    /// Push the value bound to the variable in this slot
+    Load(usize),
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+impl Instruction {
+    fn binary(expression: &Expression) -> Option<Self> {
+        match expression {
+            Expression::Add(..) => Some(Instruction::Add),
+            Expression::Subtract(..) => Some(Instruction::Subtract),
+            Expression::Multiply(..) => Some(Instruction::Multiply),
+            Expression::Divide(..) => Some(Instruction::Divide),
+            Expression::Power(..) => Some(Instruction::Power),
+            Expression::U32(_) | Expression::Variable(_) => None,
+        }
+    }
+
+    #[inline(always)]
+    fn apply(&self, lhs: f64, rhs: f64) -> f64 {
+        match self {
+            Instruction::Add => lhs + rhs,
+            Instruction::Subtract => lhs - rhs,
+            Instruction::Multiply => lhs * rhs,
+            Instruction::Divide => lhs / rhs,
+            Instruction::Power => lhs.powf(rhs),
+            Instruction::Push(_) | Instruction::Load(_) => {
+                unreachable!("{:?} is not a binary operator", self)
+            }
+        }
+    }
+}
+
+/// An [`Expression`] compiled to instructions for a stack machine. Variables
+/// are resolved to slots at compile time so evaluating the program for many
+/// different bindings never looks up names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program<'parse> {
+    instructions: Vec<Instruction>,
+    /// Variable names indexed by slot, in order of first appearance
+    variables: Vec<&'parse str>,
+    max_stack: usize,
+}
+
+impl<'parse> Program<'parse> {
+    pub fn compile(expression: &Expression<'parse>) -> Self {
+        let mut program = Self {
+            instructions: vec![],
+            variables: vec![],
+            max_stack: 0,
+        };
+        program.emit(expression, 0);
+        program
+    }
+
+    /// Emit the instructions for `expression`, given `depth` values are
+    /// already on the stack underneath it.
+    fn emit(&mut self, expression: &Expression<'parse>, depth: usize) {
+        self.max_stack = self.max_stack.max(depth + 1);
+
+        let (lhs, rhs) = match expression {
+            Expression::U32(value) => {
+                self.instructions.push(Instruction::Push(*value as f64));
+                return;
+            }
+            Expression::Variable(name) => {
+                let slot = self.slot(name);
+                self.instructions.push(Instruction::Load(slot));
+                return;
+            }
+            Expression::Add(lhs, rhs)
+            | Expression::Subtract(lhs, rhs)
+            | Expression::Multiply(lhs, rhs)
+            | Expression::Divide(lhs, rhs)
+            | Expression::Power(lhs, rhs) => (lhs, rhs),
+        };
+        let operator = Instruction::binary(expression).unwrap();
+
+        let start = self.instructions.len();
+        self.emit(lhs, depth);
+        let middle = self.instructions.len();
+        self.emit(rhs, depth + 1);
+
+        // Constant folding: both operands compiled down to a single constant
+        match self.instructions[start..] {
+            [Instruction::Push(a), Instruction::Push(b)] if middle == start + 1 => {
+                self.instructions.truncate(start);
+                self.instructions
+                    .push(Instruction::Push(operator.apply(a, b)));
+            }
+            _ => self.instructions.push(operator),
+        }
+    }
+
+    fn slot(&mut self, name: &'parse str) -> usize {
+        match self.variables.iter().position(|existing| *existing == name) {
+            Some(slot) => slot,
+            None => {
+                self.variables.push(name);
+                self.variables.len() - 1
+            }
+        }
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn variables(&self) -> &[&'parse str] {
+        &self.variables
+    }
+
+    /// Order named bindings by slot so they can be passed to [`Vm::run`]
+    pub fn bind(&self, bindings: &HashMap<&str, f64>) -> anyhow::Result<Vec<f64>> {
+        self.variables
+            .iter()
+            .map(|name| {
+                bindings
+                    .get(name)
+                    .copied()
+                    .ok_or_else(|| anyhow::anyhow!("Unbound variable {:?}", name))
+            })
+            .collect()
+    }
+}
+
+/// Disassembly, one instruction per line
+impl fmt::Display for Program<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, instruction) in self.instructions.iter().enumerate() {
+            write!(f, "{:04}  ", i)?;
+            match instruction {
+                Instruction::Push(value) => writeln!(f, "push  {}", value)?,
+                Instruction::Load(slot) => {
+                    writeln!(f, "load  {} ; {}", slot, self.variables[*slot])?
+                }
+                Instruction::Add => writeln!(f, "add")?,
+                Instruction::Subtract => writeln!(f, "sub")?,
+                Instruction::Multiply => writeln!(f, "mul")?,
+                Instruction::Divide => writeln!(f, "div")?,
+                Instruction::Power => writeln!(f, "pow")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reusable evaluation stack so repeated runs do not allocate
+#[derive(Debug, Default)]
+pub struct Vm {
+    stack: Vec<f64>,
+}
+
+impl Vm {
+    pub fn run(&mut self, program: &Program, slots: &[f64]) -> anyhow::Result<f64> {
+        if slots.len() != program.variables.len() {
+            return Err(anyhow::anyhow!(
+                "Expected {} bound variables, got {}",
+                program.variables.len(),
+                slots.len()
+            ));
+        }
+
+        self.stack.clear();
+        self.stack.reserve(program.max_stack);
+        for instruction in &program.instructions {
+            match instruction {
+                Instruction::Push(value) => self.stack.push(*value),
+                Instruction::Load(slot) => self.stack.push(slots[*slot]),
+                operator => {
+                    let rhs = self.stack.pop().expect("Stack underflow");
+                    let lhs = self.stack.last_mut().expect("Stack underflow");
+                    *lhs = operator.apply(*lhs, rhs);
+                }
+            }
+        }
+
+        assert_eq!(
+            self.stack.len(),
+            1,
+            "Program should leave exactly one value"
+        );
+        Ok(self.stack[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Instruction, Program, Vm};
+    use crate::Expression;
+
+    fn node(expression: Expression<'static>) -> Box<Expression<'static>> {
+        Box::new(expression)
+    }
+
+    /// 3 * x ^ 2 - (4 + 6) * x / y + 2 ^ 3
+    fn polynomial() -> Box<Expression<'static>> {
+        let x = || node(Expression::Variable("x"));
+        let square = node(Expression::Power(x(), node(Expression::U32(2))));
+        let quadratic = node(Expression::Multiply(node(Expression::U32(3)), square));
+        let ten = node(Expression::Add(
+            node(Expression::U32(4)),
+            node(Expression::U32(6)),
+        ));
+        let linear = node(Expression::Divide(
+            node(Expression::Multiply(ten, x())),
+            node(Expression::Variable("y")),
+        ));
+        let constant = node(Expression::Power(
+            node(Expression::U32(2)),
+            node(Expression::U32(3)),
+        ));
+        node(Expression::Add(
+            node(Expression::Subtract(quadratic, linear)),
+            constant,
+        ))
+    }
+
+    #[test]
+    fn folds_constant_subtrees() {
+        let program = Program::compile(&polynomial());
+        assert_eq!(
+            program.instructions(),
+            &[
+                Instruction::Push(3.0),
+                Instruction::Load(0),
+                Instruction::Push(2.0),
+                Instruction::Power,
+                Instruction::Multiply,
+                Instruction::Push(10.0),
+                Instruction::Load(0),
+                Instruction::Multiply,
+                Instruction::Load(1),
+                Instruction::Divide,
+                Instruction::Subtract,
+                Instruction::Push(8.0),
+                Instruction::Add,
+            ]
+        );
+        assert_eq!(program.variables(), &["x", "y"]);
+    }
+
+    #[test]
+    fn fully_constant_expression_is_one_push() {
+        let expression = node(Expression::Multiply(
+            node(Expression::Add(
+                node(Expression::U32(1)),
+                node(Expression::U32(2)),
+            )),
+            node(Expression::U32(7)),
+        ));
+        let program = Program::compile(&expression);
+        assert_eq!(program.instructions(), &[Instruction::Push(21.0)]);
+    }
+
+    #[test]
+    fn matches_tree_walk() {
+        let expression = polynomial();
+        let program = Program::compile(&expression);
+        let mut vm = Vm::default();
+        for x in -5..=5 {
+            for y in [1.0, 2.5, -3.0] {
+                let bindings = HashMap::from([("x", x as f64), ("y", y)]);
+                let expected = expression.evaluate(&bindings).unwrap();
+                let slots = program.bind(&bindings).unwrap();
+                assert_eq!(vm.run(&program, &slots).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn unbound_variable() {
+        let program = Program::compile(&polynomial());
+        assert!(program.bind(&HashMap::from([("x", 1.0)])).is_err());
+        assert!(Vm::default().run(&program, &[1.0]).is_err());
+    }
+
+    #[test]
+    fn disassemble() {
+        let expression = node(Expression::Subtract(
+            node(Expression::Variable("x")),
+            node(Expression::Add(
+                node(Expression::U32(1)),
+                node(Expression::U32(2)),
+            )),
+        ));
+        let program = Program::compile(&expression);
+        assert_eq!(
+            program.to_string(),
+            "0000  load  0 ; x\n0001  push  3\n0002  sub\n"
+        );
+    }
+}
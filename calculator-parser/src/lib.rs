@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+pub mod bytecode;
+
+#[derive(Debug, PartialEq)]
+pub enum Token<'parse> {
+    Number(&'parse str),
+    Identifier(&'parse str),
+    Plus,   // +
+    Minus,  // -
+    Times,  // *
+    Divide, // /
+    Power,  // ^
+    LParen, // (
+    RParen, // )
+    Unknown(&'parse str),
+}
+
+#[derive(Debug, Copy, Clone)]
+enum UnknownToken {
+    Number,
+    Identifier,
+    Other,
+}
+
+impl UnknownToken {
+    fn to_known<'parse>(self, text: &'parse str) -> Token<'parse> {
+        match self {
+            UnknownToken::Number => Token::Number(text),
+            UnknownToken::Identifier => Token::Identifier(text),
+            UnknownToken::Other => Token::Unknown(text),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TokenList<'parse> {
+    text: &'parse str,
+    tokens: Vec<Token<'parse>>,
+}
+
+impl<'parse> TokenList<'parse> {
+    pub fn new(text: &'parse str) -> Self {
+        let mut tokens = vec![];
+
+        let mut unknown = None;
+        for (i, token) in text.char_indices() {
+            let known = match token {
+                '+' => Some(Token::Plus),
+                '-' => Some(Token::Minus),
+                '*' => Some(Token::Times),
+                '/' => Some(Token::Divide),
+                '^' => Some(Token::Power),
+                '(' => Some(Token::LParen),
+                ')' => Some(Token::RParen),
+                ' ' | '\t' | '\n' | '\r' => None,
+                c => {
+                    let word = c.is_alphanumeric() || c == '_';
+                    unknown = match (c.is_ascii_digit(), word, unknown) {
+                        (true, _, None) => Some((i, UnknownToken::Number)),
+                        (false, true, None) => Some((i, UnknownToken::Identifier)),
+                        (false, false, None) => Some((i, UnknownToken::Other)),
+
+                        // Digits can follow letters in a name, but not the
+                        // other way round
+                        (true, _, Some((start, token_type))) => Some((start, token_type)),
+                        (false, true, Some((start, UnknownToken::Identifier))) => {
+                            Some((start, UnknownToken::Identifier))
+                        }
+                        (false, _, Some((start, _))) => Some((start, UnknownToken::Other)),
+                    };
+                    continue;
+                }
+            };
+
+            // Anything else ends the partial token, which comes first
+            if let Some((start, token_type)) = unknown.take() {
+                tokens.push(token_type.to_known(&text[start..i]));
+            }
+            tokens.extend(known);
+        }
+        if let Some((start, token_type)) = unknown {
+            tokens.push(token_type.to_known(&text[start..]));
+        }
+
+        Self { text, tokens }
+    }
+
+    pub fn text(&self) -> &str {
+        self.text
+    }
+
+    pub fn consume(self) -> Vec<Token<'parse>> {
+        self.tokens
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Expression<'parse> {
+    U32(u32),
+    Variable(&'parse str),
+    Add(Box<Expression<'parse>>, Box<Expression<'parse>>),
+    Subtract(Box<Expression<'parse>>, Box<Expression<'parse>>),
+    Multiply(Box<Expression<'parse>>, Box<Expression<'parse>>),
+    Divide(Box<Expression<'parse>>, Box<Expression<'parse>>),
+    Power(Box<Expression<'parse>>, Box<Expression<'parse>>),
+}
+
+impl Expression<'_> {
+    /// Tree-walking evaluation, looking up variables by name at every visit.
+    /// Use [`bytecode::Program`] when evaluating the same expression many times.
+    pub fn evaluate(&self, bindings: &HashMap<&str, f64>) -> anyhow::Result<f64> {
+        Ok(match self {
+            Expression::U32(value) => *value as f64,
+            Expression::Variable(name) => *bindings
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("Unbound variable {:?}", name))?,
+            Expression::Add(lhs, rhs) => lhs.evaluate(bindings)? + rhs.evaluate(bindings)?,
+            Expression::Subtract(lhs, rhs) => lhs.evaluate(bindings)? - rhs.evaluate(bindings)?,
+            Expression::Multiply(lhs, rhs) => lhs.evaluate(bindings)? * rhs.evaluate(bindings)?,
+            Expression::Divide(lhs, rhs) => lhs.evaluate(bindings)? / rhs.evaluate(bindings)?,
+            Expression::Power(lhs, rhs) => lhs.evaluate(bindings)?.powf(rhs.evaluate(bindings)?),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Parsed<'parse> {
+    text: &'parse str,
+    tree: Expression<'parse>,
+}
+
+/// Recursive descent, one function per precedence level. The tokens are
+/// reversed so the next one can be popped off the end.
+struct Parsing<'parse> {
+    input_tokens: Vec<Token<'parse>>,
+}
+
+impl<'parse> Parsing<'parse> {
+    fn next_is(&self, expected: &Token) -> bool {
+        self.input_tokens.last() == Some(expected)
+    }
+
+    /// `product (('+' | '-') product)*`
+    fn sum(&mut self) -> anyhow::Result<Expression<'parse>> {
+        let mut lhs = self.product()?;
+        loop {
+            let operator: fn(_, _) -> _ = match self.input_tokens.last() {
+                Some(Token::Plus) => Expression::Add,
+                Some(Token::Minus) => Expression::Subtract,
+                _ => return Ok(lhs),
+            };
+            self.input_tokens.pop();
+            lhs = operator(Box::new(lhs), Box::new(self.product()?));
+        }
+    }
+
+    /// `unary (('*' | '/') unary)*`
+    fn product(&mut self) -> anyhow::Result<Expression<'parse>> {
+        let mut lhs = self.unary()?;
+        loop {
+            let operator: fn(_, _) -> _ = match self.input_tokens.last() {
+                Some(Token::Times) => Expression::Multiply,
+                Some(Token::Divide) => Expression::Divide,
+                _ => return Ok(lhs),
+            };
+            self.input_tokens.pop();
+            lhs = operator(Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    /// `'-' unary | power`, so `-x ^ 2` is `-(x ^ 2)`
+    fn unary(&mut self) -> anyhow::Result<Expression<'parse>> {
+        if self.next_is(&Token::Minus) {
+            self.input_tokens.pop();
+            let operand = self.unary()?;
+            return Ok(Expression::Subtract(
+                Box::new(Expression::U32(0)),
+                Box::new(operand),
+            ));
+        }
+        self.power()
+    }
+
+    /// `atom ('^' unary)?`, which is right associative
+    fn power(&mut self) -> anyhow::Result<Expression<'parse>> {
+        let base = self.atom()?;
+        if self.next_is(&Token::Power) {
+            self.input_tokens.pop();
+            let exponent = self.unary()?;
+            return Ok(Expression::Power(Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    /// A number, a variable, or a bracketed sum
+    fn atom(&mut self) -> anyhow::Result<Expression<'parse>> {
+        match self.input_tokens.pop() {
+            // TODO: Handle numbers larger than u32
+            Some(Token::Number(text)) => Ok(Expression::U32(text.parse()?)),
+            Some(Token::Identifier(name)) => Ok(Expression::Variable(name)),
+            Some(Token::LParen) => {
+                let inner = self.sum()?;
+                match self.input_tokens.pop() {
+                    Some(Token::RParen) => Ok(inner),
+                    Some(token) => Err(anyhow::anyhow!("Expected ')', got {:?}", token)),
+                    None => Err(anyhow::anyhow!("Expected ')', got nothing")),
+                }
+            }
+            Some(token) => Err(anyhow::anyhow!(
+                "Expected number, variable or '(', got {:?}",
+                token
+            )),
+            None => Err(anyhow::anyhow!("Expected something, got nothing")),
+        }
+    }
+}
+
+impl<'parse> Parsed<'parse> {
+    pub fn new(tokens: TokenList<'parse>) -> anyhow::Result<Self> {
+        let text = tokens.text;
+        let mut parsing = Parsing {
+            input_tokens: tokens.consume().into_iter().rev().collect(),
+        };
+        let tree = parsing.sum()?;
+        if let Some(token) = parsing.input_tokens.pop() {
+            return Err(anyhow::anyhow!("Unexpected {:?} after expression", token));
+        }
+        Ok(Self { text, tree })
+    }
+
+    pub fn text(&self) -> &str {
+        self.text
+    }
+
+    pub fn tree(&self) -> &Expression<'parse> {
+        &self.tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Expression, Parsed, Token, TokenList};
+
+    #[test]
+    fn tokenize() {
+        assert_eq!(
+            TokenList::new("12+x_1 *(y2)^ 3 2x é?").consume(),
+            vec![
+                Token::Number("12"),
+                Token::Plus,
+                Token::Identifier("x_1"),
+                Token::Times,
+                Token::LParen,
+                Token::Identifier("y2"),
+                Token::RParen,
+                Token::Power,
+                Token::Number("3"),
+                Token::Unknown("2x"),
+                Token::Unknown("é?"),
+            ]
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        let parsed = Parsed::new(TokenList::new("1 - 2 - 3 * 2 ^ 3 ^ 2 / -x")).unwrap();
+        let bindings = HashMap::from([("x", 4.0)]);
+        let expected = 1.0 - 2.0 - 3.0 * 2f64.powf(9.0) / -4.0;
+        assert_eq!(parsed.tree().evaluate(&bindings).unwrap(), expected);
+
+        let parsed = Parsed::new(TokenList::new("-(a + 1)^2")).unwrap();
+        let bindings = HashMap::from([("a", 2.0)]);
+        assert_eq!(parsed.tree().evaluate(&bindings).unwrap(), -9.0);
+    }
+
+    #[test]
+    fn parse_variables() {
+        let parsed = Parsed::new(TokenList::new("x * y")).unwrap();
+        assert_eq!(
+            parsed.tree(),
+            &Expression::Multiply(
+                Box::new(Expression::Variable("x")),
+                Box::new(Expression::Variable("y"))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for text in [
+            "",
+            "1 +",
+            "(1 + 2",
+            "1 2",
+            "1 + unknown?",
+            "3)",
+            "99999999999",
+        ] {
+            assert!(Parsed::new(TokenList::new(text)).is_err(), "{text:?}");
+        }
+    }
+}
@@ -1,212 +1,167 @@
-use std::f128::consts::E;
-
-#[derive(Debug)]
-pub(crate) enum Token<'parse> {
-    Number(&'parse str),
-    Plus,   // +
-    Minus,  // -
-    Times,  // *
-    Divide, // /
-    Power,  // ^
-    LParen, // (
-    RParen, // )
-    Unknown(&'parse str),
-}
-
-#[derive(Debug, Copy, Clone)]
-enum UnknownToken {
-    Number,
-    Other,
-}
-
-impl UnknownToken {
-    fn to_known<'parse>(&self, text: &'parse str) -> Token<'parse> {
-        match self {
-            UnknownToken::Number => Token::Number(text),
-            UnknownToken::Other => Token::Unknown(text),
-        }
-    }
-}
-
-#[derive(Debug)]
-pub(crate) struct TokenList<'parse> {
-    text: &'parse str,
-    tokens: Vec<Token<'parse>>,
-}
-
-impl<'parse> TokenList<'parse> {
-    fn new(text: &'parse str) -> Self {
-        let mut tokens = vec![];
-
-        let mut unknown = None;
-        let mut iter = text.chars().enumerate().peekable();
-        while let Some((i, token)) = iter.next() {
-            let mut is_partial_token = false;
-            match token {
-                '+' => tokens.push(Token::Plus),
-                '-' => tokens.push(Token::Minus),
-                '*' => tokens.push(Token::Times),
-                '/' => tokens.push(Token::Divide),
-                '^' => tokens.push(Token::Power),
-                '(' => tokens.push(Token::LParen),
-                ')' => tokens.push(Token::RParen),
-                ' ' | '\t' | '\n' | '\r' => (),
-                c => {
-                    unknown = match (c.is_ascii_digit(), unknown) {
-                        (true, None) => Some((i, UnknownToken::Number)),
-                        (false, None) => Some((i, UnknownToken::Other)),
-
-                        (true, Some((start, token_type))) => Some((start, token_type)),
-                        (false, Some((start, _))) => Some((start, UnknownToken::Other)),
-                    };
-                    is_partial_token = true;
-                }
-            }
-
-            if let Some((start, token_type)) = unknown {
-                if !is_partial_token {
-                    tokens.push(token_type.to_known(&text[start..i]));
-                    unknown = None;
-                } else if iter.peek().is_none() {
-                    tokens.push(token_type.to_known(&text[start..]));
-                    unknown = None;
-                }
+use calculator_parser::bytecode::{Program, Vm};
+use calculator_parser::{Parsed, TokenList};
+
+const USAGE: &str = "Usage: calculator-parser <expression> [name=value | name=start:end[:step]]...";
+
+/// The values of one variable, from `name=value`, or `name=start:end[:step]`
+/// which includes the end
+fn parse_binding(arg: &str) -> anyhow::Result<(&str, Vec<f64>)> {
+    let (name, values) = arg
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Expected name=values, got {:?}", arg))?;
+    let numbers = values
+        .split(':')
+        .map(|number| number.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let values = match numbers[..] {
+        [value] => vec![value],
+        [start, end] | [start, end, _] => {
+            let step = numbers.get(2).copied().unwrap_or(1.0);
+            if step == 0.0 || (end - start) * step < 0.0 {
+                return Err(anyhow::anyhow!(
+                    "Step {} never gets from {} to {}",
+                    step,
+                    start,
+                    end
+                ));
             }
+            // A little slack so the end isn't lost to rounding
+            let count = ((end - start) / step + 1e-9).floor() as usize + 1;
+            (0..count).map(|i| start + i as f64 * step).collect()
         }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Expected value or start:end[:step], got {:?}",
+                values
+            ))
+        }
+    };
+    Ok((name.trim(), values))
+}
 
-        Self { text, tokens }
-    }
-
-    pub(crate) fn text(&self) -> &str {
-        &self.text
+/// Runs `program` on every combination of the bound values, the last
+/// binding changing fastest. Each row is the values in the order they were
+/// bound, then the result.
+fn tabulate(program: &Program, bindings: &[(&str, Vec<f64>)]) -> anyhow::Result<Vec<Vec<f64>>> {
+    let positions = program
+        .variables()
+        .iter()
+        .map(|variable| {
+            bindings
+                .iter()
+                .position(|(name, _)| name == variable)
+                .ok_or_else(|| anyhow::anyhow!("Unbound variable {:?}", variable))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if bindings.iter().any(|(_, values)| values.is_empty()) {
+        return Ok(vec![]);
     }
 
-    pub(crate) fn consume(self) -> Vec<Token<'parse>> {
-        return self.tokens;
+    let mut vm = Vm::default();
+    let mut rows = vec![];
+    let mut indices = vec![0; bindings.len()];
+    let mut slots = vec![0.0; positions.len()];
+    loop {
+        let mut row = bindings
+            .iter()
+            .zip(&indices)
+            .map(|((_, values), &i)| values[i])
+            .collect::<Vec<_>>();
+        for (slot, &position) in slots.iter_mut().zip(&positions) {
+            *slot = row[position];
+        }
+        row.push(vm.run(program, &slots)?);
+        rows.push(row);
+
+        // Count up like an odometer
+        let Some(digit) = (0..indices.len())
+            .rev()
+            .find(|&digit| indices[digit] + 1 < bindings[digit].1.len())
+        else {
+            return Ok(rows);
+        };
+        indices[digit] += 1;
+        indices[digit + 1..].fill(0);
     }
 }
 
-#[derive(Debug)]
-enum Expression<'parse> {
-    U32(u32),
-    Add(&'parse Expression<'parse>, &'parse Expression<'parse>),
-    Subtract(&'parse Expression<'parse>, &'parse Expression<'parse>),
-    Multiply(&'parse Expression<'parse>, &'parse Expression<'parse>),
-    Divide(&'parse Expression<'parse>, &'parse Expression<'parse>),
-    Power(&'parse Expression<'parse>, &'parse Expression<'parse>),
-}
-
-#[derive(Debug, Default)]
-struct PartialExpression<'parse> {
-    operator: Option<Token<'parse>>,
-    lhs: Option<&'parse Expression<'parse>>,
-    rhs: Option<&'parse Expression<'parse>>,
-}
-
-#[derive(Debug)]
-struct ParsedTree<'parse>(Expression<'parse>);
-
-#[derive(Debug)]
-pub(crate) struct Parsed<'parse> {
-    text: &'parse str,
-    expressions: Vec<Expression<'parse>>,
-    tree: ParsedTree<'parse>,
-}
-
-#[derive(Debug)]
-enum ParserState<'parse> {
-    AfterOperator {
-        operator: Token<'parse>,
-        lhs: Option<&'parse Expression<'parse>>,
-    },
-    AfterNumber {},
-}
-
-struct Parsing<'parse> {
-    input_tokens: Vec<Token<'parse>>,
-    stack: Vec<Token<'parse>>,
-    partial_thingy: Vec<PartialParsedThingy<'parse>>,
-    expressions: Vec<Expression<'parse>>,
-}
-
-enum NakedOperator {
-    Plus,
-    Minus,
-    Times,
-    Divide,
-    Power,
-}
-
-enum PartialParsedThingy<'parse> {
-    Number(u32),
-    NumberOperator(&'parse Expression<'parse>, NakedOperator),
-    Triple(&'parse Expression<'parse>, NakedOperator, &'parse PartialParsedThingy<'parse>),
-}
-
-impl<'parse> Parsing<'parse> {
-    fn expect_number_or_lparen(&mut self) -> anyhow::Result<()> {
-        while let Some(Token::LParen) = self.input_tokens.pop() {
-            self.stack.push(Token::LParen);
-            self.expect_number_or_lparen()?;
-        }
-        match self.input_tokens.pop() {
-            Some(Token::Number(text)) => Ok(text),
-            Some(token) => Err(anyhow::anyhow!("Expected number, got {:?}", token)),
-            None => Err(anyhow::anyhow!("Expected something, got nothing")),
-        }
+fn main() -> anyhow::Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let Some((text, bindings)) = args.split_first() else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+    let bindings = bindings
+        .iter()
+        .map(|arg| parse_binding(arg))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let parsed = Parsed::new(TokenList::new(text))?;
+    let program = Program::compile(parsed.tree());
+    let header = bindings
+        .iter()
+        .map(|(name, _)| *name)
+        .chain([parsed.text()])
+        .collect::<Vec<_>>();
+    println!("{}", header.join("\t"));
+    for row in tabulate(&program, &bindings)? {
+        let row = row.iter().map(f64::to_string).collect::<Vec<_>>();
+        println!("{}", row.join("\t"));
     }
+    Ok(())
 }
 
-impl<'parse> Parsed<'parse> {
-    fn new(tokens: TokenList<'parse>) -> anyhow::Result<Self> {
-        let mut stack = vec![];
-        let mut expressions = vec![];
-        let tokens = tokens.consume().into_iter().rev().collect::<Vec<_>>();
-
-        while let Some(Token::LParen) = tokens.pop() {
-            stack.push(Token::LParen);
-        }
-
-        match tokens.pop() {
-            Some(token @ Token::Number(_)) => {
-                stack.push(token);
-            }
-            _ => return Err(anyhow::anyhow!("Expected at least one number")),
-        }
-
-        while let Some(token) = tokens.pop() {
-            match token {
-                Token::Number(_) => return Err(anyhow::anyhow!("Number after number")),
-                token@ (Token::Times | Token::Divide | Token::Power) => {
-                    ;
-            }
+#[cfg(test)]
+mod tests {
+    use calculator_parser::bytecode::Program;
+    use calculator_parser::{Parsed, TokenList};
+
+    use super::{parse_binding, tabulate};
+
+    #[test]
+    fn bindings() {
+        assert_eq!(parse_binding("x=2.5").unwrap(), ("x", vec![2.5]));
+        assert_eq!(
+            parse_binding("x=0:3").unwrap(),
+            ("x", vec![0.0, 1.0, 2.0, 3.0])
+        );
+        assert_eq!(
+            parse_binding("t = 1:0:-0.5").unwrap(),
+            ("t", vec![1.0, 0.5, 0.0])
+        );
+        assert_eq!(parse_binding("x=0:1:0.1").unwrap().1.len(), 11);
+        for arg in ["x", "x=", "x=a", "x=0:1:0", "x=0:1:-1", "x=1:2:3:4"] {
+            assert!(parse_binding(arg).is_err(), "{arg:?}");
         }
     }
 
-    fn new_dont_know(tokens: TokenList<'parse>) -> anyhow::Result<Self> {
-        let mut expressions = vec![];
-        let tokens = tokens.consume();
-        let mut root = PartialExpression::default();
-        for token in &tokens {
-            let x = match token {
-                // TODO: Handle numbers larger than u32
-                Token::Number(text) => Some(Expression::U32(text.parse()?)),
-                Token::Plus => todo!(),
-                Token::Minus => todo!(),
-                Token::Times => todo!(),
-                Token::Divide => todo!(),
-                Token::Power => todo!(),
-                Token::LParen => todo!(),
-                Token::RParen => todo!(),
-                Token::Unknown(_) => panic!("Unknown token"),
-            };
-        }
-        let mut tree = ParsedTree(Expression::Number("0"));
+    #[test]
+    fn tabulate_every_combination() {
+        let parsed = Parsed::new(TokenList::new("x ^ 2 - y")).unwrap();
+        let program = Program::compile(parsed.tree());
+        let bindings = [("y", vec![10.0, 20.0]), ("x", vec![1.0, 2.0, 3.0])];
+        assert_eq!(
+            tabulate(&program, &bindings).unwrap(),
+            vec![
+                vec![10.0, 1.0, -9.0],
+                vec![10.0, 2.0, -6.0],
+                vec![10.0, 3.0, -1.0],
+                vec![20.0, 1.0, -19.0],
+                vec![20.0, 2.0, -16.0],
+                vec![20.0, 3.0, -11.0],
+            ]
+        );
+        assert!(tabulate(&program, &bindings[..1]).is_err());
+        assert_eq!(
+            tabulate(&program, &[("x", vec![]), ("y", vec![1.0])]).unwrap(),
+            Vec::<Vec<f64>>::new()
+        );
     }
-}
 
-fn main() {
-    let tokens = TokenList::new("1 + 2 * 3 / unknown");
-    let parsed = Parsed::new(tokens);
-    println!("{:?}", parsed);
+    #[test]
+    fn constant_expression() {
+        let parsed = Parsed::new(TokenList::new("(1 + 2) * 7")).unwrap();
+        let program = Program::compile(parsed.tree());
+        assert_eq!(tabulate(&program, &[]).unwrap(), vec![vec![21.0]]);
+    }
 }
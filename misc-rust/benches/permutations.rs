@@ -0,0 +1,72 @@
+//! Going through every permutation, in place against `itertools`, which
+//! allocates a `Vec` for each one
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use itertools::Itertools;
+use rust_misc::combinatorics::{Heap, KPermutations, Lexicographic};
+
+fn permutations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("permutations");
+    for n in [6, 8, 10] {
+        group.bench_with_input(BenchmarkId::new("heap", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut items = (0..n).collect_vec();
+                let mut permutations = Heap::new(&mut items);
+                let mut sum = 0;
+                while let Some(permutation) = permutations.next() {
+                    sum += permutation[0];
+                }
+                black_box(sum)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("lexicographic", n), &n, |b, &n| {
+            b.iter(|| {
+                let mut items = (0..n).collect_vec();
+                let mut permutations = Lexicographic::new(&mut items);
+                let mut sum = 0;
+                while let Some(permutation) = permutations.next() {
+                    sum += permutation[0];
+                }
+                black_box(sum)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("itertools", n), &n, |b, &n| {
+            b.iter(|| {
+                let sum = (0..n)
+                    .permutations(n)
+                    .map(|permutation| permutation[0])
+                    .sum::<usize>();
+                black_box(sum)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn k_permutations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("k-permutations");
+    let (n, k) = (12, 4);
+    group.bench_function("in place", |b| {
+        b.iter(|| {
+            let mut items = (0..n).collect_vec();
+            let mut permutations = KPermutations::new(&mut items, k);
+            let mut sum = 0;
+            while let Some(permutation) = permutations.next() {
+                sum += permutation[0];
+            }
+            black_box(sum)
+        })
+    });
+    group.bench_function("itertools", |b| {
+        b.iter(|| {
+            let sum = (0..n)
+                .permutations(k)
+                .map(|permutation| permutation[0])
+                .sum::<usize>();
+            black_box(sum)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, permutations, k_permutations);
+criterion_main!(benches);
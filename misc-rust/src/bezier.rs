@@ -0,0 +1,685 @@
+//! Bézier curves of any degree, generic over the number type, so the same
+//! curve can be worked with as `f64` or as an arbitrary-precision
+//! `rug::Float`.
+//!
+//! Everything is built on de Casteljau's algorithm, which only ever takes
+//! weighted averages of the control points, so it stays stable at any degree.
+//! Where there's no closed form (arc length, the bounding box of a high
+//! degree curve, intersections), the curve is split in half until the
+//! pieces are within a given tolerance. A curve always stays inside the
+//! bounding box of its control points, which is what makes that work.
+//!
+//! ```
+//! use rust_misc::bezier::{Bezier, Point};
+//!
+//! let arch = Bezier::new(vec![
+//!     Point::new(0.0, 0.0),
+//!     Point::new(1.0, 2.0),
+//!     Point::new(2.0, 0.0),
+//! ]);
+//! assert_eq!(arch.at(&0.5), Point::new(1.0, 1.0));
+//! let (low, high) = arch.bounding_box(&1e-9);
+//! assert_eq!(low, Point::new(0.0, 0.0));
+//! assert!((high.y - 1.0).abs() < 1e-9);
+//! ```
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use rug::Float;
+
+/// The arithmetic a curve needs. Constants are made from an existing value,
+/// so that types with a precision can match it.
+pub trait Scalar:
+    Clone
+    + Debug
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    /// `value`, at the same precision as `self`
+    fn constant(&self, value: f64) -> Self;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+}
+
+impl Scalar for f64 {
+    fn constant(&self, value: f64) -> Self {
+        value
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+}
+
+impl Scalar for Float {
+    fn constant(&self, value: f64) -> Self {
+        Float::with_val(self.prec(), value)
+    }
+
+    fn sqrt(self) -> Self {
+        Float::sqrt(self)
+    }
+
+    fn abs(self) -> Self {
+        Float::abs(self)
+    }
+}
+
+fn min<T: Scalar>(a: T, b: T) -> T {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn max<T: Scalar>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Point<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T: Scalar> Point<T> {
+    pub fn distance(&self, other: &Self) -> T {
+        let dx = other.x.clone() - self.x.clone();
+        let dy = other.y.clone() - self.y.clone();
+        (dx.clone() * dx + dy.clone() * dy).sqrt()
+    }
+
+    fn minus(&self, other: &Self) -> Self {
+        Point::new(
+            self.x.clone() - other.x.clone(),
+            self.y.clone() - other.y.clone(),
+        )
+    }
+
+    fn dot(&self, other: &Self) -> T {
+        self.x.clone() * other.x.clone() + self.y.clone() * other.y.clone()
+    }
+
+    fn cross(&self, other: &Self) -> T {
+        self.x.clone() * other.y.clone() - self.y.clone() * other.x.clone()
+    }
+}
+
+/// The curve of a single coordinate at `t`, given its control values
+fn casteljau<T: Scalar>(values: &[T], t: &T) -> T {
+    split_values(values, t).1.swap_remove(0)
+}
+
+/// The control values of the curve of a single coordinate before and after
+/// `t`
+fn split_values<T: Scalar>(values: &[T], t: &T) -> (Vec<T>, Vec<T>) {
+    let mut level = values.to_vec();
+    let mut before = Vec::with_capacity(values.len());
+    let mut after = Vec::with_capacity(values.len());
+    while let Some(last) = level.last() {
+        before.push(level[0].clone());
+        after.push(last.clone());
+        level = level
+            .windows(2)
+            .map(|pair| pair[0].clone() + (pair[1].clone() - pair[0].clone()) * t.clone())
+            .collect();
+    }
+    after.reverse();
+    (before, after)
+}
+
+/// The smallest value the curve of a single coordinate takes, to within
+/// `tolerance`. Pieces are only split while the smallest of their control
+/// values is further below the best endpoint so far than that.
+fn minimum<T: Scalar>(values: Vec<T>, tolerance: &T) -> T {
+    let half = tolerance.constant(0.5);
+    let mut best = min(values[0].clone(), values[values.len() - 1].clone());
+    let mut pieces = vec![values];
+    while let Some(values) = pieces.pop() {
+        best = min(
+            best,
+            min(values[0].clone(), values[values.len() - 1].clone()),
+        );
+        let lowest = values.iter().cloned().reduce(min).unwrap();
+        if lowest < best.clone() - tolerance.clone() {
+            let (before, after) = split_values(&values, &half);
+            pieces.push(before);
+            pieces.push(after);
+        }
+    }
+    best
+}
+
+/// A piece of a curve, with the parameters of the original it came from
+#[derive(Clone)]
+struct Piece<T> {
+    curve: Bezier<T>,
+    start: T,
+    end: T,
+}
+
+impl<T: Scalar> Piece<T> {
+    fn halves(&self) -> [Self; 2] {
+        let half = self.start.constant(0.5);
+        let middle = (self.start.clone() + self.end.clone()) * half.clone();
+        let (before, after) = self.curve.split(&half);
+        [
+            Piece {
+                curve: before,
+                start: self.start.clone(),
+                end: middle.clone(),
+            },
+            Piece {
+                curve: after,
+                start: middle,
+                end: self.end.clone(),
+            },
+        ]
+    }
+
+    /// The parameter on the original of `t` along this piece
+    fn parameter(&self, t: T) -> T {
+        self.start.clone() + (self.end.clone() - self.start.clone()) * t
+    }
+
+    /// Whether every control point is within `tolerance` of where it would
+    /// be if the piece ran along its chord at an even speed. The whole piece
+    /// is then within `tolerance` of that, at the same parameters.
+    fn is_straight(&self, tolerance: &T) -> bool {
+        let points = self.curve.points();
+        let first = &points[0];
+        let chord = points[points.len() - 1].minus(first);
+        let degree = tolerance.constant(self.curve.degree() as f64);
+        points.iter().enumerate().skip(1).all(|(i, point)| {
+            let along = tolerance.constant(i as f64) / degree.clone();
+            let even = Point::new(
+                first.x.clone() + chord.x.clone() * along.clone(),
+                first.y.clone() + chord.y.clone() * along,
+            );
+            point.distance(&even) <= *tolerance
+        })
+    }
+}
+
+/// The parameter ranges of a pair of pieces found to meet, and the
+/// parameters of both ends of the stretch they share
+type Hit<T> = ([T; 4], [(T, T); 2]);
+
+/// The ends of two pieces whose control points are each within `tolerance`
+/// of the other's, either way round. They then trace the same stretch at the
+/// same speed.
+fn matching<T: Scalar>(a: &Piece<T>, b: &Piece<T>, tolerance: &T) -> Option<[(T, T); 2]> {
+    let (a_points, b_points) = (a.curve.points(), b.curve.points());
+    if a_points.len() != b_points.len() {
+        return None;
+    }
+    let close = |p: &Point<T>, q: &Point<T>| p.distance(q) <= *tolerance;
+    if a_points.iter().zip(b_points).all(|(p, q)| close(p, q)) {
+        Some([
+            (a.start.clone(), b.start.clone()),
+            (a.end.clone(), b.end.clone()),
+        ])
+    } else if a_points
+        .iter()
+        .zip(b_points.iter().rev())
+        .all(|(p, q)| close(p, q))
+    {
+        Some([
+            (a.start.clone(), b.end.clone()),
+            (a.end.clone(), b.start.clone()),
+        ])
+    } else {
+        None
+    }
+}
+
+/// Where the chords of two straight pieces meet, as the parameters on the
+/// originals of both ends of the stretch they share. A crossing is a stretch
+/// whose ends are the same. Chords within `tolerance` of the same line share
+/// wherever they overlap along it.
+fn chord_meeting<T: Scalar>(a: &Piece<T>, b: &Piece<T>, tolerance: &T) -> Option<[(T, T); 2]> {
+    let ends = |piece: &Piece<T>| {
+        let points = piece.curve.points();
+        (points[0].clone(), points[points.len() - 1].clone())
+    };
+    let (p, p_end) = ends(a);
+    let (q, q_end) = ends(b);
+    let (da, db) = (p_end.minus(&p), q_end.minus(&q));
+    let (a_squared, b_squared) = (da.dot(&da), db.dot(&db));
+    // Measure against the longer chord
+    if a_squared < b_squared {
+        return chord_meeting(b, a, tolerance).map(|ends| ends.map(|(t, s)| (s, t)));
+    }
+    let zero = tolerance.constant(0.0);
+    let one = tolerance.constant(1.0);
+    if a_squared == zero {
+        let both = (a.start.clone(), b.start.clone());
+        return (p.distance(&q) <= *tolerance).then(|| [both.clone(), both]);
+    }
+    let (a_length, b_length) = (a_squared.clone().sqrt(), b_squared.clone().sqrt());
+
+    let offsets = [q.minus(&p), q_end.minus(&p)];
+    let off_line = tolerance.clone() * a_length.clone();
+    if offsets
+        .iter()
+        .all(|offset| da.cross(offset).abs() <= off_line)
+    {
+        // Along the same line, so they share where b's ends project onto a
+        let [from, to] = offsets.map(|offset| da.dot(&offset) / a_squared.clone());
+        let low = max(zero.clone(), min(from.clone(), to.clone()));
+        let high = min(one.clone(), max(from, to));
+        if (low.clone() - high.clone()) * a_length > *tolerance {
+            return None;
+        }
+        let high = max(low.clone(), high);
+        let on_b = |s: T| {
+            if b_squared == zero {
+                return zero.clone();
+            }
+            let point = Point::new(
+                p.x.clone() + da.x.clone() * s.clone(),
+                p.y.clone() + da.y.clone() * s,
+            );
+            let t = db.dot(&point.minus(&q)) / b_squared.clone();
+            min(one.clone(), max(zero.clone(), t))
+        };
+        return Some([low, high].map(|s| (a.parameter(s.clone()), b.parameter(on_b(s)))));
+    }
+
+    let denominator = da.cross(&db);
+    if denominator == zero {
+        return None;
+    }
+    let s = offsets[0].cross(&db) / denominator.clone();
+    let t = offsets[0].cross(&da) / denominator;
+    // Either end may be just past the crossing, by no more than the tolerance
+    let within = |u: &T, length: &T| {
+        -(u.clone() * length.clone()) <= *tolerance
+            && (u.clone() - one.clone()) * length.clone() <= *tolerance
+    };
+    if !within(&s, &a_length) || !within(&t, &b_length) {
+        return None;
+    }
+    let s = a.parameter(min(one.clone(), max(zero.clone(), s)));
+    let t = b.parameter(min(one, max(zero, t)));
+    Some([(s.clone(), t.clone()), (s, t)])
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bezier<T> {
+    points: Vec<Point<T>>,
+}
+
+impl<T: Scalar> Bezier<T> {
+    /// Needs at least one control point. The curve starts at the first and
+    /// ends at the last.
+    pub fn new(points: Vec<Point<T>>) -> Self {
+        assert!(!points.is_empty(), "A curve needs a control point");
+        Self { points }
+    }
+
+    pub fn points(&self) -> &[Point<T>] {
+        &self.points
+    }
+
+    pub fn degree(&self) -> usize {
+        self.points.len() - 1
+    }
+
+    fn xs(&self) -> Vec<T> {
+        self.points.iter().map(|p| p.x.clone()).collect()
+    }
+
+    fn ys(&self) -> Vec<T> {
+        self.points.iter().map(|p| p.y.clone()).collect()
+    }
+
+    fn from_coordinates(xs: Vec<T>, ys: Vec<T>) -> Self {
+        Self::new(
+            xs.into_iter()
+                .zip(ys)
+                .map(|(x, y)| Point::new(x, y))
+                .collect(),
+        )
+    }
+
+    /// The point at `t`. The curve runs from `t = 0` to `t = 1`, anything
+    /// outside of that extends it.
+    pub fn at(&self, t: &T) -> Point<T> {
+        Point::new(casteljau(&self.xs(), t), casteljau(&self.ys(), t))
+    }
+
+    /// The curve up to `t` and from `t` on, each as a curve of the same
+    /// degree running from 0 to 1
+    pub fn split(&self, t: &T) -> (Self, Self) {
+        let (x_before, x_after) = split_values(&self.xs(), t);
+        let (y_before, y_after) = split_values(&self.ys(), t);
+        (
+            Self::from_coordinates(x_before, y_before),
+            Self::from_coordinates(x_after, y_after),
+        )
+    }
+
+    /// The smallest box holding all of the control points, and so the curve
+    pub fn hull_box(&self) -> (Point<T>, Point<T>) {
+        let first = &self.points[0];
+        let (mut low, mut high) = (first.clone(), first.clone());
+        for point in &self.points[1..] {
+            low = Point::new(min(low.x, point.x.clone()), min(low.y, point.y.clone()));
+            high = Point::new(max(high.x, point.x.clone()), max(high.y, point.y.clone()));
+        }
+        (low, high)
+    }
+
+    /// The lowest and highest corners of the smallest box holding the curve,
+    /// each coordinate to within `tolerance`
+    pub fn bounding_box(&self, tolerance: &T) -> (Point<T>, Point<T>) {
+        let negated = |values: Vec<T>| values.into_iter().map(|v| -v).collect();
+        (
+            Point::new(minimum(self.xs(), tolerance), minimum(self.ys(), tolerance)),
+            Point::new(
+                -minimum(negated(self.xs()), tolerance),
+                -minimum(negated(self.ys()), tolerance),
+            ),
+        )
+    }
+
+    /// The length of the curve, to within about `tolerance`. Pieces are
+    /// split until their control polygon is barely longer than their chord,
+    /// then estimated from both (Gravesen's method).
+    pub fn arc_length(&self, tolerance: &T) -> T {
+        let zero = tolerance.constant(0.0);
+        assert!(*tolerance > zero, "The tolerance must be positive");
+        let half = tolerance.constant(0.5);
+        let degree = tolerance.constant(self.degree() as f64);
+        let mut total = zero.clone();
+        let mut pieces = vec![(self.clone(), tolerance.clone())];
+        while let Some((curve, tolerance)) = pieces.pop() {
+            let chord = curve.points[0].distance(&curve.points[curve.degree()]);
+            let polygon = curve.points.windows(2).fold(zero.clone(), |length, pair| {
+                length + pair[0].distance(&pair[1])
+            });
+            if polygon.clone() - chord.clone() <= tolerance {
+                let one = tolerance.constant(1.0);
+                let two = tolerance.constant(2.0);
+                total = total
+                    + (two * chord + (degree.clone() - one.clone()) * polygon)
+                        / (degree.clone() + one);
+            } else {
+                let (before, after) = curve.split(&half);
+                let tolerance = tolerance * half.clone();
+                pieces.push((before, tolerance.clone()));
+                pieces.push((after, tolerance));
+            }
+        }
+        total
+    }
+
+    /// The parameters on `self` and `other` of each point where they meet,
+    /// to within about `tolerance`. Pieces are split until they're straight
+    /// to within `tolerance`, then met as line segments, or until they match.
+    /// Where the curves run together, the stretch they share gives just its
+    /// two ends, as does a touch that stays within `tolerance` for a while.
+    /// Those ends are only as sharp as the curves part, so where they part
+    /// smoothly they're good to about the square root of `tolerance`.
+    pub fn intersections(&self, other: &Self, tolerance: &T) -> Vec<(T, T)> {
+        let zero = tolerance.constant(0.0);
+        assert!(*tolerance > zero, "The tolerance must be positive");
+        let one = tolerance.constant(1.0);
+        let whole = |curve: &Self| Piece {
+            curve: curve.clone(),
+            start: zero.clone(),
+            end: one.clone(),
+        };
+        let size = |piece: &Piece<T>| {
+            let (low, high) = piece.curve.hull_box();
+            max(high.x - low.x, high.y - low.y)
+        };
+
+        let mut hits: Vec<Hit<T>> = vec![];
+        let mut pairs = vec![(whole(self), whole(other))];
+        while let Some((a, b)) = pairs.pop() {
+            let (a_low, a_high) = a.curve.hull_box();
+            let (b_low, b_high) = b.curve.hull_box();
+            if a_high.x < b_low.x || b_high.x < a_low.x || a_high.y < b_low.y || b_high.y < a_low.y
+            {
+                continue;
+            }
+            let (a_straight, b_straight) = (a.is_straight(tolerance), b.is_straight(tolerance));
+            let (a_size, b_size) = (size(&a), size(&b));
+            let ends = if let Some(ends) = matching(&a, &b, tolerance) {
+                Some(ends)
+            } else if a_straight && b_straight {
+                chord_meeting(&a, &b, tolerance)
+            } else if a_size <= *tolerance && b_size <= *tolerance {
+                // Too small to tell apart, even if not quite straight
+                let half = tolerance.constant(0.5);
+                let middle = (a.parameter(half.clone()), b.parameter(half));
+                Some([middle.clone(), middle])
+            } else {
+                if !a_straight && (b_straight || a_size >= b_size) {
+                    let [before, after] = a.halves();
+                    pairs.push((after, b.clone()));
+                    pairs.push((before, b));
+                } else {
+                    let [before, after] = b.halves();
+                    pairs.push((a.clone(), after));
+                    pairs.push((a, before));
+                }
+                continue;
+            };
+            if let Some(mut ends) = ends {
+                ends.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+                hits.push(([a.start, a.end, b.start, b.end], ends));
+            }
+        }
+
+        // Neighbouring pieces usually all meet the same point, or carry on
+        // the same stretch
+        hits.sort_by(|a, b| a.0[0].partial_cmp(&b.0[0]).unwrap());
+        let mut merged: Vec<Hit<T>> = vec![];
+        for (hit, [first, last]) in hits {
+            match merged
+                .iter_mut()
+                .find(|(m, _)| hit[0] <= m[1] && m[0] <= hit[1] && hit[2] <= m[3] && m[2] <= hit[3])
+            {
+                Some((m, ends)) => {
+                    let [a_start, a_end, b_start, b_end] = hit;
+                    m[0] = min(m[0].clone(), a_start);
+                    m[1] = max(m[1].clone(), a_end);
+                    m[2] = min(m[2].clone(), b_start);
+                    m[3] = max(m[3].clone(), b_end);
+                    if first.0 < ends[0].0 {
+                        ends[0] = first;
+                    }
+                    if last.0 > ends[1].0 {
+                        ends[1] = last;
+                    }
+                }
+                None => merged.push((hit, [first, last])),
+            }
+        }
+        merged
+            .into_iter()
+            .flat_map(|(_, [first, last])| {
+                if self.at(&first.0).distance(&self.at(&last.0)) <= *tolerance {
+                    vec![first]
+                } else {
+                    vec![first, last]
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn curve(points: &[(f64, f64)]) -> Bezier<f64> {
+        Bezier::new(points.iter().map(|&(x, y)| Point::new(x, y)).collect())
+    }
+
+    fn close(a: &Point<f64>, b: &Point<f64>) -> bool {
+        a.distance(b) < 1e-9
+    }
+
+    #[rstest]
+    fn matches_bernstein_form(#[values(0.0, 0.25, 0.5, 0.9, 1.0)] t: f64) {
+        let (a, b, c, d) = ((0.0, 0.0), (1.0, 3.0), (4.0, -1.0), (5.0, 2.0));
+        let cubic = curve(&[a, b, c, d]);
+        let s = 1.0 - t;
+        let weights = [s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t];
+        let expected = [a, b, c, d]
+            .iter()
+            .zip(weights)
+            .fold(Point::new(0.0, 0.0), |sum, (&(x, y), w)| {
+                Point::new(sum.x + w * x, sum.y + w * y)
+            });
+        assert!(close(&cubic.at(&t), &expected));
+    }
+
+    #[rstest]
+    fn split_halves_follow_the_curve(#[values(0.3, 0.5, 0.8)] at: f64) {
+        let quartic = curve(&[(0.0, 0.0), (1.0, 4.0), (3.0, -2.0), (5.0, 5.0), (6.0, 1.0)]);
+        let (before, after) = quartic.split(&at);
+        assert_eq!(before.degree(), 4);
+        for t in [0.0, 0.2, 0.7, 1.0] {
+            assert!(close(&before.at(&t), &quartic.at(&(t * at))));
+            assert!(close(&after.at(&t), &quartic.at(&(at + t * (1.0 - at)))));
+        }
+    }
+
+    #[rstest]
+    #[case::line(&[(0.0, 0.0), (1.0, 1.0), (3.0, 3.0)], 18f64.sqrt())]
+    #[case::point(&[(2.0, 2.0)], 0.0)]
+    // The usual cubic for a quarter of the unit circle is a little long
+    #[case::quarter_circle(
+        &[(1.0, 0.0), (1.0, 0.5522847498), (0.5522847498, 1.0), (0.0, 1.0)],
+        1.5710167,
+    )]
+    fn arc_length(#[case] points: &[(f64, f64)], #[case] expected: f64) {
+        assert!((curve(points).arc_length(&1e-9) - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn bounding_box() {
+        let cubic = curve(&[(0.0, 0.0), (1.0, 2.0), (2.0, -2.0), (3.0, 0.0)]);
+        let (low, high) = cubic.bounding_box(&1e-12);
+        // y = 6t(1 - t)(1 - 2t), which peaks at t = (3 - sqrt(3)) / 6
+        let peak = 3f64.sqrt() / 3.0;
+        assert!(close(&low, &Point::new(0.0, -peak)));
+        assert!(close(&high, &Point::new(3.0, peak)));
+        let (hull_low, hull_high) = cubic.hull_box();
+        assert_eq!(hull_low, Point::new(0.0, -2.0));
+        assert_eq!(hull_high, Point::new(3.0, 2.0));
+    }
+
+    #[rstest]
+    #[case::crossing_lines(
+        curve(&[(0.0, 0.0), (2.0, 2.0)]),
+        curve(&[(0.0, 2.0), (2.0, 0.0)]),
+        vec![(0.5, 0.5)],
+    )]
+    #[case::wave_along_axis(
+        curve(&[(0.0, 0.0), (1.0, 2.0), (2.0, -2.0), (3.0, 0.0)]),
+        curve(&[(-1.0, 0.0), (4.0, 0.0)]),
+        vec![(0.0, 0.2), (0.5, 0.5), (1.0, 0.8)],
+    )]
+    #[case::apart(
+        curve(&[(0.0, 0.0), (1.0, 2.0), (2.0, 0.0)]),
+        curve(&[(0.0, 3.0), (2.0, 3.0)]),
+        vec![],
+    )]
+    fn intersections(
+        #[case] a: Bezier<f64>,
+        #[case] b: Bezier<f64>,
+        #[case] expected: Vec<(f64, f64)>,
+    ) {
+        let found = a.intersections(&b, &1e-9);
+        assert_eq!(found.len(), expected.len(), "{found:?}");
+        for ((s, t), (expected_s, expected_t)) in found.into_iter().zip(expected) {
+            assert!((s - expected_s).abs() < 1e-6 && (t - expected_t).abs() < 1e-6);
+            assert!(a.at(&s).distance(&b.at(&t)) < 1e-6);
+        }
+    }
+
+    #[rstest]
+    #[case::same_line(
+        curve(&[(0.0, 0.0), (2.0, 2.0)]),
+        curve(&[(0.0, 0.0), (2.0, 2.0)]),
+        vec![(0.0, 0.0), (1.0, 1.0)],
+    )]
+    #[case::reversed_part_of_line(
+        curve(&[(0.0, 0.0), (4.0, 0.0)]),
+        curve(&[(3.0, 0.0), (1.0, 0.0)]),
+        vec![(0.25, 1.0), (0.75, 0.0)],
+    )]
+    #[case::same_wave(
+        curve(&[(0.0, 0.0), (1.0, 2.0), (2.0, -2.0), (3.0, 0.0)]),
+        curve(&[(0.0, 0.0), (1.0, 2.0), (2.0, -2.0), (3.0, 0.0)]),
+        vec![(0.0, 0.0), (1.0, 1.0)],
+    )]
+    #[case::middle_of_wave(
+        curve(&[(0.0, 0.0), (1.0, 2.0), (2.0, -2.0), (3.0, 0.0)]),
+        curve(&[(0.0, 0.0), (1.0, 2.0), (2.0, -2.0), (3.0, 0.0)]).split(&0.3).1.split(&0.5).0,
+        vec![(0.3, 0.0), (0.65, 1.0)],
+    )]
+    fn overlapping(
+        #[case] a: Bezier<f64>,
+        #[case] b: Bezier<f64>,
+        #[case] expected: Vec<(f64, f64)>,
+    ) {
+        // Only the ends of the stretch, without splitting all along it
+        let found = a.intersections(&b, &1e-9);
+        assert_eq!(found.len(), expected.len(), "{found:?}");
+        for ((s, t), (expected_s, expected_t)) in found.into_iter().zip(expected) {
+            assert!((s - expected_s).abs() < 1e-4 && (t - expected_t).abs() < 1e-4);
+            assert!(a.at(&s).distance(&b.at(&t)) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn arbitrary_precision() {
+        let precision = 256;
+        let float = |value: f64| Float::with_val(precision, value);
+        let points = [(0.0, 0.0), (1.0, 2.0), (2.0, -2.0), (3.0, 0.0)];
+        let cubic = Bezier::new(
+            points
+                .iter()
+                .map(|&(x, y)| Point::new(float(x), float(y)))
+                .collect(),
+        );
+        let tolerance = float(1e-40);
+        let (_, high) = cubic.bounding_box(&tolerance);
+        let peak = float(3.0).sqrt() / float(3.0);
+        assert!((high.y - peak).abs() < tolerance);
+        // Agrees with the same curve as f64
+        let (s, _) = cubic.split(&float(0.25));
+        let (s_f64, _) = curve(&points).split(&0.25);
+        for (p, q) in s.points().iter().zip(s_f64.points()) {
+            assert_eq!((p.x.to_f64(), p.y.to_f64()), (q.x, q.y));
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Hashes from earlier scans, so that a scan that crashed halfway, or a
+//! re-scan of a mostly unchanged tree, only hashes what it has to. Walking
+//! the tree again is cheap in comparison, so that isn't saved.
+//!
+//! The cache file is a log of one JSON entry per line. Entries are appended
+//! as files are hashed and flushed every few seconds, so a crash loses at
+//! most the last few seconds of work and a half-written last line. Later
+//! entries win, and the log is compacted when a scan finishes, dropping
+//! files the scan didn't see.
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::hashing::Digest;
+use crate::report::lossless_path;
+
+const FLUSH_EVERY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Stage {
+    Partial,
+    Full,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    #[serde(with = "lossless_path")]
+    path: PathBuf,
+    stage: Stage,
+    size: u64,
+    modified: SystemTime,
+    #[serde(with = "hex_digest")]
+    digest: Digest,
+}
+
+mod hex_digest {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(digest: &Digest, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::hashing::to_hex(digest).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Digest, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        blake3::Hash::from_hex(hex)
+            .map(Into::into)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+pub(crate) struct HashCache {
+    known: HashMap<(PathBuf, Stage), Entry>,
+    /// `None` when the cache only lives for this run
+    file: Option<(PathBuf, BufWriter<File>)>,
+    last_flush: Instant,
+    pub hits: usize,
+}
+
+impl HashCache {
+    pub fn in_memory() -> Self {
+        Self {
+            known: HashMap::new(),
+            file: None,
+            last_flush: Instant::now(),
+            hits: 0,
+        }
+    }
+
+    /// Load the cache at `path` if there is one, and append to it from now on
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut cache = Self::in_memory();
+        let log = match fs::read_to_string(path) {
+            Ok(log) => log,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        for (i, line) in log.lines().enumerate() {
+            match serde_json::from_str::<Entry>(line) {
+                Ok(entry) => cache.add(entry),
+                // Most likely cut short by a crash
+                Err(err) => eprintln!(
+                    "{}:{}: skipping cache entry, {}",
+                    path.display(),
+                    i + 1,
+                    err
+                ),
+            }
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if !log.is_empty() && !log.ends_with('\n') {
+            // Don't append to a half-written line
+            writeln!(file)?;
+        }
+        cache.file = Some((path.to_owned(), BufWriter::new(file)));
+        Ok(cache)
+    }
+
+    pub fn len(&self) -> usize {
+        self.known.len()
+    }
+
+    fn add(&mut self, entry: Entry) {
+        self.known.insert((entry.path.clone(), entry.stage), entry);
+    }
+
+    /// Only if the file's size and modification time haven't changed since
+    pub fn get(
+        &mut self,
+        path: &Path,
+        stage: Stage,
+        size: u64,
+        modified: SystemTime,
+    ) -> Option<Digest> {
+        let entry = self.known.get(&(path.to_owned(), stage))?;
+        if entry.size != size || entry.modified != modified {
+            return None;
+        }
+        self.hits += 1;
+        Some(entry.digest)
+    }
+
+    pub fn insert(
+        &mut self,
+        path: &Path,
+        stage: Stage,
+        size: u64,
+        modified: SystemTime,
+        digest: Digest,
+    ) -> io::Result<()> {
+        let entry = Entry {
+            path: path.to_owned(),
+            stage,
+            size,
+            modified,
+            digest,
+        };
+        if let Some((_, file)) = &mut self.file {
+            serde_json::to_writer(&mut *file, &entry)?;
+            writeln!(file)?;
+            if self.last_flush.elapsed() >= FLUSH_EVERY {
+                file.flush()?;
+                self.last_flush = Instant::now();
+            }
+        }
+        self.add(entry);
+        Ok(())
+    }
+
+    /// Rewrite the log with only the latest entry for each file that `keep`
+    /// accepts. Passing the files of this scan drops files that have since
+    /// been deleted or filtered out, so the log doesn't grow forever.
+    pub fn compact(&mut self, keep: impl Fn(&Path) -> bool) -> io::Result<()> {
+        self.known.retain(|(path, _), _| keep(path));
+        let Some((path, file)) = &mut self.file else {
+            return Ok(());
+        };
+        file.flush()?;
+
+        let temp = path.with_extension("tmp");
+        let mut out = BufWriter::new(File::create(&temp)?);
+        for entry in self.known.values() {
+            serde_json::to_writer(&mut out, entry)?;
+            writeln!(out)?;
+        }
+        out.flush()?;
+        fs::rename(&temp, &*path)?;
+
+        let file = OpenOptions::new().append(true).open(&*path)?;
+        self.file = Some((path.clone(), BufWriter::new(file)));
+        Ok(())
+    }
+}
+
+/// Wrap a hash function to use and fill `cache`
+pub(crate) fn cached<'a>(
+    cache: &'a Mutex<HashCache>,
+    stage: Stage,
+    hash: impl Fn(&Path, u64) -> io::Result<Digest> + Sync + 'a,
+) -> impl Fn(&Path, u64) -> io::Result<Digest> + Sync + 'a {
+    move |path, size| {
+        let modified = fs::metadata(path)?.modified()?;
+        if let Some(digest) = cache.lock().unwrap().get(path, stage, size, modified) {
+            return Ok(digest);
+        }
+        let digest = hash(path, size)?;
+        cache
+            .lock()
+            .unwrap()
+            .insert(path, stage, size, modified, digest)?;
+        Ok(digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::SystemTime;
+
+    use super::{HashCache, Stage};
+
+    #[test]
+    fn survives_restarts() {
+        let path = std::env::temp_dir().join("find-duplicate-files-cache.jsonl");
+        let _ = fs::remove_file(&path);
+        let file = std::path::Path::new("/some/file");
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_nanos(1_234_567_891);
+
+        let mut cache = HashCache::open(&path).unwrap();
+        cache
+            .insert(file, Stage::Partial, 10, modified, [1; 32])
+            .unwrap();
+        cache
+            .insert(file, Stage::Partial, 10, modified, [2; 32])
+            .unwrap();
+        cache
+            .insert(file, Stage::Full, 10, modified, [3; 32])
+            .unwrap();
+        // Dropping without compacting, like a crash would, still flushes the
+        // writer. Then a half-written line on top
+        drop(cache);
+        let mut log = fs::read_to_string(&path).unwrap();
+        assert_eq!(log.lines().count(), 3);
+        log.push_str(r#"{"path":"/some/oth"#);
+        fs::write(&path, log).unwrap();
+
+        let mut cache = HashCache::open(&path).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(file, Stage::Partial, 10, modified), Some([2; 32]));
+        assert_eq!(cache.get(file, Stage::Full, 10, modified), Some([3; 32]));
+        assert_eq!(cache.get(file, Stage::Full, 11, modified), None);
+        assert_eq!(cache.get(file, Stage::Full, 10, SystemTime::now()), None);
+        assert_eq!(cache.hits, 2);
+
+        cache
+            .insert(file, Stage::Full, 12, modified, [4; 32])
+            .unwrap();
+        drop(cache);
+        // Not lost by being appended to the half-written line
+        let mut cache = HashCache::open(&path).unwrap();
+        assert_eq!(cache.get(file, Stage::Full, 12, modified), Some([4; 32]));
+
+        cache.compact(|_| true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        let mut cache = HashCache::open(&path).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // The file wasn't in the latest scan
+        cache.compact(|path| path != file).unwrap();
+        assert_eq!(cache.len(), 0);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        assert_eq!(HashCache::open(&path).unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,566 @@
+//! Getting rid of duplicates once they're found. One file of each group is
+//! kept according to a policy, and the rest are hardlinked or reflinked to
+//! it, deleted, or moved into a quarantine directory.
+//!
+//! Nothing is touched without `--apply`, the plan is only printed. When
+//! applying, every change is appended to an undo manifest and synced before
+//! it happens, then marked done afterwards. Every change can be undone since
+//! the kept file has the same contents: replaced and deleted files are
+//! restored as copies of the kept file, and moved files are moved back. A
+//! change that was never marked done is only undone if it did happen.
+use std::ffi::OsString;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Component, Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::hashing;
+use crate::report::{human_size, lossless_path, DuplicateGroup};
+use crate::with_path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Action {
+    Hardlink,
+    /// Copy-on-write copy, only on filesystems that support it (e.g. Btrfs,
+    /// XFS, APFS)
+    Reflink,
+    Delete,
+    /// Into the quarantine directory, mirroring the file's absolute path
+    Move,
+}
+
+/// Which file of a group survives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Keep {
+    /// Earliest modification time
+    Oldest,
+    /// Fewest directories deep, then fewest bytes
+    ShortestPath,
+    /// The first file under the first of the preferred roots, falling back
+    /// to the shortest path
+    PreferredRoot,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Planned {
+    pub action: Action,
+    pub keep: PathBuf,
+    pub path: PathBuf,
+    pub size: u64,
+    /// Why this file is left alone
+    pub skip: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum UndoEntry {
+    /// `path` was hardlinked, reflinked or deleted
+    Replaced {
+        action: Action,
+        #[serde(with = "lossless_path")]
+        kept: PathBuf,
+        #[serde(with = "lossless_path")]
+        path: PathBuf,
+    },
+    Moved {
+        #[serde(with = "lossless_path")]
+        path: PathBuf,
+        #[serde(with = "lossless_path")]
+        quarantined: PathBuf,
+    },
+}
+
+/// A line of the manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ManifestLine {
+    entry: UndoEntry,
+    /// Written as `false` before the change and `true` after it
+    done: bool,
+}
+
+fn shortest_path(paths: &[PathBuf]) -> usize {
+    (0..paths.len())
+        .min_by_key(|i| {
+            let path = &paths[*i];
+            (path.components().count(), path.as_os_str().len(), path)
+        })
+        .expect("Groups aren't empty")
+}
+
+/// The index of the file to keep
+pub(crate) fn choose_keeper(
+    paths: &[PathBuf],
+    keep: Keep,
+    preferred: &[PathBuf],
+) -> io::Result<usize> {
+    match keep {
+        Keep::Oldest => {
+            let mut oldest = 0;
+            let mut oldest_time = fs::metadata(&paths[0])?.modified()?;
+            for (i, path) in paths.iter().enumerate().skip(1) {
+                let modified = fs::metadata(path)?.modified()?;
+                if modified < oldest_time {
+                    (oldest, oldest_time) = (i, modified);
+                }
+            }
+            Ok(oldest)
+        }
+        Keep::ShortestPath => Ok(shortest_path(paths)),
+        Keep::PreferredRoot => Ok(preferred
+            .iter()
+            .find_map(|root| paths.iter().position(|path| path.starts_with(root)))
+            .unwrap_or_else(|| shortest_path(paths))),
+    }
+}
+
+#[cfg(unix)]
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn same_file(_: &Metadata, _: &Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn same_filesystem(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev()
+}
+
+/// Let the OS refuse instead
+#[cfg(not(unix))]
+fn same_filesystem(_: &Metadata, _: &Metadata) -> bool {
+    true
+}
+
+fn skip_reason(action: Action, keep: &Path, path: &Path) -> io::Result<Option<&'static str>> {
+    if matches!(action, Action::Hardlink | Action::Reflink) {
+        let (keep, path) = (fs::metadata(keep)?, fs::metadata(path)?);
+        if same_file(&keep, &path) {
+            return Ok(Some("already the same file"));
+        }
+        if !same_filesystem(&keep, &path) {
+            return Ok(Some("on a different filesystem"));
+        }
+    }
+    Ok(None)
+}
+
+/// What `apply()` would do to each group
+pub(crate) fn plan(
+    groups: &[DuplicateGroup],
+    action: Action,
+    keep: Keep,
+    preferred: &[PathBuf],
+) -> (Vec<Planned>, Vec<io::Error>) {
+    let mut planned = vec![];
+    let mut errors = vec![];
+    for group in groups {
+        let keeper = match choose_keeper(&group.paths, keep, preferred) {
+            Ok(keeper) => &group.paths[keeper],
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        };
+        for path in group.paths.iter().filter(|path| *path != keeper) {
+            match skip_reason(action, keeper, path) {
+                Ok(skip) => planned.push(Planned {
+                    action,
+                    keep: keeper.clone(),
+                    path: path.clone(),
+                    size: group.size,
+                    skip,
+                }),
+                Err(err) => errors.push(with_path(err, path)),
+            }
+        }
+    }
+    (planned, errors)
+}
+
+pub(crate) fn write_plan(plan: &[Planned], applied: bool, mut out: impl Write) -> io::Result<()> {
+    for step in plan {
+        match step.skip {
+            Some(reason) => writeln!(out, "skip {}: {}", step.path.display(), reason)?,
+            None => writeln!(
+                out,
+                "{:?} {}\n    keeping {}",
+                step.action,
+                step.path.display(),
+                step.keep.display()
+            )?,
+        }
+    }
+
+    let acted = plan.iter().filter(|step| step.skip.is_none());
+    let (files, bytes) = acted.fold((0, 0), |(files, bytes), step| {
+        (files + 1, bytes + step.size)
+    });
+    if applied {
+        writeln!(out, "Freed {} from {} files", human_size(bytes), files)
+    } else {
+        writeln!(
+            out,
+            "Dry run, would free {} from {} files. Pass --apply to go ahead",
+            human_size(bytes),
+            files
+        )
+    }
+}
+
+/// `.name.dedup-tmp` next to `path`, so renaming it over `path` is atomic
+fn temp_sibling(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".dedup-tmp");
+    path.with_file_name(name)
+}
+
+/// Make the new file next to `path`, then swap it in
+fn replace_with(path: &Path, make: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+    let temp = temp_sibling(path);
+    make(&temp)?;
+    if let Err(err) = fs::rename(&temp, path) {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Renaming doesn't work across filesystems, so fall back to copying
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// The absolute path of `path`, under `quarantine`
+fn quarantine_path(quarantine: &Path, path: &Path) -> PathBuf {
+    quarantine.join(
+        path.components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect::<PathBuf>(),
+    )
+}
+
+/// What undoing `step` will take, known before it's carried out
+fn undo_entry(step: &Planned, quarantine: Option<&Path>) -> UndoEntry {
+    match step.action {
+        Action::Move => {
+            let quarantine = quarantine.expect("Moving needs a quarantine directory");
+            UndoEntry::Moved {
+                path: step.path.clone(),
+                quarantined: quarantine_path(quarantine, &step.path),
+            }
+        }
+        action => UndoEntry::Replaced {
+            action,
+            kept: step.keep.clone(),
+            path: step.path.clone(),
+        },
+    }
+}
+
+fn apply_step(step: &Planned, entry: &UndoEntry) -> io::Result<()> {
+    // The file may have changed since it was hashed
+    if !hashing::same_contents(&step.keep, &step.path)? {
+        return Err(io::Error::other(format!(
+            "no longer the same as {}",
+            step.keep.display()
+        )));
+    }
+
+    match entry {
+        UndoEntry::Replaced {
+            action: Action::Hardlink,
+            ..
+        } => replace_with(&step.path, |temp| fs::hard_link(&step.keep, temp)),
+        UndoEntry::Replaced {
+            action: Action::Reflink,
+            ..
+        } => replace_with(&step.path, |temp| reflink_copy::reflink(&step.keep, temp)),
+        UndoEntry::Replaced { .. } => fs::remove_file(&step.path),
+        UndoEntry::Moved { quarantined, .. } => {
+            if quarantined.exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", quarantined.display()),
+                ));
+            }
+            move_file(&step.path, quarantined)
+        }
+    }
+}
+
+/// Append a line to the manifest and make sure it's on disk
+fn record(manifest: &mut File, entry: &UndoEntry, done: bool) -> io::Result<()> {
+    let line = ManifestLine {
+        entry: entry.clone(),
+        done,
+    };
+    let mut line = serde_json::to_vec(&line)?;
+    line.push(b'\n');
+    manifest.write_all(&line)?;
+    manifest.sync_data()
+}
+
+/// Carry out the plan, recording each step in `manifest` before it's taken.
+/// Returns the errors of the steps that failed, which are left as they were.
+/// Stops early if the manifest can't be written, with that error last.
+pub(crate) fn apply(
+    plan: &[Planned],
+    quarantine: Option<&Path>,
+    manifest_path: &Path,
+) -> io::Result<Vec<io::Error>> {
+    let mut manifest = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(manifest_path)?;
+    let mut errors = vec![];
+    for step in plan.iter().filter(|step| step.skip.is_none()) {
+        let entry = undo_entry(step, quarantine);
+        if let Err(err) = record(&mut manifest, &entry, false) {
+            errors.push(with_path(err, manifest_path));
+            break;
+        }
+        if let Err(err) = apply_step(step, &entry) {
+            errors.push(with_path(err, &step.path));
+        } else if let Err(err) = record(&mut manifest, &entry, true) {
+            // Still pending in the manifest, which undo copes with
+            errors.push(with_path(err, manifest_path));
+            break;
+        }
+    }
+    Ok(errors)
+}
+
+/// Reverse everything in `manifest`, latest first
+pub(crate) fn undo(manifest: &Path) -> io::Result<Vec<io::Error>> {
+    let mut entries: Vec<(UndoEntry, bool)> = vec![];
+    for line in BufReader::new(File::open(manifest)?).lines() {
+        let ManifestLine { entry, done } = serde_json::from_str(&line?)?;
+        match entries.last_mut() {
+            Some((pending, pending_done)) if done && *pending == entry => *pending_done = true,
+            _ => entries.push((entry, done)),
+        }
+    }
+
+    let mut errors = vec![];
+    for (entry, done) in entries.into_iter().rev() {
+        let result = match &entry {
+            // Failed or interrupted before anything changed, so there's
+            // nothing to undo. A file that can't be compared is left alone.
+            UndoEntry::Replaced { kept, path, .. }
+                if !done
+                    && path.exists()
+                    && !hashing::same_contents(kept, path).unwrap_or(false) =>
+            {
+                Ok(())
+            }
+            UndoEntry::Moved { quarantined, .. } if !done && !quarantined.exists() => Ok(()),
+            UndoEntry::Replaced { kept, path, .. } => {
+                replace_with(path, |temp| fs::copy(kept, temp).map(|_| ()))
+                    .map_err(|err| with_path(err, path))
+            }
+            UndoEntry::Moved { path, .. } if path.exists() => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            )),
+            UndoEntry::Moved { path, quarantined } => {
+                move_file(quarantined, path).map_err(|err| with_path(err, quarantined))
+            }
+        };
+        if let Err(err) = result {
+            errors.push(err);
+        }
+    }
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    use super::{
+        apply, choose_keeper, plan, quarantine_path, undo, write_plan, Action, Keep, ManifestLine,
+    };
+    use crate::report::DuplicateGroup;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    /// Three copies, `a/deep/x` being the oldest
+    fn copies(dir: &Path) -> DuplicateGroup {
+        let paths = vec![dir.join("a/deep/x"), dir.join("b/x"), dir.join("c/x")];
+        for (age, path) in paths.iter().rev().enumerate() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "contents").unwrap();
+            let modified = SystemTime::now() - Duration::from_secs(100 * age as u64);
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        }
+        DuplicateGroup {
+            size: 8,
+            hash: "h".into(),
+            paths,
+        }
+    }
+
+    #[test]
+    fn keepers() {
+        let dir = temp_dir("find-duplicate-files-keepers");
+        let group = copies(&dir);
+        let keeper =
+            |keep, preferred: &[PathBuf]| choose_keeper(&group.paths, keep, preferred).unwrap();
+
+        assert_eq!(keeper(Keep::Oldest, &[]), 0);
+        assert_eq!(keeper(Keep::ShortestPath, &[]), 1);
+        assert_eq!(keeper(Keep::PreferredRoot, &[dir.join("c")]), 2);
+        assert_eq!(
+            keeper(Keep::PreferredRoot, &[dir.join("d"), dir.join("a")]),
+            0
+        );
+        assert_eq!(keeper(Keep::PreferredRoot, &[]), 1);
+    }
+
+    #[test]
+    fn dry_run() {
+        let dir = temp_dir("find-duplicate-files-dry-run");
+        let (planned, errors) = plan(&[copies(&dir)], Action::Delete, Keep::Oldest, &[]);
+        assert!(errors.is_empty());
+        assert_eq!(planned.len(), 2);
+        assert!(planned.iter().all(|step| step.keep == dir.join("a/deep/x")));
+
+        let mut out = vec![];
+        write_plan(&planned, false, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with(&format!(
+            "Delete {}\n    keeping {}\n",
+            dir.join("b/x").display(),
+            dir.join("a/deep/x").display()
+        )));
+        assert!(out.ends_with("Dry run, would free 16 B from 2 files. Pass --apply to go ahead\n"));
+        // Planning touches nothing
+        assert!(dir.join("b/x").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hardlink_and_undo() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = temp_dir("find-duplicate-files-hardlink");
+        let group = copies(&dir);
+        let manifest = dir.join("undo.jsonl");
+        let inode = |path: &Path| fs::metadata(path).unwrap().ino();
+
+        let (planned, _) = plan(
+            std::slice::from_ref(&group),
+            Action::Hardlink,
+            Keep::ShortestPath,
+            &[],
+        );
+        assert!(apply(&planned, None, &manifest).unwrap().is_empty());
+        assert_eq!(inode(&dir.join("a/deep/x")), inode(&dir.join("b/x")));
+        assert_eq!(inode(&dir.join("c/x")), inode(&dir.join("b/x")));
+
+        // Running it again has nothing left to do
+        let (planned, _) = plan(&[group], Action::Hardlink, Keep::ShortestPath, &[]);
+        assert!(planned
+            .iter()
+            .all(|step| step.skip == Some("already the same file")));
+
+        assert!(undo(&manifest).unwrap().is_empty());
+        assert_ne!(inode(&dir.join("a/deep/x")), inode(&dir.join("b/x")));
+        assert_eq!(
+            fs::read_to_string(dir.join("a/deep/x")).unwrap(),
+            "contents"
+        );
+    }
+
+    #[rstest::rstest]
+    fn remove_and_undo(#[values(Action::Delete, Action::Move)] action: Action) {
+        let dir = temp_dir(&format!("find-duplicate-files-{:?}", action));
+        let quarantine = dir.join("quarantine");
+        let manifest = dir.join("undo.jsonl");
+        let group = copies(&dir);
+
+        let (planned, _) = plan(std::slice::from_ref(&group), action, Keep::Oldest, &[]);
+        // Changed after hashing, so it's left alone
+        fs::write(dir.join("c/x"), "modified").unwrap();
+        let errors = apply(&planned, Some(&quarantine), &manifest).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("no longer the same"));
+
+        assert!(!dir.join("b/x").exists());
+        assert_eq!(
+            quarantine_path(&quarantine, &dir.join("b/x")).exists(),
+            action == Action::Move
+        );
+        assert_eq!(fs::read_to_string(dir.join("c/x")).unwrap(), "modified");
+
+        assert!(undo(&manifest).unwrap().is_empty());
+        assert_eq!(fs::read_to_string(dir.join("b/x")).unwrap(), "contents");
+        // Only recorded as pending, and undoing it mustn't overwrite the change
+        assert_eq!(fs::read_to_string(dir.join("c/x")).unwrap(), "modified");
+    }
+
+    #[test]
+    fn interrupted() {
+        let dir = temp_dir("find-duplicate-files-interrupted");
+        let manifest = dir.join("undo.jsonl");
+        let group = copies(&dir);
+
+        let (planned, _) = plan(&[group], Action::Delete, Keep::Oldest, &[]);
+        assert!(apply(&planned, None, &manifest).unwrap().is_empty());
+        let lines = fs::read_to_string(&manifest).unwrap();
+        let done = lines
+            .lines()
+            .map(|line| serde_json::from_str::<ManifestLine>(line).unwrap().done)
+            .collect::<Vec<_>>();
+        assert_eq!(done, [false, true, false, true]);
+
+        // As if it stopped after deleting the last file but before saying so
+        let (last_done, _) = lines.trim_end().rsplit_once('\n').unwrap();
+        fs::write(&manifest, format!("{}\n", last_done)).unwrap();
+        assert!(undo(&manifest).unwrap().is_empty());
+        assert_eq!(fs::read_to_string(dir.join("b/x")).unwrap(), "contents");
+        assert_eq!(fs::read_to_string(dir.join("c/x")).unwrap(), "contents");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn manifest_unwritable() {
+        let dir = temp_dir("find-duplicate-files-unwritable");
+        let group = copies(&dir);
+
+        let (planned, _) = plan(&[group], Action::Delete, Keep::Oldest, &[]);
+        let errors = apply(&planned, None, Path::new("/dev/full")).unwrap();
+        assert_eq!(errors.len(), 1);
+        // Nothing happens without a record of it
+        assert!(dir.join("b/x").exists());
+        assert!(dir.join("c/x").exists());
+    }
+}
@@ -0,0 +1,523 @@
+//! Whole copied directory trees, so that a duplicated tree is reported once
+//! instead of as thousands of file pairs.
+//!
+//! Every file is identified by its duplicate group, or as unique if it isn't
+//! in one. A directory's fingerprint is a Merkle hash of its sorted children:
+//! file names with their identity, and subdirectory names with their
+//! fingerprint. Directories with the same fingerprint are identical.
+//!
+//! Directories that aren't identical are compared by the files anywhere
+//! below them, as a multiset of identities weighted by size. One is a subset
+//! of another if all of its files are in the other, and they are similar if
+//! the shared bytes are a large enough part of both.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::hashing::{self, Digest};
+use crate::report::{human_size, lossless_path, lossless_paths, DuplicateGroup};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct IdenticalDirs {
+    /// Of each copy
+    pub size: u64,
+    pub files: usize,
+    pub hash: String,
+    #[serde(with = "lossless_paths")]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct SimilarDirs {
+    /// The smaller of the two
+    #[serde(with = "lossless_path")]
+    pub a: PathBuf,
+    #[serde(with = "lossless_path")]
+    pub b: PathBuf,
+    /// Shared bytes over the bytes in either
+    pub similarity: f64,
+    pub shared: u64,
+    /// Everything in `a` is also in `b`
+    pub subset: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct DirectoryReport {
+    pub identical: Vec<IdenticalDirs>,
+    pub similar: Vec<SimilarDirs>,
+}
+
+#[derive(Default)]
+struct Dir {
+    /// `(name, identity)` of the files directly inside
+    files: Vec<(OsString, usize)>,
+    subdirs: HashSet<PathBuf>,
+    /// How many of each identity are anywhere below
+    contents: HashMap<usize, usize>,
+    bytes: u64,
+    file_count: usize,
+}
+
+/// Directories from each file's parent up to the root it was found in
+fn ancestors<'a>(path: &'a Path, roots: &'a [PathBuf]) -> impl Iterator<Item = &'a Path> {
+    let root = roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count());
+    path.ancestors()
+        .skip(1)
+        .take_while(move |dir| root.is_some_and(|root| dir.starts_with(root)))
+}
+
+fn merkle_hash(dir: &Dir, fingerprints: &HashMap<PathBuf, Digest>) -> Digest {
+    let mut children = dir
+        .files
+        .iter()
+        .map(|(name, identity)| {
+            (
+                name.clone(),
+                b'f',
+                (*identity as u64).to_le_bytes().to_vec(),
+            )
+        })
+        .chain(dir.subdirs.iter().map(|subdir| {
+            (
+                subdir.file_name().unwrap_or_default().to_owned(),
+                b'd',
+                fingerprints[subdir].to_vec(),
+            )
+        }))
+        .collect::<Vec<_>>();
+    children.sort();
+
+    let mut hasher = blake3::Hasher::new();
+    for (name, kind, hash) in children {
+        hasher.update(&[kind]);
+        hasher.update(&(name.len() as u64).to_le_bytes());
+        hasher.update(name.as_encoded_bytes());
+        hasher.update(&hash);
+    }
+    hasher.finalize().into()
+}
+
+/// `files` is every file that was scanned, and `min_similarity` is between 0
+/// and 1
+pub(crate) fn find_duplicate_dirs(
+    roots: &[PathBuf],
+    files: &HashMap<PathBuf, u64>,
+    groups: &[DuplicateGroup],
+    min_similarity: f64,
+) -> DirectoryReport {
+    // Identities below `groups.len()` are duplicate groups, the rest unique
+    let mut identities = HashMap::new();
+    for (i, group) in groups.iter().enumerate() {
+        for path in &group.paths {
+            identities.insert(path.as_path(), i);
+        }
+    }
+    let mut sizes = groups.iter().map(|group| group.size).collect::<Vec<_>>();
+
+    let mut dirs = BTreeMap::<&Path, Dir>::new();
+    for (path, size) in files {
+        let identity = *identities.entry(path.as_path()).or_insert_with(|| {
+            sizes.push(*size);
+            sizes.len() - 1
+        });
+        let mut child = path.as_path();
+        for dir in ancestors(path, roots) {
+            let entry = dirs.entry(dir).or_default();
+            if child == path {
+                let name = path.file_name().unwrap_or_default().to_owned();
+                entry.files.push((name, identity));
+            } else if !entry.subdirs.contains(child) {
+                entry.subdirs.insert(child.to_owned());
+            }
+            *entry.contents.entry(identity).or_default() += 1;
+            entry.bytes += size;
+            entry.file_count += 1;
+            child = dir;
+        }
+    }
+
+    // Children before their parents
+    let mut by_depth = dirs.keys().copied().collect::<Vec<_>>();
+    by_depth.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+    let mut fingerprints = HashMap::new();
+    for dir in by_depth {
+        let hash = merkle_hash(&dirs[dir], &fingerprints);
+        fingerprints.insert(dir.to_owned(), hash);
+    }
+
+    let mut same_fingerprint = HashMap::<Digest, Vec<&Path>>::new();
+    for (dir, hash) in &fingerprints {
+        same_fingerprint.entry(*hash).or_default().push(dir);
+    }
+    let copies = |dir: &Path| &same_fingerprint[&fingerprints[dir]];
+    let in_identical_group = |dir: Option<&Path>| {
+        dir.is_some_and(|dir| fingerprints.contains_key(dir) && copies(dir).len() > 1)
+    };
+    // `outer` has `inner`, or an identical copy of it, somewhere below
+    let contains = |outer: &Path, inner: &Path| {
+        copies(inner)
+            .iter()
+            .any(|copy| copy != &outer && copy.starts_with(outer))
+    };
+
+    let mut report = DirectoryReport::default();
+    for (hash, paths) in &same_fingerprint {
+        // Already reported as part of their parents
+        if paths.len() < 2 || paths.iter().all(|path| in_identical_group(path.parent())) {
+            continue;
+        }
+        let mut paths = paths.clone();
+        paths.sort();
+        let dir = &dirs[paths[0]];
+        report.identical.push(IdenticalDirs {
+            size: dir.bytes,
+            files: dir.file_count,
+            hash: hashing::to_hex(hash),
+            paths: paths.into_iter().map(Path::to_owned).collect(),
+        });
+    }
+    report
+        .identical
+        .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.paths.cmp(&b.paths)));
+
+    // Only directories sharing a duplicate can be similar, and only the first
+    // of identical directories is compared
+    let compared = dirs
+        .iter()
+        .filter(|(dir, _)| copies(dir).iter().min() == Some(dir))
+        .collect::<Vec<_>>();
+    let mut containing = HashMap::<usize, Vec<&Path>>::new();
+    for (dir, contents) in &compared {
+        for identity in contents.contents.keys().filter(|id| **id < groups.len()) {
+            containing.entry(*identity).or_default().push(dir);
+        }
+    }
+
+    // Being similar to `a`, or a superset of it, means sharing at least
+    // `min_similarity` of its bytes, so sharing one of its rarest files
+    // before the rest add up to less than that. Only looking those up keeps
+    // a file that's everywhere from pairing up every directory.
+    let mut pairs = HashSet::new();
+    for (a, contents) in compared {
+        let mut rarest = contents
+            .contents
+            .iter()
+            .map(|(identity, count)| {
+                let dirs_with_identity = containing.get(identity).map_or(&[][..], Vec::as_slice);
+                (dirs_with_identity, *count as u64 * sizes[*identity])
+            })
+            .collect::<Vec<_>>();
+        rarest.sort_by_key(|(dirs_with_identity, _)| dirs_with_identity.len());
+
+        let mut rest = contents.bytes;
+        for (dirs_with_identity, bytes) in rarest {
+            if contents.bytes > 0 && (rest as f64 / contents.bytes as f64) < min_similarity {
+                break;
+            }
+            rest -= bytes;
+            for b in dirs_with_identity {
+                // A copy of a directory inside another is trivially a subset
+                if !a.starts_with(b) && !b.starts_with(a) && !contains(a, b) && !contains(b, a) {
+                    pairs.insert(if a < b { (*a, *b) } else { (*b, *a) });
+                }
+            }
+        }
+    }
+    // Shallowest pairs first, so that pairs inside a reported pair are skipped
+    let mut pairs = pairs.into_iter().collect::<Vec<_>>();
+    pairs.sort_by_key(|(a, b)| (a.components().count() + b.components().count(), *a, *b));
+
+    let mut reported = HashSet::new();
+    for (a, b) in pairs {
+        if let (Some(parent_a), Some(parent_b)) = (a.parent(), b.parent()) {
+            let parents = if parent_a < parent_b {
+                (parent_a, parent_b)
+            } else {
+                (parent_b, parent_a)
+            };
+            if parent_a != parent_b && reported.contains(&parents) {
+                reported.insert((a, b));
+                continue;
+            }
+        }
+
+        let (dir_a, dir_b) = (&dirs[a], &dirs[b]);
+        let shared = dir_a
+            .contents
+            .iter()
+            .filter_map(|(identity, count)| {
+                let other = dir_b.contents.get(identity)?;
+                Some(*count.min(other) as u64 * sizes[*identity])
+            })
+            .sum::<u64>();
+        let union = dir_a.bytes + dir_b.bytes - shared;
+        let similarity = shared as f64 / union.max(1) as f64;
+        let (a, b, smaller) = if dir_a.bytes <= dir_b.bytes {
+            (a, b, dir_a)
+        } else {
+            (b, a, dir_b)
+        };
+        let subset = shared == smaller.bytes;
+
+        if subset || similarity >= min_similarity {
+            reported.insert(if a < b { (a, b) } else { (b, a) });
+            report.similar.push(SimilarDirs {
+                a: a.to_owned(),
+                b: b.to_owned(),
+                similarity,
+                shared,
+                subset,
+            });
+        }
+    }
+
+    // Of the directories something is a subset of, only the innermost
+    let supersets = report
+        .similar
+        .iter()
+        .filter(|dirs| dirs.subset)
+        .map(|dirs| (dirs.a.clone(), dirs.b.clone()))
+        .collect::<Vec<_>>();
+    report.similar.retain(|dirs| {
+        !dirs.subset
+            || !supersets
+                .iter()
+                .any(|(a, b)| a == &dirs.a && contains(&dirs.b, b))
+    });
+    report.similar.sort_by(|x, y| {
+        y.shared
+            .cmp(&x.shared)
+            .then_with(|| (&x.a, &x.b).cmp(&(&y.a, &y.b)))
+    });
+    report
+}
+
+/// File groups with a copy outside the identical directories, the rest
+/// being covered by the directory report
+pub(crate) fn uncovered_groups(
+    report: &DirectoryReport,
+    groups: &[DuplicateGroup],
+) -> Vec<DuplicateGroup> {
+    let identical = report
+        .identical
+        .iter()
+        .flat_map(|dirs| &dirs.paths)
+        .collect::<Vec<_>>();
+    groups
+        .iter()
+        .filter(|group| {
+            !group
+                .paths
+                .iter()
+                .all(|path| identical.iter().any(|dir| path.starts_with(dir)))
+        })
+        .cloned()
+        .collect()
+}
+
+pub(crate) fn write_table(report: &DirectoryReport, mut out: impl Write) -> io::Result<()> {
+    for dirs in &report.identical {
+        writeln!(
+            out,
+            "{} identical directories of {} in {} files ({})",
+            dirs.paths.len(),
+            human_size(dirs.size),
+            dirs.files,
+            dirs.hash
+        )?;
+        for path in &dirs.paths {
+            writeln!(out, "  {}", path.display())?;
+        }
+    }
+    for dirs in &report.similar {
+        if dirs.subset {
+            write!(out, "{} is a subset of", dirs.a.display())?;
+        } else {
+            write!(
+                out,
+                "{:.0}% similar: {} and",
+                dirs.similarity * 100.0,
+                dirs.a.display()
+            )?;
+        }
+        writeln!(
+            out,
+            " {} ({} shared)",
+            dirs.b.display(),
+            human_size(dirs.shared)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
+
+    use itertools::Itertools;
+
+    use super::{find_duplicate_dirs, uncovered_groups};
+    use crate::report::DuplicateGroup;
+
+    /// Files given as `(path, contents)`, with groups made from the contents
+    fn scanned(files: &[(&str, &str)]) -> (HashMap<PathBuf, u64>, Vec<DuplicateGroup>) {
+        let sizes = files
+            .iter()
+            .map(|(path, contents)| (PathBuf::from(path), contents.len() as u64))
+            .collect();
+        let groups = files
+            .iter()
+            .map(|(path, contents)| (*contents, PathBuf::from(path)))
+            .into_group_map()
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|(contents, mut paths)| {
+                paths.sort();
+                DuplicateGroup {
+                    size: contents.len() as u64,
+                    hash: contents.into(),
+                    paths,
+                }
+            })
+            .sorted_by(|a, b| a.hash.cmp(&b.hash))
+            .collect();
+        (sizes, groups)
+    }
+
+    #[test]
+    fn identical_trees_are_reported_once() {
+        let (files, groups) = scanned(&[
+            ("/r/photos/2020/a.jpg", "aaaa"),
+            ("/r/photos/2020/b.jpg", "bbbbbb"),
+            ("/r/photos/2021/c.jpg", "cc"),
+            ("/r/backup/photos/2020/a.jpg", "aaaa"),
+            ("/r/backup/photos/2020/b.jpg", "bbbbbb"),
+            ("/r/backup/photos/2021/c.jpg", "cc"),
+            ("/r/other/a.jpg", "aaaa"),
+        ]);
+        let report = find_duplicate_dirs(&[PathBuf::from("/r")], &files, &groups, 0.8);
+
+        assert_eq!(report.identical.len(), 1);
+        assert_eq!(
+            report.identical[0].paths,
+            vec![
+                PathBuf::from("/r/backup/photos"),
+                PathBuf::from("/r/photos")
+            ]
+        );
+        assert_eq!(
+            (report.identical[0].size, report.identical[0].files),
+            (12, 3)
+        );
+
+        // Only against the first copy of `2020`, not either copy of `photos`
+        // or `backup`, which contain it
+        assert_eq!(report.similar.len(), 1);
+        assert_eq!(report.similar[0].a, PathBuf::from("/r/other"));
+        assert_eq!(report.similar[0].b, PathBuf::from("/r/backup/photos/2020"));
+        assert!(report.similar[0].subset);
+
+        // Only `a.jpg` has a copy outside the identical directories
+        let uncovered = uncovered_groups(&report, &groups);
+        assert_eq!(uncovered.len(), 1);
+        assert_eq!(uncovered[0].hash, "aaaa");
+    }
+
+    #[test]
+    fn names_matter_for_identical() {
+        let (files, groups) = scanned(&[("/r/a/x", "same"), ("/r/b/y", "same")]);
+        let report = find_duplicate_dirs(&[PathBuf::from("/r")], &files, &groups, 0.8);
+        assert!(report.identical.is_empty());
+        // But the contents are the same
+        assert_eq!(report.similar.len(), 1);
+        assert_eq!(report.similar[0].similarity, 1.0);
+    }
+
+    #[test]
+    fn subsets_and_near_matches() {
+        let (files, groups) = scanned(&[
+            ("/r/full/a", "aaaaaaaaaa"),
+            ("/r/full/b", "bbbbbbbbbb"),
+            ("/r/full/c", "c"),
+            ("/r/part/a", "aaaaaaaaaa"),
+            ("/r/near/a", "aaaaaaaaaa"),
+            ("/r/near/b", "bbbbbbbbbb"),
+            ("/r/near/d", "dd"),
+        ]);
+        let report = find_duplicate_dirs(&[PathBuf::from("/r")], &files, &groups, 0.8);
+        let similar = report
+            .similar
+            .iter()
+            .map(|dirs| (dirs.a.as_path(), dirs.b.as_path(), dirs.subset))
+            .collect_vec();
+
+        let (full, part, near) = (
+            Path::new("/r/full"),
+            Path::new("/r/part"),
+            Path::new("/r/near"),
+        );
+        // 20 shared bytes out of 23
+        assert_eq!(similar[0], (full, near, false));
+        assert!((report.similar[0].similarity - 20.0 / 23.0).abs() < 1e-9);
+        assert!(similar.contains(&(part, full, true)));
+        assert!(similar.contains(&(part, near, true)));
+
+        let report = find_duplicate_dirs(&[PathBuf::from("/r")], &files, &groups, 0.9);
+        assert!(report.similar.iter().all(|dirs| dirs.subset));
+    }
+
+    #[test]
+    fn nested_similar_pairs_are_skipped() {
+        let (files, groups) = scanned(&[
+            ("/r/a/sub/x", "xxxx"),
+            ("/r/a/sub/y", "yyyy"),
+            ("/r/a/z", "z"),
+            ("/r/b/sub/x", "xxxx"),
+            ("/r/b/sub/y", "yyyy"),
+            ("/r/b/w", "w"),
+        ]);
+        let report = find_duplicate_dirs(&[PathBuf::from("/r")], &files, &groups, 0.5);
+        assert_eq!(report.identical.len(), 1);
+        assert_eq!(report.similar.len(), 1);
+        assert_eq!(report.similar[0].a, PathBuf::from("/r/a"));
+    }
+
+    #[test]
+    fn shared_file_everywhere() {
+        // Every package has the same licence, but mostly its own code
+        let packages = (0..20_000)
+            .map(|i| {
+                (
+                    format!("/r/package{}/LICENSE", i),
+                    format!("/r/package{}/main.rs", i),
+                    format!("code {:015}", i),
+                )
+            })
+            .collect_vec();
+        let files = packages
+            .iter()
+            .flat_map(|(licence, main, code)| {
+                [
+                    (licence.as_str(), "licence"),
+                    (main.as_str(), code.as_str()),
+                ]
+            })
+            .collect_vec();
+        let (files, groups) = scanned(&files);
+
+        // Comparing every pair would take hours
+        let start = Instant::now();
+        let report = find_duplicate_dirs(&[PathBuf::from("/r")], &files, &groups, 0.5);
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert!(report.identical.is_empty());
+        assert!(report.similar.is_empty());
+    }
+}
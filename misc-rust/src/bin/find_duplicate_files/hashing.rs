@@ -0,0 +1,134 @@
+//! Each stage is more expensive than the last, so only files that still
+//! collide go through to the next one:
+//!
+//! 1. Files of different sizes can't be duplicates, and sizes are free.
+//! 2. A hash of the first and last few KB weeds out most of the rest, like
+//!    files sharing a format's header but not its contents.
+//! 3. A full BLAKE3 hash, streamed so huge files don't need to fit in memory.
+//! 4. Optionally comparing the bytes themselves, for those who don't trust
+//!    hashes.
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// How much of each end of the file the partial hash covers
+pub(crate) const PARTIAL_LEN: u64 = 4 * 1024;
+const BUFFER_LEN: usize = 64 * 1024;
+
+pub(crate) type Digest = [u8; 32];
+
+/// BLAKE3 of the first and last `PARTIAL_LEN` bytes. Files up to twice that
+/// are hashed whole, so the partial hash is also the full hash.
+pub(crate) fn partial_hash(path: &Path, size: u64) -> io::Result<Digest> {
+    if is_fully_hashed_by_partial(size) {
+        return full_hash(path);
+    }
+
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = [0; PARTIAL_LEN as usize];
+    file.read_exact(&mut buffer)?;
+    hasher.update(&buffer);
+    file.seek(SeekFrom::End(-(PARTIAL_LEN as i64)))?;
+    file.read_exact(&mut buffer)?;
+    hasher.update(&buffer);
+    Ok(hasher.finalize().into())
+}
+
+pub(crate) fn is_fully_hashed_by_partial(size: u64) -> bool {
+    size <= 2 * PARTIAL_LEN
+}
+
+/// How many bytes `partial_hash` reads
+pub(crate) fn partial_len(size: u64) -> u64 {
+    size.min(2 * PARTIAL_LEN)
+}
+
+pub(crate) fn full_hash(path: &Path) -> io::Result<Digest> {
+    let mut hasher = blake3::Hasher::new();
+    io::copy(
+        &mut BufReader::with_capacity(BUFFER_LEN, File::open(path)?),
+        &mut hasher,
+    )?;
+    Ok(hasher.finalize().into())
+}
+
+pub(crate) fn to_hex(digest: &Digest) -> String {
+    blake3::Hash::from(*digest).to_hex().to_string()
+}
+
+/// Compare two files byte by byte
+pub(crate) fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let (a, b) = (File::open(a)?, File::open(b)?);
+    if a.metadata()?.len() != b.metadata()?.len() {
+        return Ok(false);
+    }
+
+    let mut a = BufReader::with_capacity(BUFFER_LEN, a);
+    let mut b = BufReader::with_capacity(BUFFER_LEN, b);
+    let mut other = vec![0; BUFFER_LEN];
+    loop {
+        let chunk = a.fill_buf()?;
+        if chunk.is_empty() {
+            return Ok(b.fill_buf()?.is_empty());
+        }
+        let len = chunk.len();
+        let other = &mut other[..len];
+        // The other file may have changed size since the check above
+        if let Err(err) = b.read_exact(other) {
+            return match err.kind() {
+                io::ErrorKind::UnexpectedEof => Ok(false),
+                _ => Err(err),
+            };
+        }
+        if chunk != other {
+            return Ok(false);
+        }
+        a.consume(len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{full_hash, partial_hash, partial_len, same_contents, PARTIAL_LEN};
+
+    #[test]
+    fn stages() {
+        let dir = std::env::temp_dir().join("find-duplicate-files-hashing");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // Same ends, different middles
+        let len = 3 * PARTIAL_LEN as usize;
+        let a = vec![1_u8; len];
+        let mut b = a.clone();
+        b[len / 2] = 2;
+        let (a_path, b_path, c_path) = (dir.join("a"), dir.join("b"), dir.join("c"));
+        fs::write(&a_path, &a).unwrap();
+        fs::write(&b_path, &b).unwrap();
+        fs::write(&c_path, &a).unwrap();
+
+        let size = len as u64;
+        assert_eq!(
+            partial_hash(&a_path, size).unwrap(),
+            partial_hash(&b_path, size).unwrap()
+        );
+        assert_ne!(full_hash(&a_path).unwrap(), full_hash(&b_path).unwrap());
+        assert_eq!(full_hash(&a_path).unwrap(), full_hash(&c_path).unwrap());
+        assert_eq!(partial_len(size), 2 * PARTIAL_LEN);
+
+        assert!(same_contents(&a_path, &c_path).unwrap());
+        assert!(!same_contents(&a_path, &b_path).unwrap());
+
+        // Small files are hashed whole either way
+        let small = dir.join("small");
+        fs::write(&small, b"hello").unwrap();
+        assert_eq!(
+            partial_hash(&small, 5).unwrap(),
+            *blake3::hash(b"hello").as_bytes()
+        );
+        assert_eq!(partial_len(5), 5);
+    }
+}
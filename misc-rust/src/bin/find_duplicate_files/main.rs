@@ -0,0 +1,615 @@
+use std::collections::HashMap;
+use std::fs::{self, DirEntry, File};
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use clap::Parser;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use itertools::Itertools;
+
+mod cache;
+mod dedup;
+mod directories;
+mod hashing;
+mod pipeline;
+mod report;
+
+use cache::{cached, HashCache, Stage};
+use dedup::{Action, Keep};
+use pipeline::hash_files;
+use report::{DuplicateGroup, Format};
+
+/// Find files with identical contents
+#[derive(Parser, Debug)]
+struct Args {
+    /// Directories to search. Files reachable from more than one are only
+    /// counted once
+    #[arg(required_unless_present = "undo")]
+    roots: Vec<PathBuf>,
+    /// Only look at files matching one of these globs, e.g. `**/*.jpg`.
+    /// Globs match the path relative to its root
+    #[arg(short, long)]
+    include: Vec<String>,
+    /// Skip files and whole directories matching these globs, e.g.
+    /// `**/.git`
+    #[arg(short, long)]
+    exclude: Vec<String>,
+    /// Ignore files smaller than this many bytes. Empty files are all
+    /// "duplicates" of each other, so they're skipped by default
+    #[arg(long, default_value_t = 1)]
+    min_size: u64,
+    /// Where to write the report, stdout if not given
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    #[arg(short, long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+    /// Compare files with the same hash byte by byte before reporting them
+    #[arg(long)]
+    verify: bool,
+    /// Also report identical directory trees, and directories that are
+    /// subsets of or similar to each other. Files inside identical
+    /// directories are then left out of the file report
+    #[arg(long)]
+    dirs: bool,
+    /// How much of two directories' bytes must be shared for them to be
+    /// reported as similar, between 0 and 1
+    #[arg(long, default_value_t = 0.8, requires = "dirs")]
+    similarity: f64,
+    /// Threads hashing files, defaults to the number of CPUs
+    #[arg(short, long)]
+    workers: Option<usize>,
+    /// Keep hashes in this file, so an interrupted scan can pick up where it
+    /// left off, and re-scans only hash files whose size or modification time
+    /// changed
+    #[arg(long)]
+    cache: Option<PathBuf>,
+    /// What to do with all but one file of each group. Only prints what
+    /// would be done unless `--apply` is given
+    #[arg(short, long, value_enum)]
+    action: Option<Action>,
+    /// Which file of each group to keep
+    #[arg(long, value_enum, default_value_t = Keep::Oldest)]
+    keep: Keep,
+    /// Roots to keep files from with `--keep preferred-root`, most preferred
+    /// first
+    #[arg(long, required_if_eq("keep", "preferred-root"))]
+    prefer: Vec<PathBuf>,
+    /// Where `--action move` puts files
+    #[arg(long, required_if_eq("action", "move"))]
+    quarantine: Option<PathBuf>,
+    /// Actually carry out the action, recording what was done in the
+    /// manifest
+    #[arg(long, requires_all = ["action", "manifest"])]
+    apply: bool,
+    /// Undo manifest written by `--apply`
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+    /// Undo everything recorded in this manifest, instead of scanning
+    #[arg(long, conflicts_with_all = ["roots", "apply"])]
+    undo: Option<PathBuf>,
+}
+
+/// Which files and directories to look at, from the command line arguments
+struct Filter {
+    /// `None` to include everything
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    min_size: u64,
+}
+
+impl Filter {
+    fn new(include: &[String], exclude: &[String], min_size: u64) -> Result<Self, globset::Error> {
+        let build = |globs: &[String]| {
+            let mut set = GlobSetBuilder::new();
+            for glob in globs {
+                set.add(Glob::new(glob)?);
+            }
+            set.build()
+        };
+
+        Ok(Self {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(build(include)?)
+            },
+            exclude: build(exclude)?,
+            min_size,
+        })
+    }
+
+    /// `relative` is relative to the root being scanned
+    fn wants_dir(&self, relative: &Path) -> bool {
+        !self.exclude.is_match(relative)
+    }
+
+    fn wants_file(&self, relative: &Path, len: u64) -> bool {
+        len >= self.min_size
+            && !self.exclude.is_match(relative)
+            && self
+                .include
+                .as_ref()
+                .is_none_or(|include| include.is_match(relative))
+    }
+}
+
+fn visit_dirs(
+    dir: &Path,
+    wants_dir: &dyn Fn(&Path) -> bool,
+    cb: &mut dyn FnMut(&DirEntry) -> io::Result<()>,
+) -> Vec<io::Error> {
+    return _visit_dirs(dir, wants_dir, cb, vec![]);
+}
+
+fn _visit_dirs(
+    dir: &Path,
+    wants_dir: &dyn Fn(&Path) -> bool,
+    cb: &mut dyn FnMut(&DirEntry) -> io::Result<()>,
+    mut errors: Vec<io::Error>,
+) -> Vec<io::Error> {
+    if dir.is_dir() {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                errors.push(with_path(err, dir));
+                return errors;
+            }
+        };
+        let mut entries = entries
+            .filter_map(|entry| match entry {
+                Ok(entry) => Some(entry),
+                Err(err) => {
+                    errors.push(with_path(err, dir));
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let path = entry.path();
+            if path.is_symlink() {
+                continue;
+            } else if path.is_dir() {
+                if wants_dir(&path) {
+                    errors = _visit_dirs(&path, wants_dir, cb, errors);
+                }
+            } else {
+                let Err(err) = cb(&entry) else { continue };
+                errors.push(with_path(err, &path));
+            }
+        }
+    }
+    return errors;
+}
+
+/// `io::Error`s don't say which file they're about
+pub(crate) fn with_path(err: io::Error, path: &Path) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
+/// Every file under `root` that passes the filter, with its size
+fn walk(root: &Path, filter: &Filter) -> (Vec<(PathBuf, u64)>, Vec<io::Error>) {
+    let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path).to_owned();
+    let mut files = vec![];
+    let errors = visit_dirs(root, &|dir| filter.wants_dir(&relative(dir)), &mut |file| {
+        let path = file.path();
+        let len = file.metadata()?.len();
+        if filter.wants_file(&relative(&path), len) {
+            files.push((path, len));
+        }
+        Ok(())
+    });
+    (files, errors)
+}
+
+/// Only the groups of more than one value
+fn collisions<K: Hash + Eq, V>(items: impl IntoIterator<Item = (K, V)>) -> Vec<(K, Vec<V>)> {
+    items
+        .into_iter()
+        .into_group_map()
+        .into_iter()
+        .filter(|(_, values)| values.len() > 1)
+        .collect()
+}
+
+fn report_errors(errors: Vec<io::Error>) {
+    for err in errors {
+        eprintln!("{}", err);
+    }
+}
+
+/// Every file under any of `roots`, with its size. Keyed by path, so
+/// overlapping roots don't make a file its own duplicate
+fn walk_roots(roots: &[PathBuf], filter: &Filter) -> HashMap<PathBuf, u64> {
+    let mut files = HashMap::new();
+    for root in roots {
+        let (found, errors) = walk(root, filter);
+        report_errors(errors);
+        files.extend(found);
+    }
+    files
+}
+
+/// See `hashing` for the stages. With `verify`, files with the same hash
+/// are also compared byte by byte.
+fn find_duplicates(
+    files: &HashMap<PathBuf, u64>,
+    verify: bool,
+    workers: usize,
+    cache: &Mutex<HashCache>,
+) -> Vec<DuplicateGroup> {
+    let show_progress = |progress: &pipeline::Progress| eprintln!("{}", progress);
+
+    let total = files.len();
+
+    let same_size = collisions(files.iter().map(|(path, len)| (*len, path.clone())))
+        .into_iter()
+        .flat_map(|(len, paths)| paths.into_iter().map(move |path| (path, len)))
+        .collect_vec();
+    let same_size_count = same_size.len();
+
+    let (partial, errors) = hash_files(
+        same_size,
+        workers,
+        "partial hash",
+        cached(cache, Stage::Partial, hashing::partial_hash),
+        hashing::partial_len,
+        show_progress,
+    );
+    report_errors(errors);
+    let same_partial = collisions(
+        partial
+            .into_iter()
+            .map(|(path, len, digest)| ((len, digest), path)),
+    );
+    eprintln!(
+        "{} files, {} share a size, {} share a partial hash",
+        total,
+        same_size_count,
+        same_partial
+            .iter()
+            .map(|(_, paths)| paths.len())
+            .sum::<usize>()
+    );
+
+    // The partial hash of small files already covers the whole file
+    let (done, to_hash): (Vec<_>, Vec<_>) = same_partial
+        .into_iter()
+        .partition(|((len, _), _)| hashing::is_fully_hashed_by_partial(*len));
+    let (full, errors) = hash_files(
+        to_hash
+            .into_iter()
+            .flat_map(|((len, _), paths)| paths.into_iter().map(move |path| (path, len)))
+            .collect(),
+        workers,
+        "full hash",
+        cached(cache, Stage::Full, |path, _| hashing::full_hash(path)),
+        |len| len,
+        show_progress,
+    );
+    report_errors(errors);
+    let same_full = collisions(
+        full.into_iter()
+            .map(|(path, len, digest)| ((len, digest), path)),
+    );
+
+    let mut groups = done
+        .into_iter()
+        .chain(same_full)
+        .flat_map(|((size, digest), paths)| {
+            let hash = hashing::to_hex(&digest);
+            let identical = if verify {
+                split_identical(paths)
+            } else {
+                vec![paths]
+            };
+            identical
+                .into_iter()
+                .filter(|paths| paths.len() > 1)
+                .map(move |paths| DuplicateGroup {
+                    size,
+                    hash: hash.clone(),
+                    paths,
+                })
+        })
+        .collect_vec();
+    report::sort_groups(&mut groups);
+    groups
+}
+
+/// Group files by comparing their bytes. Files with the same hash will
+/// practically always end up in one group.
+fn split_identical(paths: Vec<PathBuf>) -> Vec<Vec<PathBuf>> {
+    let mut groups: Vec<Vec<PathBuf>> = vec![];
+    for path in paths {
+        let mut matched = None;
+        for (i, group) in groups.iter().enumerate() {
+            match hashing::same_contents(&group[0], &path) {
+                Ok(true) => {
+                    matched = Some(i);
+                    break;
+                }
+                Ok(false) => {}
+                Err(err) => eprintln!("{}", with_path(err, &path)),
+            }
+        }
+        match matched {
+            Some(i) => groups[i].push(path),
+            None => groups.push(vec![path]),
+        }
+    }
+    groups
+}
+
+fn canonical_dirs(dirs: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    dirs.iter()
+        .map(|dir| match dir.canonicalize() {
+            Ok(dir) if dir.is_dir() => Ok(dir),
+            Ok(_) => Err(format!("{} is not a directory", dir.display())),
+            Err(err) => Err(format!("{}: {}", dir.display(), err)),
+        })
+        .collect()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    if let Some(manifest) = &args.undo {
+        report_errors(dedup::undo(manifest)?);
+        return Ok(());
+    }
+
+    let filter = Filter::new(&args.include, &args.exclude, args.min_size)?;
+    let roots = canonical_dirs(&args.roots)?;
+    let preferred = canonical_dirs(&args.prefer)?;
+
+    let cache = match &args.cache {
+        Some(path) => {
+            let cache = HashCache::open(path)?;
+            eprintln!("Loaded {} cached hashes", cache.len());
+            cache
+        }
+        None => HashCache::in_memory(),
+    };
+    let cache = Mutex::new(cache);
+    let workers = args.workers.unwrap_or_else(|| {
+        std::thread::available_parallelism().map_or(4, std::num::NonZeroUsize::get)
+    });
+    if args.dirs && args.format == Format::Csv {
+        return Err("--dirs can't be written as CSV".into());
+    }
+    let files = walk_roots(&roots, &filter);
+    let groups = find_duplicates(&files, args.verify, workers, &cache);
+
+    let mut cache = cache.into_inner().unwrap();
+    if args.cache.is_some() {
+        eprintln!("Reused {} cached hashes", cache.hits);
+    }
+    cache.compact(|path| files.contains_key(path))?;
+    let mut out: Box<dyn io::Write> = match &args.output {
+        Some(path) => Box::new(io::BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    };
+    if args.dirs {
+        let directories =
+            directories::find_duplicate_dirs(&roots, &files, &groups, args.similarity);
+        let uncovered = directories::uncovered_groups(&directories, &groups);
+        match args.format {
+            Format::Json => {
+                let report = serde_json::json!({ "directories": directories, "files": uncovered });
+                serde_json::to_writer_pretty(&mut out, &report)?;
+                writeln!(out)?;
+            }
+            _ => {
+                directories::write_table(&directories, &mut out)?;
+                report::write_report(&uncovered, args.format, &mut out)?;
+            }
+        }
+    } else {
+        report::write_report(&groups, args.format, &mut out)?;
+    }
+    out.flush()?;
+
+    if let Some(action) = args.action {
+        let (plan, errors) = dedup::plan(&groups, action, args.keep, &preferred);
+        report_errors(errors);
+        if let (true, Some(manifest)) = (args.apply, &args.manifest) {
+            let quarantine = args.quarantine.as_deref();
+            report_errors(dedup::apply(&plan, quarantine, manifest)?);
+        }
+        dedup::write_plan(&plan, args.apply, io::stderr().lock())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use clap::Parser;
+    use itertools::Itertools;
+
+    use std::sync::Mutex;
+
+    use super::{find_duplicates, walk_roots, Action, Args, Filter, Keep};
+    use crate::cache::HashCache;
+    use crate::hashing::PARTIAL_LEN;
+    use crate::report::Format;
+
+    /// A fresh directory with `files` written into it
+    fn temp_tree(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn parse_args() {
+        let args = Args::parse_from([
+            "find_duplicate_files",
+            "a",
+            "b",
+            "-e",
+            "**/.git",
+            "--min-size",
+            "10",
+            "-f",
+            "csv",
+        ]);
+        assert_eq!(args.roots, vec![PathBuf::from("a"), PathBuf::from("b")]);
+        assert_eq!(args.exclude, vec!["**/.git"]);
+        assert_eq!(args.min_size, 10);
+        assert_eq!(args.format, Format::Csv);
+        assert_eq!(args.output, None);
+        assert!(!args.dirs);
+
+        // Similarity only means something for directories
+        assert!(
+            Args::try_parse_from(["find_duplicate_files", "a", "--similarity", "0.5"]).is_err()
+        );
+        let args =
+            Args::try_parse_from(["find_duplicate_files", "a", "--dirs", "--similarity", "0.5"])
+                .unwrap();
+        assert_eq!((args.dirs, args.similarity), (true, 0.5));
+
+        assert!(Args::try_parse_from(["find_duplicate_files"]).is_err());
+        assert!(Args::try_parse_from(["find_duplicate_files", "--undo", "undo.jsonl"]).is_ok());
+        // Moving needs somewhere to move to, and applying needs a manifest
+        assert!(Args::try_parse_from(["find_duplicate_files", "a", "--action", "move"]).is_err());
+        assert!(
+            Args::try_parse_from(["find_duplicate_files", "a", "-a", "delete", "--apply"]).is_err()
+        );
+        let args = Args::try_parse_from([
+            "find_duplicate_files",
+            "a",
+            "-a",
+            "hardlink",
+            "--keep",
+            "preferred-root",
+            "--prefer",
+            "a/originals",
+        ])
+        .unwrap();
+        assert_eq!(args.action, Some(Action::Hardlink));
+        assert_eq!(args.keep, Keep::PreferredRoot);
+        assert!(!args.apply);
+    }
+
+    #[test]
+    fn filters() {
+        let filter = Filter::new(&["**/*.jpg".into()], &["**/.git".into()], 2).unwrap();
+        assert!(filter.wants_file(Path::new("a/b.jpg"), 2));
+        assert!(!filter.wants_file(Path::new("a/b.jpg"), 1));
+        assert!(!filter.wants_file(Path::new("a/b.png"), 2));
+        assert!(!filter.wants_dir(Path::new("a/.git")));
+        assert!(filter.wants_dir(Path::new("a/git")));
+
+        assert!(Filter::new(&["[".into()], &[], 0).is_err());
+    }
+
+    #[test]
+    fn finds_duplicates_across_roots() {
+        let dir = temp_tree(
+            "find-duplicate-files-roots",
+            &[
+                ("one/a.txt", "same"),
+                ("one/b.txt", "different"),
+                ("one/.git/c.txt", "same"),
+                ("two/nested/a.txt", "same"),
+                ("two/empty.txt", ""),
+                ("two/empty2.txt", ""),
+            ],
+        );
+        let filter = Filter::new(&[], &["**/.git".into()], 1).unwrap();
+        // `one` is listed twice, which mustn't make a.txt its own duplicate
+        let roots = [dir.join("one"), dir.join("two"), dir.join("one")];
+
+        let groups = find_duplicates(
+            &walk_roots(&roots, &filter),
+            false,
+            2,
+            &Mutex::new(HashCache::in_memory()),
+        );
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].size, 4);
+        assert_eq!(
+            groups[0].paths,
+            vec![dir.join("one/a.txt"), dir.join("two/nested/a.txt")]
+        );
+    }
+
+    #[rstest::rstest]
+    fn staged(#[values(false, true)] verify: bool) {
+        // Big enough that the partial hash doesn't cover the middle
+        let big = "x".repeat(3 * PARTIAL_LEN as usize);
+        let mut changed_middle = big.clone();
+        changed_middle.replace_range(big.len() / 2..big.len() / 2 + 1, "y");
+        let dir = temp_tree(
+            "find-duplicate-files-staged",
+            &[
+                ("big", &big),
+                ("big copy", &big),
+                ("changed middle", &changed_middle),
+                ("small", "abc"),
+                ("small copy", "abc"),
+                ("same size", "abd"),
+            ],
+        );
+        let filter = Filter::new(&[], &[], 1).unwrap();
+
+        let groups = find_duplicates(
+            &walk_roots(std::slice::from_ref(&dir), &filter),
+            verify,
+            2,
+            &Mutex::new(HashCache::in_memory()),
+        );
+        let paths = groups.iter().map(|group| group.paths.clone()).collect_vec();
+        assert_eq!(
+            paths,
+            vec![
+                vec![dir.join("big"), dir.join("big copy")],
+                vec![dir.join("small"), dir.join("small copy")],
+            ]
+        );
+        assert_eq!(groups[1].hash, blake3::hash(b"abc").to_hex().as_str());
+    }
+
+    #[test]
+    fn rescans_are_incremental() {
+        let dir = temp_tree(
+            "find-duplicate-files-incremental",
+            &[("a", "same"), ("b", "same"), ("c", "other"), ("d", "other")],
+        );
+        let cache_path = std::env::temp_dir().join("find-duplicate-files-incremental.jsonl");
+        let _ = fs::remove_file(&cache_path);
+        let filter = Filter::new(&[], &[], 1).unwrap();
+        let scan = || {
+            let cache = Mutex::new(HashCache::open(&cache_path).unwrap());
+            let files = walk_roots(std::slice::from_ref(&dir), &filter);
+            let groups = find_duplicates(&files, false, 2, &cache);
+            let mut cache = cache.into_inner().unwrap();
+            cache.compact(|path| files.contains_key(path)).unwrap();
+            (groups.len(), cache.hits)
+        };
+
+        assert_eq!(scan(), (2, 0));
+        assert_eq!(scan(), (2, 4));
+        fs::write(dir.join("d"), "changed").unwrap();
+        assert_eq!(scan(), (1, 2));
+        // Files this small only need their partial hash
+        assert_eq!(HashCache::open(&cache_path).unwrap().len(), 4);
+
+        // Deleted files are dropped from the cache
+        fs::remove_file(dir.join("a")).unwrap();
+        assert_eq!(scan(), (0, 0));
+        assert_eq!(HashCache::open(&cache_path).unwrap().len(), 3);
+    }
+}
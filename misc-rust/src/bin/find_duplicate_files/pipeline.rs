@@ -0,0 +1,210 @@
+//! Hashing files on a pool of workers. Files are fed through a bounded
+//! channel, so the feeder blocks instead of queueing up every path when the
+//! workers fall behind, and results come back through another one. Nothing
+//! polls: workers block on the channel until there's work or it's closed.
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{bounded, RecvTimeoutError};
+
+use crate::report::human_size;
+use crate::with_path;
+
+const PROGRESS_EVERY: Duration = Duration::from_secs(1);
+/// Files queued per worker
+const QUEUE_PER_WORKER: usize = 16;
+
+/// Counts for one stage of hashing, for reporting rates and how long is left
+#[derive(Debug, Clone)]
+pub(crate) struct Progress {
+    pub stage: &'static str,
+    pub files: usize,
+    pub bytes: u64,
+    pub total_files: usize,
+    pub total_bytes: u64,
+    pub start: Instant,
+}
+
+impl Progress {
+    /// `read` is how many bytes of a file of each length the stage reads
+    fn new(stage: &'static str, files: &[(PathBuf, u64)], read: impl Fn(u64) -> u64) -> Self {
+        Self {
+            stage,
+            files: 0,
+            bytes: 0,
+            total_files: files.len(),
+            total_bytes: files.iter().map(|(_, len)| read(*len)).sum(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Based on the bytes left, as those dominate for big files
+    pub fn eta(&self) -> Option<Duration> {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        if self.bytes == 0 || elapsed == 0.0 {
+            return None;
+        }
+        let rate = self.bytes as f64 / elapsed;
+        Some(Duration::from_secs_f64(
+            (self.total_bytes - self.bytes) as f64 / rate,
+        ))
+    }
+}
+
+impl std::fmt::Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let elapsed = self.start.elapsed().as_secs_f64().max(1e-9);
+        write!(
+            f,
+            "{}: {}/{} files, {}/{}, {:.1} files/s, {}/s",
+            self.stage,
+            self.files,
+            self.total_files,
+            human_size(self.bytes),
+            human_size(self.total_bytes),
+            self.files as f64 / elapsed,
+            human_size((self.bytes as f64 / elapsed) as u64),
+        )?;
+        if let Some(eta) = self.eta() {
+            write!(f, ", ETA {}s", eta.as_secs())?;
+        }
+        Ok(())
+    }
+}
+
+/// Run `hash` over every file on `workers` threads, calling `on_progress`
+/// about once a second. `read` is how many bytes `hash` reads of a file of
+/// each length, for the rates and ETA.
+pub(crate) fn hash_files<H: Send>(
+    files: Vec<(PathBuf, u64)>,
+    workers: usize,
+    stage: &'static str,
+    hash: impl Fn(&Path, u64) -> io::Result<H> + Sync,
+    read: impl Fn(u64) -> u64,
+    mut on_progress: impl FnMut(&Progress),
+) -> (Vec<(PathBuf, u64, H)>, Vec<io::Error>) {
+    let workers = workers.max(1);
+    let mut progress = Progress::new(stage, &files, &read);
+    let mut results = Vec::with_capacity(files.len());
+    let mut errors = vec![];
+
+    std::thread::scope(|s| {
+        let (work_tx, work_rx) = bounded::<(PathBuf, u64)>(workers * QUEUE_PER_WORKER);
+        let (result_tx, result_rx) = bounded(workers * QUEUE_PER_WORKER);
+
+        s.spawn(move || {
+            for file in files {
+                if work_tx.send(file).is_err() {
+                    return;
+                }
+            }
+        });
+        for _ in 0..workers {
+            let (work_rx, result_tx) = (work_rx.clone(), result_tx.clone());
+            let hash = &hash;
+            s.spawn(move || {
+                for (path, len) in work_rx {
+                    let result = match hash(&path, len) {
+                        Ok(digest) => Ok((path, len, digest)),
+                        Err(err) => Err((with_path(err, &path), len)),
+                    };
+                    if result_tx.send(result).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        // Otherwise the channel never closes
+        drop(result_tx);
+
+        let mut last_report = Instant::now();
+        loop {
+            match result_rx.recv_timeout(PROGRESS_EVERY) {
+                Ok(result) => {
+                    progress.files += 1;
+                    match result {
+                        Ok(result) => {
+                            progress.bytes += read(result.1);
+                            results.push(result);
+                        }
+                        Err((err, len)) => {
+                            progress.bytes += read(len);
+                            errors.push(err);
+                        }
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if last_report.elapsed() >= PROGRESS_EVERY {
+                on_progress(&progress);
+                last_report = Instant::now();
+            }
+        }
+    });
+
+    on_progress(&progress);
+    (results, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+
+    use super::{hash_files, Progress};
+
+    #[test]
+    fn hashes_everything() {
+        let files = (0..100)
+            .map(|i| (PathBuf::from(i.to_string()), i))
+            .collect::<Vec<_>>();
+        let mut reports = 0;
+        let (mut results, errors) = hash_files(
+            files,
+            3,
+            "test",
+            |path, len| match len % 10 {
+                0 => Err(io::Error::other("no tens")),
+                _ => Ok(path.to_str().unwrap().len()),
+            },
+            // Only the first 10 bytes
+            |len| len.min(10),
+            |progress| {
+                reports += 1;
+                assert_eq!(progress.total_files, 100);
+                assert_eq!(progress.total_bytes, 45 + 90 * 10);
+                assert!(progress.bytes <= progress.total_bytes);
+            },
+        );
+
+        results.sort();
+        assert_eq!(results.len(), 90);
+        assert_eq!(results[0], (PathBuf::from("1"), 1, 1));
+        assert_eq!(errors.len(), 10);
+        assert_eq!(errors[0].to_string().split(": ").nth(1), Some("no tens"));
+        // At least the final report
+        assert!(reports >= 1);
+    }
+
+    #[test]
+    fn progress() {
+        let mut progress = Progress::new(
+            "full hash",
+            &[("a".into(), 1024), ("b".into(), 3072)],
+            |len| len,
+        );
+        assert_eq!(progress.eta(), None);
+
+        progress.start = Instant::now() - Duration::from_secs(2);
+        progress.files = 1;
+        progress.bytes = 1024;
+        let eta = progress.eta().unwrap().as_secs_f64();
+        assert!((5.9..6.1).contains(&eta), "{}", eta);
+        assert!(progress
+            .to_string()
+            .starts_with("full hash: 1/2 files, 1.0 KiB/4.0 KiB, 0.5 files/s, "));
+    }
+}
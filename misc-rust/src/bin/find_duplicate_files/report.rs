@@ -0,0 +1,271 @@
+//! Writing out the groups of duplicate files. Paths aren't necessarily
+//! unicode, so JSON and CSV output keeps their raw bytes, and only the human
+//! readable table is lossy.
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Format {
+    Json,
+    Csv,
+    Table,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct DuplicateGroup {
+    pub size: u64,
+    pub hash: String,
+    #[serde(with = "lossless_paths")]
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    /// Bytes that would be freed by keeping a single copy
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64).saturating_sub(1)
+    }
+}
+
+/// A path is written as a string when it's valid UTF-8, otherwise as serde's
+/// representation of `OsString`, e.g. `{"Unix": [102, 111, 255]}`
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LosslessPath<'a> {
+    Utf8(Cow<'a, str>),
+    Os(OsString),
+}
+
+impl<'a> LosslessPath<'a> {
+    fn new(path: &'a Path) -> Self {
+        match path.to_str() {
+            Some(path) => LosslessPath::Utf8(Cow::Borrowed(path)),
+            None => LosslessPath::Os(path.as_os_str().to_owned()),
+        }
+    }
+
+    fn into_path_buf(self) -> PathBuf {
+        match self {
+            LosslessPath::Utf8(path) => PathBuf::from(path.into_owned()),
+            LosslessPath::Os(path) => PathBuf::from(path),
+        }
+    }
+}
+
+pub(crate) mod lossless_path {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+        LosslessPath::new(path).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+        Ok(LosslessPath::deserialize(deserializer)?.into_path_buf())
+    }
+}
+
+pub(crate) mod lossless_paths {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(paths: &[PathBuf], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(paths.iter().map(|path| LosslessPath::new(path)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<PathBuf>, D::Error> {
+        Ok(Vec::<LosslessPath>::deserialize(deserializer)?
+            .into_iter()
+            .map(LosslessPath::into_path_buf)
+            .collect())
+    }
+}
+
+/// The raw bytes of the path where the platform has them
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Cow::Borrowed(path.as_os_str().as_bytes())
+    }
+    #[cfg(not(unix))]
+    {
+        match path.to_string_lossy() {
+            Cow::Borrowed(path) => Cow::Borrowed(path.as_bytes()),
+            Cow::Owned(path) => Cow::Owned(path.into_bytes()),
+        }
+    }
+}
+
+pub(crate) fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Groups are expected to be sorted already, see `sort_groups()`
+pub(crate) fn write_report(
+    groups: &[DuplicateGroup],
+    format: Format,
+    mut out: impl Write,
+) -> io::Result<()> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, groups)?;
+            writeln!(out)?;
+        }
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["group", "size", "hash", "path"])?;
+            for (i, group) in groups.iter().enumerate() {
+                let (i, size) = (i.to_string(), group.size.to_string());
+                for path in &group.paths {
+                    writer.write_record([
+                        i.as_bytes(),
+                        size.as_bytes(),
+                        group.hash.as_bytes(),
+                        &path_bytes(path),
+                    ])?;
+                }
+            }
+            writer.flush()?;
+        }
+        Format::Table => {
+            for group in groups {
+                writeln!(
+                    out,
+                    "{} copies of {} ({})",
+                    group.paths.len(),
+                    human_size(group.size),
+                    group.hash
+                )?;
+                for path in &group.paths {
+                    writeln!(out, "  {}", path.display())?;
+                }
+            }
+            let wasted = groups.iter().map(DuplicateGroup::wasted).sum();
+            writeln!(
+                out,
+                "{} groups of duplicates, {} wasted",
+                groups.len(),
+                human_size(wasted)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Most wasted space first, ties broken by path so output is stable
+pub(crate) fn sort_groups(groups: &mut [DuplicateGroup]) {
+    for group in groups.iter_mut() {
+        group.paths.sort();
+    }
+    groups.sort_by(|a, b| {
+        b.wasted()
+            .cmp(&a.wasted())
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{human_size, sort_groups, write_report, DuplicateGroup, Format};
+
+    fn groups() -> Vec<DuplicateGroup> {
+        vec![
+            DuplicateGroup {
+                size: 10,
+                hash: "aa".into(),
+                paths: vec!["/b/small".into(), "/a/small".into()],
+            },
+            DuplicateGroup {
+                size: 2048,
+                hash: "bb".into(),
+                paths: vec!["/a/big".into(), "/b/big, with comma".into()],
+            },
+        ]
+    }
+
+    #[test]
+    fn sorted_by_wasted_space() {
+        let mut groups = groups();
+        sort_groups(&mut groups);
+        assert_eq!(groups[0].hash, "bb");
+        assert_eq!(groups[1].paths[0], PathBuf::from("/a/small"));
+    }
+
+    #[test]
+    fn formats() {
+        let mut groups = groups();
+        sort_groups(&mut groups);
+
+        let mut csv = vec![];
+        write_report(&groups, Format::Csv, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "group,size,hash,path\n\
+             0,2048,bb,/a/big\n\
+             0,2048,bb,\"/b/big, with comma\"\n\
+             1,10,aa,/a/small\n\
+             1,10,aa,/b/small\n"
+        );
+
+        let mut table = vec![];
+        write_report(&groups, Format::Table, &mut table).unwrap();
+        let table = String::from_utf8(table).unwrap();
+        assert!(table.starts_with("2 copies of 2.0 KiB (bb)\n  /a/big\n"));
+        assert!(table.ends_with("2 groups of duplicates, 2.0 KiB wasted\n"));
+
+        let mut json = vec![];
+        write_report(&groups, Format::Json, &mut json).unwrap();
+        let parsed: Vec<DuplicateGroup> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(parsed, groups);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_unicode_paths() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"/tmp/caf\xe9"));
+        let groups = vec![DuplicateGroup {
+            size: 1,
+            hash: "cc".into(),
+            paths: vec![path.clone(), "/tmp/cafe".into()],
+        }];
+
+        let json = serde_json::to_string(&groups).unwrap();
+        assert!(json.contains(r#"{"Unix":[47,116,109,112,47,99,97,102,233]}"#));
+        assert!(json.contains(r#""/tmp/cafe""#));
+        let parsed: Vec<DuplicateGroup> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[0].paths[0], path);
+
+        let mut csv = vec![];
+        write_report(&groups, Format::Csv, &mut csv).unwrap();
+        assert!(csv.ends_with(b"0,1,cc,/tmp/caf\xe9\n0,1,cc,/tmp/cafe\n"));
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(5 << 30), "5.0 GiB");
+    }
+}
@@ -0,0 +1,161 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Technique {
+    SimpleOverlap,
+    Identities,
+    /// Every placement of a line's clues, see `line_solver::solve_line()`
+    LineSolver,
+    /// Assume a cell's value and look for a contradiction
+    Probing,
+    /// Backtracking search, used once nothing else makes progress
+    Guessing,
+}
+
+impl Technique {
+    /// Rough relative effort for a human to settle one cell with this technique
+    pub fn weight(&self) -> f64 {
+        match self {
+            Technique::SimpleOverlap => 1.0,
+            Technique::Identities => 2.0,
+            Technique::LineSolver => 3.0,
+            Technique::Probing => 6.0,
+            Technique::Guessing => GUESS_WEIGHT,
+        }
+    }
+}
+
+/// Cells that no technique could settle have to be guessed, which is charged
+/// at this weight
+pub(crate) const GUESS_WEIGHT: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Solutions {
+    None,
+    Unique,
+    /// At least two
+    Multiple,
+}
+
+impl Solutions {
+    pub fn from_count(count: usize) -> Self {
+        match count {
+            0 => Solutions::None,
+            1 => Solutions::Unique,
+            _ => Solutions::Multiple,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Step {
+    pub iteration: usize,
+    pub technique: Technique,
+    pub settled: usize,
+}
+
+/// What it took to solve a puzzle, so that whole puzzle sets can be ranked by
+/// `score`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct DifficultyReport {
+    pub rows: usize,
+    pub cols: usize,
+    /// Only steps that settled at least one cell
+    pub steps: Vec<Step>,
+    /// Up to the last iteration that settled anything
+    pub iterations: usize,
+    /// Cells still unknown at the end, i.e. the puzzle has no unique solution
+    pub unsettled: usize,
+    pub needed_guessing: bool,
+    pub solutions: Solutions,
+    pub score: f64,
+}
+
+impl DifficultyReport {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            steps: vec![],
+            iterations: 0,
+            unsettled: rows * cols,
+            needed_guessing: false,
+            solutions: Solutions::None,
+            score: 0.0,
+        }
+    }
+
+    /// Every technique recorded until the next call belongs to a new iteration
+    pub fn next_iteration(&mut self) {
+        self.iterations += 1;
+    }
+
+    /// Returns the new step, if anything was settled
+    pub fn record(&mut self, technique: Technique, settled: usize) -> Option<&Step> {
+        if settled == 0 {
+            return None;
+        }
+        self.steps.push(Step {
+            iteration: self.iterations,
+            technique,
+            settled,
+        });
+        self.steps.last()
+    }
+
+    pub fn settled_by(&self, technique: Technique) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| step.technique == technique)
+            .map(|step| step.settled)
+            .sum()
+    }
+
+    /// Compute the score once the solver is done. The score is the average
+    /// weight per cell, multiplied by the number of iterations, so puzzles
+    /// are harder when they need expensive techniques or when each iteration
+    /// only settles a sparse handful of cells.
+    pub fn finish(&mut self, unsettled: usize) {
+        self.iterations = self.steps.last().map_or(0, |step| step.iteration);
+        self.unsettled = unsettled;
+        self.needed_guessing = unsettled > 0 || self.settled_by(Technique::Guessing) > 0;
+
+        let cells = (self.rows * self.cols).max(1) as f64;
+        let cost = self
+            .steps
+            .iter()
+            .map(|step| step.technique.weight() * step.settled as f64)
+            .sum::<f64>()
+            + GUESS_WEIGHT * unsettled as f64;
+        self.score = cost / cells * self.iterations.max(1) as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DifficultyReport, Technique};
+
+    #[test]
+    fn score_and_json() {
+        let mut report = DifficultyReport::new(2, 2);
+        report.next_iteration();
+        report.record(Technique::SimpleOverlap, 2);
+        report.record(Technique::Identities, 0);
+        report.next_iteration();
+        report.record(Technique::Identities, 1);
+        report.finish(1);
+
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(report.settled_by(Technique::Identities), 1);
+        assert!(report.needed_guessing);
+        // (2 * 1 + 1 * 2 + 1 * 10) / 4 cells * 2 iterations
+        assert_eq!(report.score, 7.0);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["steps"][1]["technique"], "identities");
+        assert_eq!(json["steps"][1]["iteration"], 2);
+        assert_eq!(json["needed_guessing"], true);
+    }
+}
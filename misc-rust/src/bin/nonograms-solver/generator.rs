@@ -0,0 +1,192 @@
+//! Make puzzles from a target image. The clues of an arbitrary image often
+//! have more than one solution, so the image is tweaked one cell at a time
+//! until the solution is unique.
+use std::path::Path;
+
+use itertools::Itertools;
+use rand::Rng;
+
+use crate::difficulty::{DifficultyReport, Solutions};
+use crate::puzzle_file::{clues_of, NonFile};
+use crate::{GridCell, Nonograms};
+
+/// Indexed `image[row][col]`, `true` for shaded
+pub(crate) type Bitmap = Vec<Vec<bool>>;
+
+pub(crate) struct Generated {
+    pub file: NonFile,
+    pub report: DifficultyReport,
+    /// Number of cells flipped from the target image
+    pub tweaks: usize,
+}
+
+/// Dark pixels (luma below half) are shaded
+pub(crate) fn load_bitmap(path: impl AsRef<Path>) -> image::ImageResult<Bitmap> {
+    let image = image::open(path)?.into_luma8();
+    Ok(image
+        .rows()
+        .map(|row| row.map(|pixel| pixel.0[0] < 128).collect())
+        .collect())
+}
+
+/// Each cell is shaded with probability `density`, or `None` if that isn't
+/// between 0 and 1
+pub(crate) fn random_bitmap(
+    rows: usize,
+    cols: usize,
+    density: f64,
+    rng: &mut impl Rng,
+) -> Option<Bitmap> {
+    if !(0.0..=1.0).contains(&density) {
+        return None;
+    }
+    Some(
+        (0..rows)
+            .map(|_| (0..cols).map(|_| rng.gen_bool(density)).collect())
+            .collect(),
+    )
+}
+
+impl Nonograms {
+    /// `None` if the image has a run of more than 255 shaded cells
+    pub fn from_bitmap(image: &Bitmap) -> Option<Self> {
+        let cols = image.first().map_or(0, Vec::len);
+        assert!(image.iter().all(|row| row.len() == cols), "Ragged image");
+
+        Some(Self::new(
+            image
+                .iter()
+                .map(|row| clues_of(row.iter().copied()))
+                .collect::<Option<_>>()?,
+            (0..cols)
+                .map(|col| clues_of(image.iter().map(|row| row[col])))
+                .collect::<Option<_>>()?,
+        ))
+    }
+}
+
+/// Flip cells of `image` until its clues have a unique solution, giving up
+/// after `max_tweaks` flips, or if a run of cells is too long for a clue
+pub(crate) fn generate(
+    mut image: Bitmap,
+    max_tweaks: usize,
+    rng: &mut impl Rng,
+) -> Option<Generated> {
+    for tweaks in 0..=max_tweaks {
+        let puzzle = Nonograms::from_bitmap(&image)?;
+        let solutions = puzzle.find_solutions(2);
+        let other = match Solutions::from_count(solutions.len()) {
+            Solutions::None => unreachable!("The image itself is a solution"),
+            Solutions::Unique => {
+                let mut solved = puzzle.clone();
+                let report = solved.solve();
+                return Some(Generated {
+                    file: NonFile::from_nonograms(&solved),
+                    report,
+                    tweaks,
+                });
+            }
+            Solutions::Multiple => solutions
+                .into_iter()
+                .find(|solution| !matches_bitmap(solution, &image))
+                .expect("At least one solution differs from the image"),
+        };
+
+        // Flipping a cell where the solutions disagree changes the clues of
+        // the ambiguous region
+        let ambiguous = (0..image.len())
+            .cartesian_product(0..image[0].len())
+            .filter(|(row, col)| (other[*row][*col] == GridCell::Shaded) != image[*row][*col])
+            .collect_vec();
+        let (row, col) = ambiguous[rng.gen_range(0..ambiguous.len())];
+        image[row][col] = !image[row][col];
+    }
+    None
+}
+
+fn matches_bitmap(grid: &[Vec<GridCell>], image: &Bitmap) -> bool {
+    grid.iter()
+        .flatten()
+        .zip(image.iter().flatten())
+        .all(|(cell, shaded)| (cell == &GridCell::Shaded) == *shaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::{generate, load_bitmap, random_bitmap};
+    use crate::difficulty::Solutions;
+    use crate::Nonograms;
+
+    #[test]
+    fn from_bitmap() {
+        let image = vec![vec![true, false, true], vec![true, true, false]];
+        let puzzle = Nonograms::from_bitmap(&image).unwrap();
+        assert_eq!(puzzle.row_clues, vec![vec![1, 1], vec![2]]);
+        assert_eq!(puzzle.col_clues, vec![vec![2], vec![1], vec![1]]);
+
+        // Runs past 255 don't fit in a clue, whichever way they go
+        let wide = vec![vec![true; 300]];
+        assert!(Nonograms::from_bitmap(&wide).is_none());
+        let tall = vec![vec![true]; 300];
+        assert!(Nonograms::from_bitmap(&tall).is_none());
+        assert!(generate(tall, 1, &mut StdRng::seed_from_u64(0)).is_none());
+    }
+
+    #[test]
+    fn bitmap_file() {
+        let path = std::env::temp_dir().join("nonograms-solver-bitmap.png");
+        let image =
+            image::GrayImage::from_fn(3, 2, |x, y| image::Luma([if x == y { 0 } else { 255 }]));
+        image.save(&path).unwrap();
+        assert_eq!(
+            load_bitmap(&path).unwrap(),
+            vec![vec![true, false, false], vec![false, true, false]]
+        );
+    }
+
+    #[test]
+    fn already_unique() {
+        let image = vec![vec![true, true], vec![true, false]];
+        let generated = generate(image.clone(), 0, &mut StdRng::seed_from_u64(0)).unwrap();
+        assert_eq!(generated.tweaks, 0);
+        assert_eq!(generated.file.goal, Some(image));
+        assert_eq!(generated.report.solutions, Solutions::Unique);
+    }
+
+    #[test]
+    fn tweaks_until_unique() {
+        // A diagonal has two solutions, and needs tweaking
+        let image = vec![vec![true, false], vec![false, true]];
+        assert!(generate(image.clone(), 0, &mut StdRng::seed_from_u64(0)).is_none());
+
+        let generated = generate(image, 4, &mut StdRng::seed_from_u64(0)).unwrap();
+        assert!(generated.tweaks > 0);
+        assert_eq!(generated.report.solutions, Solutions::Unique);
+        let goal = generated.file.goal.unwrap();
+        assert_eq!(
+            Nonograms::from_bitmap(&goal).unwrap().row_clues,
+            generated.file.row_clues
+        );
+    }
+
+    #[test]
+    fn random_puzzle() {
+        let mut rng = StdRng::seed_from_u64(1234);
+        let image = random_bitmap(10, 15, 0.6, &mut rng).unwrap();
+        let generated = generate(image, 150, &mut rng).unwrap();
+        let report = generated.file.to_nonograms().solve();
+        assert_eq!(report.solutions, Solutions::Unique);
+        assert_eq!(generated.report.unsettled, 0);
+
+        for density in [1.5, -0.1, f64::NAN] {
+            assert_eq!(random_bitmap(2, 2, density, &mut rng), None);
+        }
+        assert_eq!(
+            random_bitmap(1, 2, 1.0, &mut rng),
+            Some(vec![vec![true; 2]])
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Exact solver for a single row or column. Rather than using a human
+//! technique, it considers every placement of the clues that agrees with the
+//! known cells and settles the cells that agree across all of those
+//! placements.
+use crate::GridCell;
+
+/// Returns `None` if no placement of `clues` fits the known cells of `line`.
+/// Otherwise, returns the line with every cell that is the same in all valid
+/// placements settled.
+pub(crate) fn solve_line(clues: &[u8], line: &[GridCell]) -> Option<Vec<GridCell>> {
+    let n = line.len();
+    let k = clues.len();
+    let can_be_empty = |i: usize| line[i] != GridCell::Shaded;
+    let can_be_block = |start: usize, len: usize| {
+        line[start..start + len]
+            .iter()
+            .all(|cell| cell != &GridCell::Unshaded)
+    };
+
+    // prefix[i][j]: The first `j` clues fit in `line[..i]`
+    let mut prefix = vec![vec![false; k + 1]; n + 1];
+    prefix[0][0] = true;
+    for i in 1..=n {
+        for j in 0..=k {
+            let mut fits = can_be_empty(i - 1) && prefix[i - 1][j];
+            if !fits && j > 0 {
+                // The `j`th clue ends right at `i`
+                let len = clues[j - 1] as usize;
+                if len <= i && can_be_block(i - len, len) {
+                    let start = i - len;
+                    fits = if start == 0 {
+                        prefix[0][j - 1]
+                    } else {
+                        can_be_empty(start - 1) && prefix[start - 1][j - 1]
+                    };
+                }
+            }
+            prefix[i][j] = fits;
+        }
+    }
+    if !prefix[n][k] {
+        return None;
+    }
+
+    // suffix[i][j]: Clues `j..` fit in `line[i..]`
+    let mut suffix = vec![vec![false; k + 1]; n + 1];
+    suffix[n][k] = true;
+    for i in (0..n).rev() {
+        for j in (0..=k).rev() {
+            let mut fits = can_be_empty(i) && suffix[i + 1][j];
+            if !fits && j < k {
+                // The `j`th clue starts right at `i`
+                let len = clues[j] as usize;
+                if i + len <= n && can_be_block(i, len) {
+                    let end = i + len;
+                    fits = if end == n {
+                        suffix[n][j + 1]
+                    } else {
+                        can_be_empty(end) && suffix[end + 1][j + 1]
+                    };
+                }
+            }
+            suffix[i][j] = fits;
+        }
+    }
+
+    let mut empty_possible = vec![false; n];
+    for (i, possible) in empty_possible.iter_mut().enumerate() {
+        *possible = can_be_empty(i) && (0..=k).any(|j| prefix[i][j] && suffix[i + 1][j]);
+    }
+
+    // Mark where each clue can go using a difference array
+    let mut shaded_coverage = vec![0_isize; n + 1];
+    for (j, clue) in clues.iter().enumerate() {
+        let len = *clue as usize;
+        for start in 0..=n.saturating_sub(len) {
+            let end = start + len;
+            if end > n || !can_be_block(start, len) {
+                continue;
+            }
+            let before = if start == 0 {
+                prefix[0][j]
+            } else {
+                can_be_empty(start - 1) && prefix[start - 1][j]
+            };
+            let after = if end == n {
+                suffix[n][j + 1]
+            } else {
+                can_be_empty(end) && suffix[end + 1][j + 1]
+            };
+            if before && after {
+                shaded_coverage[start] += 1;
+                shaded_coverage[end] -= 1;
+            }
+        }
+    }
+
+    let mut coverage = 0;
+    let mut solved = Vec::with_capacity(n);
+    for i in 0..n {
+        coverage += shaded_coverage[i];
+        solved.push(match (coverage > 0, empty_possible[i]) {
+            (true, true) => GridCell::Unknown,
+            (true, false) => GridCell::Shaded,
+            (false, true) => GridCell::Unshaded,
+            (false, false) => unreachable!("prefix[n][k] guarantees a placement exists"),
+        });
+    }
+    Some(solved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::solve_line;
+    use crate::GridCell;
+
+    fn parse(line: &str) -> Vec<GridCell> {
+        line.chars()
+            .map(|c| match c {
+                '.' => GridCell::Unknown,
+                '#' => GridCell::Shaded,
+                'x' => GridCell::Unshaded,
+                _ => panic!("bad cell {:?}", c),
+            })
+            .collect()
+    }
+
+    #[rstest::rstest]
+    #[case::no_clues(vec![], ".....", "xxxxx")]
+    #[case::full(vec![5], ".....", "#####")]
+    #[case::overlap(vec![4], ".....", ".###.")]
+    #[case::no_overlap(vec![2], ".....", ".....")]
+    #[case::exact_fit(vec![1, 3], ".....", "#x###")]
+    #[case::blocked_by_empty(vec![3], ".x...", "xx###")]
+    #[case::anchored_by_shaded(vec![2], "#....", "##xxx")]
+    #[case::reach(vec![3], "...#....", "x..#..xx")]
+    #[case::two_clues_split(vec![1, 1], "#.#", "#x#")]
+    #[case::must_use_first_hole(vec![2, 2], "..x..#..", "##xx.#.x")]
+    fn solves(#[case] clues: Vec<u8>, #[case] line: &str, #[case] expected: &str) {
+        assert_eq!(solve_line(&clues, &parse(line)), Some(parse(expected)));
+    }
+
+    #[rstest::rstest]
+    #[case::too_long(vec![6], ".....")]
+    #[case::shaded_without_clues(vec![], "..#..")]
+    #[case::too_many_shaded(vec![1], "#.#")]
+    #[case::gap_required(vec![1, 1], "##.")]
+    fn contradictions(#[case] clues: Vec<u8>, #[case] line: &str) {
+        assert_eq!(solve_line(&clues, &parse(line)), None);
+    }
+}
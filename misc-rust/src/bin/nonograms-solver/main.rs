@@ -0,0 +1,1082 @@
+/// The point of this solver is not to find the solution, but to help me solve
+/// it by checking the difficulty by using human solving techniques (rather than
+/// brute force enumeration) and ranking it by how sparse the results are per
+/// iteration step. At least, that's the purpose I have at the moment.
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+mod difficulty;
+mod generator;
+mod line_solver;
+mod puzzle_file;
+mod render;
+
+use difficulty::{DifficultyReport, Solutions, Step, Technique};
+use puzzle_file::NonFile;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum GridCell {
+    Unknown,
+    // Settled(bool),
+    Shaded,
+    Unshaded,
+}
+
+impl GridCell {
+    /// Returns None if Unknown
+    pub fn is_shaded(&self) -> Option<bool> {
+        match self {
+            GridCell::Unknown => None,
+            GridCell::Shaded => Some(true),
+            GridCell::Unshaded => Some(false),
+        }
+    }
+}
+
+impl From<GridCell> for char {
+    fn from(value: GridCell) -> Self {
+        match value {
+            GridCell::Unknown => ' ',
+            GridCell::Shaded => '■',
+            GridCell::Unshaded => 'X',
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+enum RowOrCol {
+    Row(usize),
+    Col(usize),
+}
+
+/// No arrangement of the clues in this line fits the settled cells
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Contradiction(RowOrCol);
+
+#[derive(Debug, Clone)]
+struct Nonograms {
+    rows: usize,
+    cols: usize,
+    // grid: Vec<GridCell>,
+    grid: Vec<Vec<GridCell>>,
+    row_clues: Vec<Vec<u8>>,
+    col_clues: Vec<Vec<u8>>,
+    unchecked: HashSet<RowOrCol>,
+}
+
+impl Nonograms {
+    /// The grid is `row_clues.len()` rows by `col_clues.len()` columns
+    fn new(row_clues: Vec<Vec<u8>>, col_clues: Vec<Vec<u8>>) -> Self {
+        let rows = row_clues.len();
+        let cols = col_clues.len();
+
+        Self {
+            rows,
+            cols,
+            grid: vec![vec![GridCell::Unknown; cols]; rows],
+            row_clues,
+            col_clues,
+            unchecked: HashSet::new(),
+        }
+    }
+
+    /// Rows span every column and vice versa
+    pub fn line_len(&self, at: RowOrCol) -> usize {
+        match at {
+            RowOrCol::Row(_) => self.cols,
+            RowOrCol::Col(_) => self.rows,
+        }
+    }
+
+    /// Would the new cell value increase the number of settled cells? None if
+    /// the existing cell is already settled the other way. Panics if the new
+    /// cell value isn't a settled value
+    pub fn is_new_settled(existing: &GridCell, cell: &GridCell) -> Option<bool> {
+        match (cell.is_shaded(), existing.is_shaded()) {
+            (Some(_), None) => Some(true),
+            (Some(will_be_shaded), Some(shaded)) if will_be_shaded == shaded => Some(false),
+            (Some(_), Some(_)) => None,
+
+            (None, _) => unreachable!("Could not replace grid cell {:?} with {:?}", existing, cell),
+        }
+    }
+
+    /// Apply each technique until they all stall, recording how many cells
+    /// each one settled per iteration. The cheapest technique that makes
+    /// progress is always preferred, and guessing is the last resort.
+    pub fn solve(&mut self) -> DifficultyReport {
+        self.solve_with_snapshots(|_, _| {})
+    }
+
+    /// Same as `solve()`, but `on_step` sees the grid after every step that
+    /// settled something
+    pub fn solve_with_snapshots(
+        &mut self,
+        mut on_step: impl FnMut(&Nonograms, &Step),
+    ) -> DifficultyReport {
+        let mut report = DifficultyReport::new(self.rows, self.cols);
+
+        report.next_iteration();
+        let mut contradiction = false;
+        match self.fill_simple_overlap() {
+            Ok(settled) => {
+                assert!(
+                    settled == 0 || (self.unchecked.len() > 0 && self.unchecked.len() <= settled)
+                );
+                if let Some(step) = report.record(Technique::SimpleOverlap, settled) {
+                    on_step(self, step);
+                }
+            }
+            Err(_) => contradiction = true,
+        }
+
+        while !contradiction {
+            report.next_iteration();
+            let Ok(settled) = self.apply_identities() else {
+                contradiction = true;
+                break;
+            };
+            if let Some(step) = report.record(Technique::Identities, settled) {
+                on_step(self, step);
+            }
+            if settled > 0 {
+                continue;
+            }
+
+            let Ok(settled) = self.line_solver_round() else {
+                contradiction = true;
+                break;
+            };
+            if let Some(step) = report.record(Technique::LineSolver, settled) {
+                on_step(self, step);
+            }
+            if settled > 0 || !self.unchecked.is_empty() {
+                continue;
+            }
+
+            let Ok(settled) = self.probe() else {
+                contradiction = true;
+                break;
+            };
+            if let Some(step) = report.record(Technique::Probing, settled) {
+                on_step(self, step);
+            }
+            if settled == 0 {
+                break;
+            }
+        }
+
+        report.solutions = if contradiction {
+            Solutions::None
+        } else {
+            let solutions = self.find_solutions(2);
+            if let [solution] = &solutions[..] {
+                let guessed = self.unsettled();
+                self.grid = solution.clone();
+                report.next_iteration();
+                if let Some(step) = report.record(Technique::Guessing, guessed) {
+                    on_step(self, step);
+                }
+            }
+            Solutions::from_count(solutions.len())
+        };
+
+        report.finish(self.unsettled());
+        report
+    }
+
+    pub fn unsettled(&self) -> usize {
+        self.grid
+            .iter()
+            .flatten()
+            .filter(|cell| cell == &&GridCell::Unknown)
+            .count()
+    }
+
+    pub fn get_line(&self, at: RowOrCol) -> Vec<GridCell> {
+        match at {
+            RowOrCol::Row(row) => self.grid[row].clone(),
+            RowOrCol::Col(col) => (0..self.rows).map(|i| self.grid[i][col]).collect(),
+        }
+    }
+
+    pub fn get_clues(&self, at: RowOrCol) -> &[u8] {
+        match at {
+            RowOrCol::Row(row) => &self.row_clues[row],
+            RowOrCol::Col(col) => &self.col_clues[col],
+        }
+    }
+
+    /// Settle a cell and mark the perpendicular line as unchecked
+    fn settle(&mut self, at: RowOrCol, index: usize, cell: GridCell) {
+        match at {
+            RowOrCol::Row(row) => {
+                self.grid[row][index] = cell;
+                self.unchecked.insert(RowOrCol::Col(index));
+            }
+            RowOrCol::Col(col) => {
+                self.grid[index][col] = cell;
+                self.unchecked.insert(RowOrCol::Row(index));
+            }
+        }
+    }
+
+    /// Run the exact line solver over every unchecked line. Lines whose cells
+    /// change get their perpendicular lines checked in the next round, so
+    /// calling this until `unchecked` is empty reaches a fixpoint.
+    pub fn line_solver_round(&mut self) -> Result<usize, Contradiction> {
+        let mut settled = 0;
+        // Sorted so the number of rounds doesn't depend on hash order
+        let lines = std::mem::take(&mut self.unchecked)
+            .into_iter()
+            .sorted_by_key(|at| match at {
+                RowOrCol::Row(row) => (0, *row),
+                RowOrCol::Col(col) => (1, *col),
+            })
+            .collect::<Vec<_>>();
+
+        for at in lines {
+            let line = self.get_line(at);
+            let solved =
+                line_solver::solve_line(self.get_clues(at), &line).ok_or(Contradiction(at))?;
+            for (index, (before, after)) in line.into_iter().zip(solved).enumerate() {
+                if before != after {
+                    settled += 1;
+                    self.settle(at, index, after);
+                }
+            }
+        }
+        Ok(settled)
+    }
+
+    /// Run the line solver until nothing changes
+    pub fn propagate(&mut self) -> Result<usize, Contradiction> {
+        let mut settled = 0;
+        while !self.unchecked.is_empty() {
+            settled += self.line_solver_round()?;
+        }
+        Ok(settled)
+    }
+
+    /// Try each value of each unknown cell and propagate. If one value leads
+    /// to a contradiction, the cell must be the other value. Stops after the
+    /// first successful probe since the cheaper techniques might take over
+    /// from there.
+    pub fn probe(&mut self) -> Result<usize, Contradiction> {
+        for (row, col) in (0..self.rows).cartesian_product(0..self.cols) {
+            if self.grid[row][col] != GridCell::Unknown {
+                continue;
+            }
+
+            let works = [GridCell::Shaded, GridCell::Unshaded].map(|guess| {
+                let mut attempt = self.clone();
+                attempt.settle(RowOrCol::Row(row), col, guess);
+                attempt.unchecked.insert(RowOrCol::Row(row));
+                attempt.propagate().is_ok()
+            });
+
+            let forced = match works {
+                [true, true] => continue,
+                [true, false] => GridCell::Shaded,
+                [false, true] => GridCell::Unshaded,
+                [false, false] => return Err(Contradiction(RowOrCol::Row(row))),
+            };
+            self.settle(RowOrCol::Row(row), col, forced);
+            self.unchecked.insert(RowOrCol::Row(row));
+            return Ok(1);
+        }
+        Ok(0)
+    }
+
+    /// Find up to `limit` solutions by guessing the first unknown cell,
+    /// propagating, and backtracking on contradictions. A limit of 2 is
+    /// enough to check that a puzzle has a unique solution.
+    pub fn find_solutions(&self, limit: usize) -> Vec<Vec<Vec<GridCell>>> {
+        let mut solutions = vec![];
+        let mut start = self.clone();
+        start.unchecked.extend((0..self.rows).map(RowOrCol::Row));
+        start.unchecked.extend((0..self.cols).map(RowOrCol::Col));
+        if start.propagate().is_ok() {
+            start._find_solutions(limit, &mut solutions);
+        }
+        solutions
+    }
+
+    fn _find_solutions(&self, limit: usize, solutions: &mut Vec<Vec<Vec<GridCell>>>) {
+        let Some((row, col)) = (0..self.rows)
+            .cartesian_product(0..self.cols)
+            .find(|(row, col)| self.grid[*row][*col] == GridCell::Unknown)
+        else {
+            solutions.push(self.grid.clone());
+            return;
+        };
+
+        for guess in [GridCell::Shaded, GridCell::Unshaded] {
+            if solutions.len() >= limit {
+                return;
+            }
+            let mut attempt = self.clone();
+            attempt.settle(RowOrCol::Row(row), col, guess);
+            attempt.unchecked.insert(RowOrCol::Row(row));
+            if attempt.propagate().is_ok() {
+                attempt._find_solutions(limit, solutions);
+            }
+        }
+    }
+
+    /// At the start of a puzzle, there are cells that cannot be unshaded
+    /// without making that row/column of clues impossible, e.g. an 18 clue in a
+    /// 30x30 puzzle fills the middle 6 cells.
+    pub fn fill_simple_overlap(&mut self) -> Result<usize, Contradiction> {
+        let mut settled = 0;
+        let lines = (0..self.rows)
+            .map(RowOrCol::Row)
+            .chain((0..self.cols).map(RowOrCol::Col))
+            .collect_vec();
+        for at in lines {
+            let line = self.get_line(at);
+            for (index, cell) in Self::_fill_simple_overlap(line.len(), self.get_clues(at)) {
+                if Self::is_new_settled(&line[index], &cell).ok_or(Contradiction(at))? {
+                    settled += 1;
+                    self.settle(at, index, cell);
+                }
+            }
+        }
+        Ok(settled)
+    }
+
+    /// The cells of a line that every arrangement of its clues agrees on
+    fn _fill_simple_overlap(size: usize, clues: &[u8]) -> Vec<(usize, GridCell)> {
+        if clues.is_empty() {
+            // A line without clues is entirely unshaded
+            return (0..size).map(|index| (index, GridCell::Unshaded)).collect();
+        }
+
+        let leeway = Self::calc_leeway(clues, size);
+
+        let mut cells = vec![];
+        let mut start = 0;
+        for clue in clues {
+            let clue = *clue as usize;
+            cells.extend((start + leeway..start + clue).map(|index| (index, GridCell::Shaded)));
+            start += clue + 1;
+        }
+        cells
+    }
+
+    fn calc_leeway(clues: &[u8], size: usize) -> usize {
+        let sum = clues.iter().map(|clue| *clue as usize).sum::<usize>();
+        return size
+            .checked_sub((sum + clues.len()).saturating_sub(1))
+            .expect("clues were too large for the grid size");
+    }
+
+    pub fn apply_identities(&mut self) -> Result<usize, Contradiction> {
+        let mut settled = 0;
+        let mut next_round = HashSet::new();
+        for row_or_col in std::mem::take(&mut self.unchecked) {
+            let size = self.line_len(row_or_col);
+            // TODO: Do I want to use sentinel values for this?
+            let cell_unknown = size;
+            let cell_known_empty = size + 1;
+            // Vec<clue_index_of_filled_cell | unknown_identity | known_empty_cell>
+            let mut identities = vec![cell_unknown; size];
+
+            let clues = match row_or_col {
+                RowOrCol::Row(row) => self.row_clues[row].clone(),
+                RowOrCol::Col(col) => self.col_clues[col].clone(),
+            };
+            if clues.is_empty() {
+                continue; // Settled by simple overlap
+            }
+            let leeway = Self::calc_leeway(&clues, size);
+            if leeway == 0 {
+                continue; // Line already solved
+            }
+
+            // TODO: I should really provide a way to reference the original
+            // data rather than copying it. I could either switch to a 1D array
+            // or always work on the grid row-wise and just transpose every time
+            // I switch.
+            let mut line = (0..size)
+                .map(|i| match row_or_col {
+                    RowOrCol::Row(row) => self.grid[row][i],
+                    RowOrCol::Col(col) => self.grid[i][col],
+                })
+                .collect::<Vec<_>>();
+            let grid = &mut self.grid;
+            let mut set_index: Box<dyn FnMut(usize, GridCell)> = match row_or_col {
+                RowOrCol::Row(row) => Box::new(move |i: usize, cell: GridCell| grid[row][i] = cell),
+                RowOrCol::Col(col) => Box::new(move |i: usize, cell: GridCell| grid[i][col] = cell),
+            };
+
+            let Some((first_unknown_cell, _)) = line
+                .iter()
+                .find_position(|cell| cell == &&GridCell::Unknown)
+            else {
+                continue;
+            };
+
+            let mut newly_settled = vec![];
+            let mut start = 0;
+            for (clue_index, clue) in clues.iter().enumerate() {
+                let clue = *clue as usize;
+
+                let mut shaded_length = 0_usize;
+                for known_index in 0..first_unknown_cell {
+                    start = known_index;
+                    match (shaded_length, line[known_index]) {
+                        (_, GridCell::Unknown) => {
+                            unreachable!("We should know the index of the first unknown cell")
+                        }
+                        (_, GridCell::Shaded) => shaded_length += 1,
+                        (0, GridCell::Unshaded) => {}
+                        (1.., GridCell::Unshaded) => break,
+                    }
+                }
+
+                // The first clue's run is already as long as the clue and
+                // reaches the unknown cells, so the next one must be empty.
+                // The line is checked again next round with that known.
+                let empty = GridCell::Unshaded;
+                if clue_index == 0
+                    && shaded_length == clue
+                    && line[start] == GridCell::Shaded
+                    && start + 1 == first_unknown_cell
+                {
+                    start += 1;
+                    settled += 1;
+                    identities[start] = cell_known_empty;
+                    set_index(start, empty);
+                    line[start] = empty;
+                    newly_settled.push(start);
+                    break;
+                }
+
+                while start < line.len() && line[start] == GridCell::Unshaded {
+                    identities[start] = cell_known_empty;
+                    start += 1;
+                }
+                // Too many clues for the line
+                if start >= size {
+                    return Err(Contradiction(row_or_col));
+                }
+
+                // Only valid when the known prefix has no shaded cells, i.e.
+                // the first clue hasn't been (partially) placed yet
+                if clue_index == 0 && shaded_length == 0 {
+                    // Example for a clue of 4: [unknown unknown x ...] => [x x x ...]
+                    while let Some(rightmost_blocker) = line[start..(start + clue).min(size)]
+                        .iter()
+                        .rposition(|cell| cell == &GridCell::Unshaded)
+                        .map(|index| start + index)
+                    {
+                        let empty = GridCell::Unshaded;
+                        for set_to_empty in start..rightmost_blocker {
+                            if Self::is_new_settled(&line[set_to_empty], &empty)
+                                .ok_or(Contradiction(row_or_col))?
+                            {
+                                settled += 1;
+                                set_index(set_to_empty, empty);
+                                line[set_to_empty] = empty;
+                                newly_settled.push(set_to_empty);
+                            }
+                            identities[set_to_empty] = cell_known_empty;
+                        }
+                        start = rightmost_blocker + 1;
+                        if start >= size {
+                            return Err(Contradiction(row_or_col));
+                        }
+                    }
+                }
+
+                // TODO: Perhaps I should actually change this to use an array
+                // of slots and clues and work with numbers exclusively at first.
+            }
+
+            if line[clues[0] as usize] == GridCell::Shaded {
+                let empty = GridCell::Unshaded;
+                if Self::is_new_settled(&line[0], &empty).ok_or(Contradiction(row_or_col))? {
+                    settled += 1;
+                    set_index(0, empty);
+                    newly_settled.push(0);
+                }
+            }
+
+            next_round.insert(row_or_col);
+            next_round.extend(newly_settled.into_iter().map(|index| match row_or_col {
+                RowOrCol::Row(_) => RowOrCol::Col(index),
+                RowOrCol::Col(_) => RowOrCol::Row(index),
+            }));
+        }
+        self.unchecked = next_round;
+        Ok(settled)
+    }
+
+    pub fn get_holes(&self, at: RowOrCol) -> Vec<u8> {
+        match at {
+            RowOrCol::Row(row) => (0..self.cols)
+                .map(|i| self.grid[row][i])
+                .group_by(|cell| cell == &GridCell::Unshaded)
+                .into_iter()
+                .filter_map(|(is_hole, group)| (!is_hole).then(|| group.count() as u8))
+                .collect(),
+            RowOrCol::Col(col) => (0..self.rows)
+                .map(|i| self.grid[i][col])
+                .group_by(|cell| cell == &GridCell::Unshaded)
+                .into_iter()
+                .filter_map(|(is_hole, group)| (!is_hole).then(|| group.count() as u8))
+                .collect(),
+        }
+    }
+
+    // TODO: I kinda hate that everything is being dumped into this class. I
+    // should move some of these methods into a module called holy_rocks (yes, I
+    // did just do that. You're welcome).
+
+    /// Revser
+    pub(crate) fn reverse_and_invert(
+        indices: Vec<HashSet<usize>>,
+        x: usize,
+    ) -> Vec<HashSet<usize>> {
+        return indices
+            .into_iter()
+            .rev()
+            .map(|indices| indices.into_iter().map(|index| x - index).collect())
+            .collect();
+    }
+
+    /// By placing rocks (aka clues) from left to right and then right to left,
+    /// some of the rocks end up in the same hole. This means that rock can only
+    /// go in that hole, which can be useful if the hole is smaller than twice
+    /// the length of the hole since that requires at least one shaded cell.
+    pub fn hole_identities_intersection(holes: &[u8], rocks: &[u8]) -> Vec<HashSet<usize>> {
+        assert!(
+            holes.len() > 1,
+            "No point in knowing which hole the rocks fall in if there is only one hole"
+        );
+
+        let leftmost = Self::_one_direction(holes.iter(), rocks.iter());
+        let rightmost = Self::_one_direction(holes.iter().rev(), rocks.iter().rev());
+        let rightmost = Self::reverse_and_invert(rightmost, holes.len() - 1);
+        assert_eq!(leftmost.len(), rightmost.len());
+        assert_eq!(leftmost.len(), holes.len());
+
+        return leftmost
+            .into_iter()
+            .zip(rightmost)
+            .map(|(left, right)| {
+                left.intersection(&right)
+                    .map(ToOwned::to_owned)
+                    .collect::<HashSet<usize>>()
+            })
+            .collect();
+    }
+
+    /// As opposed to hole_identities_intersection(), this method returns the
+    /// indices of *all* rocks that can possibly reside in each hole rather than
+    /// the rocks that must reside in that hole.
+    pub fn hole_identities_union(holes: &[u8], rocks: &[u8]) -> Vec<HashSet<usize>> {
+        assert!(
+            holes.len() > 1,
+            "No point in knowing which hole the rocks fall in if there is only one hole"
+        );
+        let leftmost = Self::_one_direction(holes.iter(), rocks.iter());
+        let rightmost = Self::_one_direction(holes.iter().rev(), rocks.iter().rev());
+        let rightmost = Self::reverse_and_invert(rightmost, holes.len() - 1);
+        assert_eq!(leftmost.len(), rightmost.len());
+        assert_eq!(leftmost.len(), holes.len());
+
+        let mut available = HashSet::<usize>::new();
+        return holes
+            .iter()
+            .enumerate()
+            .map(|(hole_i, hole)| {
+                available.extend(&leftmost[hole_i]);
+
+                let union = available
+                    .iter()
+                    .filter(|rock_i| rocks[**rock_i] <= *hole)
+                    .map(ToOwned::to_owned)
+                    .collect();
+
+                rightmost[hole_i].iter().for_each(|out_of_date| {
+                    assert!(
+                        available.remove(&out_of_date),
+                        "each rock should be accounted for exactly once"
+                    )
+                });
+
+                return union;
+            })
+            .collect();
+    }
+
+    pub(crate) fn _one_direction<'a>(
+        mut holes: impl Iterator<Item = &'a u8>,
+        rocks: impl Iterator<Item = &'a u8>,
+    ) -> Vec<HashSet<usize>> {
+        let mut rocks = rocks.enumerate();
+        let Some((mut rock_i, mut rock)) = rocks.next() else {
+            return holes.map(|_| HashSet::new()).collect();
+        };
+        let mut alignments = vec![];
+        let mut last_alignment = HashSet::new();
+
+        'holes_loop: for hole in &mut holes {
+            let mut hole = *hole;
+            while hole >= *rock {
+                last_alignment.insert(rock_i);
+
+                if hole == *rock {
+                    hole = 0;
+                } else {
+                    hole -= *rock + 1;
+                }
+
+                let Some(next_rock) = rocks.next() else {
+                    break 'holes_loop;
+                };
+                (rock_i, rock) = next_rock;
+            }
+
+            alignments.push(last_alignment);
+            last_alignment = HashSet::new();
+        }
+
+        assert_eq!(rocks.next(), None);
+        alignments.push(last_alignment);
+        alignments.extend(holes.map(|_| HashSet::new()));
+        return alignments;
+    }
+
+    fn debug_print(&self) {
+        for row in 0..=2 * self.rows {
+            let chars = match row {
+                0 => "┏━┯┳┓",
+                _ if row == 2 * self.rows => "┗━┷┻┛",
+                _ if row % 10 == 0 => "┣━┿╋┫",
+                _ if row & 1 == 1 => "┃ │┃┃",
+                _ if row & 1 == 0 => "┠─┼╂┨",
+                _ => unreachable!(),
+            };
+            let chars = chars.chars().collect::<Vec<_>>();
+            print!("{}", chars[0]);
+            for col in 1..2 * self.cols {
+                let mut c = if col & 1 == 1 {
+                    chars[1]
+                } else if col % 10 == 0 {
+                    chars[3]
+                } else {
+                    chars[2]
+                };
+
+                if c == ' ' {
+                    c = self.grid[row / 2][col / 2].into();
+                }
+                print!("{}", c);
+            }
+            print!("{}", chars[4]);
+            println!();
+        }
+    }
+}
+
+fn init_example_nonogram() -> Nonograms {
+    // Source: A manual transcription of Nonogram Galaxies puzzle #1-318
+    Nonograms::new(
+        vec![
+            vec![1, 2, 1, 2, 2, 2, 1, 3, 1, 1],
+            vec![1, 1, 1, 1, 5, 1, 3, 2, 1],
+            vec![1, 4, 2, 3, 3, 4, 1],
+            vec![1, 8, 1, 1, 2, 4],
+            vec![5, 2, 6, 1, 1],
+            vec![1, 2, 1, 2, 2, 2, 2, 1],
+            vec![1, 2, 2, 5, 1, 1, 1],
+            vec![1, 5, 3, 2, 1, 3],
+            vec![2, 1, 3, 1, 5, 3, 1],
+            vec![2, 2, 1, 2, 2, 1, 3, 1],
+            vec![4, 1, 6, 1, 1, 3],
+            vec![1, 2, 2, 1, 2],
+            vec![2, 1, 2, 2, 1, 1, 2],
+            vec![1, 2, 2, 1, 1, 1, 1, 1, 3, 3],
+            vec![5, 1, 1, 2, 1],
+            vec![2, 3, 1, 1, 2, 1, 1, 2, 1],
+            vec![2, 1, 1, 6, 1, 1, 1],
+            vec![2, 2, 2, 2, 1],
+            vec![1, 1, 6, 3, 2, 1],
+            vec![1, 1, 2, 13, 2, 2],
+            vec![1, 2, 1, 3, 2],
+            vec![1, 3, 6, 6, 2],
+            vec![1, 2, 1, 1, 1, 4, 1, 1, 2, 3],
+            vec![1, 3, 2, 1, 1, 2, 1, 3, 2, 1],
+            vec![1, 1, 1, 2, 1, 1, 2, 1, 2, 3],
+            vec![3, 2, 5, 4, 3, 2, 2],
+            vec![4, 7, 2, 1, 2, 2],
+            vec![1, 5, 1, 1, 1, 4],
+            vec![1, 1, 2, 2, 2, 7, 1],
+            vec![1, 7, 2, 6, 2, 2, 1],
+        ],
+        vec![
+            vec![1, 6, 3, 1, 2, 5, 1, 1],
+            vec![1, 1, 1, 2, 1, 2, 2, 1],
+            vec![1, 2, 2, 2, 5, 1],
+            vec![1, 4, 1, 2, 7, 4],
+            vec![7, 1, 3, 3, 3, 1],
+            vec![2, 2, 2, 3, 2, 2, 1, 1],
+            vec![2, 3, 3, 2, 1, 2, 1],
+            vec![1, 1, 2, 2, 2, 1, 1],
+            vec![1, 1, 1, 2, 1],
+            vec![3, 2, 9, 6],
+            vec![3, 3, 4, 5, 3, 1],
+            vec![1, 1, 2, 1, 2, 1],
+            vec![2, 2, 1, 2, 8],
+            vec![3, 2, 2, 1, 2, 1, 1, 2],
+            vec![3, 3, 1, 1, 1, 1, 2, 2, 1],
+            vec![3, 3, 1, 2, 1, 2, 2, 2],
+            vec![2, 3, 1, 2, 1, 5, 2],
+            vec![2, 1, 1, 1, 1, 2, 2, 1],
+            vec![5, 2, 1, 2, 1, 1, 2],
+            vec![1, 1, 1, 2, 1, 2, 8],
+            vec![2, 1, 1, 1, 2, 1, 1, 1],
+            vec![2, 2, 5, 5, 2, 2],
+            vec![1, 5, 7, 5, 3],
+            vec![1, 2, 2, 1, 2, 1, 2, 1],
+            vec![1, 2, 1, 6, 2, 4, 1],
+            vec![3, 2, 2, 2, 2, 2, 1, 2],
+            vec![5, 1, 2, 3, 2, 3, 3],
+            vec![1, 2, 2, 4, 2, 2, 4],
+            vec![1, 5, 1, 7, 1, 2, 1],
+            vec![1, 2, 4, 1, 2, 2],
+        ],
+    )
+}
+
+const USAGE: &str = "\
+Usage:
+    nonograms-solver [puzzle.non]
+    nonograms-solver render <puzzle.non> <output-dir>
+    nonograms-solver generate image <bitmap> <output.non>
+    nonograms-solver generate random <rows> <cols> <density> <seed> <output.non>";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = std::env::args().skip(1).collect_vec();
+    let (image, output, mut rng) = match args.iter().map(String::as_str).collect_vec()[..] {
+        [] => return solve(init_example_nonogram()),
+        ["generate", "image", path, output] => {
+            let image = generator::load_bitmap(path)?;
+            (image, output, StdRng::from_entropy())
+        }
+        ["generate", "random", rows, cols, density, seed, output] => {
+            let mut rng = StdRng::seed_from_u64(seed.parse()?);
+            let image =
+                generator::random_bitmap(rows.parse()?, cols.parse()?, density.parse()?, &mut rng)
+                    .ok_or("The density must be between 0 and 1")?;
+            (image, output, rng)
+        }
+        ["render", path, dir] => {
+            let file = std::fs::read_to_string(path)?.parse::<NonFile>()?;
+            let report = render::render_solve(&mut file.to_nonograms(), dir.as_ref())?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        [path] if path != "generate" && path != "render" => {
+            let file = std::fs::read_to_string(path)?.parse::<NonFile>()?;
+            return solve(file.to_nonograms());
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if Nonograms::from_bitmap(&image).is_none() {
+        return Err(
+            "The image has a run of more than 255 cells, which is too long for a clue".into(),
+        );
+    }
+    let max_tweaks = image.len() * image.first().map_or(0, Vec::len);
+    let generated = generator::generate(image, max_tweaks, &mut rng)
+        .ok_or("Could not make the solution unique")?;
+    std::fs::write(output, generated.file.to_string())?;
+    println!(
+        "Flipped {} cells to get a unique solution",
+        generated.tweaks
+    );
+    println!("{}", serde_json::to_string_pretty(&generated.report)?);
+    Ok(())
+}
+
+fn solve(mut grid: Nonograms) -> Result<(), Box<dyn std::error::Error>> {
+    let report = grid.solve();
+    grid.debug_print();
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::{init_example_nonogram, GridCell, Nonograms, RowOrCol, Solutions, Technique};
+
+    #[test]
+    fn rectangular_grid() {
+        // A 2x5 "L" shape
+        let mut puzzle = Nonograms::new(
+            vec![vec![1], vec![5]],
+            vec![vec![2], vec![1], vec![1], vec![1], vec![1]],
+        );
+        assert_eq!((puzzle.rows, puzzle.cols), (2, 5));
+        assert_eq!(puzzle.line_len(RowOrCol::Row(0)), 5);
+        assert_eq!(puzzle.line_len(RowOrCol::Col(0)), 2);
+
+        assert_eq!(puzzle.fill_simple_overlap(), Ok(6));
+        assert_eq!(puzzle.grid[1], vec![GridCell::Shaded; 5]);
+        assert_eq!(puzzle.grid[0][0], GridCell::Shaded);
+        assert_eq!(puzzle.get_holes(RowOrCol::Row(1)), vec![5]);
+    }
+
+    #[test]
+    fn solve_reports_difficulty() {
+        // Settled entirely by simple overlap
+        let mut puzzle = Nonograms::new(vec![vec![2], vec![]], vec![vec![1], vec![1]]);
+        let report = puzzle.solve();
+        assert_eq!(puzzle.unsettled(), 0);
+        assert!(!report.needed_guessing);
+        assert_eq!(report.settled_by(Technique::SimpleOverlap), 4);
+        assert_eq!(report.score, 1.0);
+    }
+
+    #[test]
+    fn identities_end_a_finished_run() {
+        // The clue of 2 is already placed against the left edge
+        let mut puzzle = Nonograms::new(
+            vec![vec![2]],
+            vec![vec![1], vec![1], vec![], vec![], vec![]],
+        );
+        puzzle.grid[0][0] = GridCell::Shaded;
+        puzzle.grid[0][1] = GridCell::Shaded;
+        puzzle.unchecked.insert(RowOrCol::Row(0));
+        assert_eq!(puzzle.apply_identities(), Ok(1));
+        assert_eq!(
+            puzzle.grid[0][..3],
+            [GridCell::Shaded, GridCell::Shaded, GridCell::Unshaded]
+        );
+        assert!(puzzle.unchecked.contains(&RowOrCol::Col(2)));
+    }
+
+    #[test]
+    fn solve_counts_every_settled_cell() {
+        // Overlap shades the first two columns, which finishes the top row's
+        // run for the identities to end
+        let mut puzzle = Nonograms::new(
+            vec![vec![2], vec![4]],
+            vec![vec![2], vec![2], vec![1], vec![1]],
+        );
+        let report = puzzle.solve();
+        assert_eq!(puzzle.unsettled(), 0);
+        assert!(report.settled_by(Technique::Identities) > 0);
+        let settled = report.steps.iter().map(|step| step.settled).sum::<usize>();
+        assert_eq!(settled, puzzle.rows * puzzle.cols);
+    }
+
+    #[test]
+    fn solve_example_uniquely() {
+        let mut puzzle = init_example_nonogram();
+        let report = puzzle.solve();
+        assert_eq!(report.solutions, Solutions::Unique);
+        assert_eq!(puzzle.unsettled(), 0);
+        assert!(report.settled_by(Technique::LineSolver) > 0);
+
+        for row in 0..puzzle.rows {
+            assert_eq!(puzzle.get_holes(RowOrCol::Row(row)), puzzle.row_clues[row]);
+        }
+        for col in 0..puzzle.cols {
+            assert_eq!(puzzle.get_holes(RowOrCol::Col(col)), puzzle.col_clues[col]);
+        }
+    }
+
+    #[test]
+    fn multiple_solutions() {
+        // Either diagonal works
+        let mut puzzle = Nonograms::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]);
+        let report = puzzle.solve();
+        assert_eq!(report.solutions, Solutions::Multiple);
+        assert!(report.needed_guessing);
+        assert_eq!(puzzle.unsettled(), 4);
+        assert_eq!(puzzle.find_solutions(10).len(), 2);
+    }
+
+    #[rstest::rstest]
+    // Simple overlap shades the top row against the empty columns
+    #[case(vec![vec![2], vec![]], vec![vec![], vec![]])]
+    // The first and last columns can't be shaded in both rows
+    #[case(vec![vec![6], vec![]], vec![vec![2], vec![], vec![], vec![2], vec![1], vec![2]])]
+    // The empty columns leave no room for the 2, which the identities find
+    #[case(vec![vec![2], vec![]], vec![vec![1], vec![], vec![1], vec![]])]
+    fn no_solutions(#[case] row_clues: Vec<Vec<u8>>, #[case] col_clues: Vec<Vec<u8>>) {
+        let mut puzzle = Nonograms::new(row_clues, col_clues);
+        assert_eq!(puzzle.solve().solutions, Solutions::None);
+    }
+
+    #[test]
+    fn line_solver_reaches_fixpoint() {
+        let mut puzzle = Nonograms::new(
+            vec![vec![1], vec![4]],
+            vec![vec![2], vec![1], vec![1], vec![1], vec![]],
+        );
+        puzzle
+            .unchecked
+            .extend([RowOrCol::Row(0), RowOrCol::Row(1)]);
+        assert_eq!(puzzle.line_solver_round(), Ok(3));
+        assert_eq!(puzzle.unchecked.len(), 3);
+
+        assert_eq!(puzzle.propagate(), Ok(3));
+        assert!(puzzle.unchecked.is_empty());
+        // Only the empty column says which way row 1 is shifted
+        assert_eq!(puzzle.unsettled(), 4);
+
+        puzzle.unchecked.insert(RowOrCol::Col(4));
+        assert_eq!(puzzle.propagate(), Ok(4));
+        assert_eq!(puzzle.unsettled(), 0);
+    }
+
+    #[test]
+    fn empty_clues_are_unshaded() {
+        let mut puzzle = Nonograms::new(vec![vec![], vec![2]], vec![vec![1], vec![1]]);
+        puzzle.fill_simple_overlap().unwrap();
+        assert_eq!(puzzle.grid[0], vec![GridCell::Unshaded; 2]);
+        assert_eq!(puzzle.grid[1], vec![GridCell::Shaded; 2]);
+    }
+
+    #[rstest::rstest]
+    #[case(vec![5, 6], vec![], vec![vec![], vec![]])]
+    #[case(vec![5, 6], vec![4], vec![vec![0], vec![]])]
+    #[case(vec![5, 6], vec![5], vec![vec![0], vec![]])]
+    #[case(vec![5, 6], vec![6], vec![vec![], vec![0]])]
+    #[case(vec![5, 6], vec![2, 2, 2], vec![vec![0, 1], vec![2]])]
+    #[case(vec![5, 1], vec![1, 1, 1, 1], vec![vec![0, 1, 2], vec![3]])]
+    #[case(vec![5, 1], vec![3, 1, 1], vec![vec![0, 1], vec![2]])]
+    #[case(vec![5, 1], vec![1, 3, 1], vec![vec![0, 1], vec![2]])]
+    #[case(vec![6, 1], vec![3, 1, 1], vec![vec![0, 1], vec![2]])]
+    #[case(vec![6, 1], vec![1, 3, 1], vec![vec![0, 1], vec![2]])]
+    #[case(vec![1, 2, 3, 4, 5], vec![5], vec![vec![], vec![], vec![], vec![], vec![0]])]
+    #[case(vec![8, 8, 8], vec![1, 8, 1], vec![vec![0], vec![1], vec![2]])]
+    #[case(vec![8, 8, 8], vec![1, 7, 1], vec![vec![0], vec![1], vec![2]])]
+    #[case(vec![8, 8, 8], vec![1, 6, 1], vec![vec![0, 1], vec![2], vec![]])]
+    fn test_one_direction(
+        #[case] holes: Vec<u8>,
+        #[case] rocks: Vec<u8>,
+        #[case] expected: Vec<Vec<usize>>,
+    ) {
+        assert_eq!(
+            Nonograms::_one_direction(holes.iter(), rocks.iter()),
+            expected
+                .into_iter()
+                .map(HashSet::from_iter)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(vec![8, 8, 8], vec![1, 8, 1], vec![vec![0], vec![1], vec![2]])]
+    #[case(vec![8, 8, 8], vec![1, 7, 1], vec![vec![0], vec![1], vec![2]])]
+    #[case(vec![8, 8, 8], vec![1, 6, 1], vec![vec![], vec![], vec![]])]
+    fn hole_intersection(
+        #[case] holes: Vec<u8>,
+        #[case] rocks: Vec<u8>,
+        #[case] expected: Vec<Vec<usize>>,
+    ) {
+        let actual = Nonograms::hole_identities_intersection(&holes, &rocks);
+        assert_eq!(
+            actual,
+            expected
+                .into_iter()
+                .map(HashSet::from_iter)
+                .collect::<Vec<_>>()
+        )
+    }
+
+    #[rstest::rstest]
+    #[case(vec![8, 8, 8], vec![1, 8, 1], vec![vec![0], vec![1], vec![2]])]
+    #[case(vec![8, 8, 8], vec![1, 7, 1], vec![vec![0], vec![1], vec![2]])]
+    #[case(vec![8, 8, 8], vec![1, 6, 1], vec![vec![0, 1], vec![0, 1, 2], vec![1, 2]])]
+    fn hole_union(
+        #[case] holes: Vec<u8>,
+        #[case] rocks: Vec<u8>,
+        #[case] expected: Vec<Vec<usize>>,
+    ) {
+        let actual = Nonograms::hole_identities_union(&holes, &rocks);
+        assert_eq!(
+            actual,
+            expected
+                .into_iter()
+                .map(HashSet::from_iter)
+                .collect::<Vec<_>>()
+        )
+    }
+
+    // TODO: This is not the best way to write this test (the initialization is
+    // separate from the test, but whatever)
+    #[rstest::fixture]
+    #[once]
+    fn get_holes_example_nonograms() -> Nonograms {
+        // I don't actually need the clues for this test, so this counts as a
+        // generic 30x30 grid.
+        let mut puzzle = init_example_nonogram();
+
+        let shaded = GridCell::Shaded;
+        let empty = GridCell::Unshaded;
+
+        // Row(0)
+        puzzle.grid[0][5] = empty;
+        puzzle.grid[0][7] = shaded;
+        // Row(3)
+        puzzle.grid[3][0] = empty;
+        puzzle.grid[3][1] = empty;
+        puzzle.grid[3][2] = empty;
+        puzzle.grid[3][3] = empty;
+
+        puzzle.grid[3][7] = empty;
+        puzzle.grid[3][8] = empty;
+        puzzle.grid[3][9] = empty;
+
+        puzzle.grid[3][11] = empty;
+        puzzle.grid[3][12] = shaded;
+        puzzle.grid[3][13] = empty;
+
+        puzzle.grid[3][16] = empty;
+
+        puzzle.grid[3][27] = empty;
+        puzzle.grid[3][28] = empty;
+        puzzle.grid[3][29] = empty;
+        // Col(6)
+        puzzle.grid[1][6] = empty;
+        puzzle.grid[28][6] = empty;
+        // Row(4)
+        puzzle.grid[4][0] = shaded;
+        puzzle.grid[4][1] = empty;
+        puzzle.grid[4][28] = empty;
+        puzzle.grid[4][29] = shaded;
+
+        return puzzle;
+    }
+
+    #[rstest::rstest]
+    #[case(RowOrCol::Row(0), vec![5, 24])]
+    #[case(RowOrCol::Row(3), vec![3, 1, 1, 2, 10])]
+    #[case(RowOrCol::Col(6), vec![1, 26, 1])]
+    #[case(RowOrCol::Row(4), vec![1, 26, 1])]
+    fn get_holes(
+        get_holes_example_nonograms: &Nonograms,
+        #[case] row_or_col: RowOrCol,
+        #[case] expected: Vec<u8>,
+    ) {
+        let puzzle = get_holes_example_nonograms;
+        assert_eq!(puzzle.get_holes(row_or_col), expected);
+    }
+}
@@ -0,0 +1,400 @@
+//! Reading and writing puzzles in Steve Simpson's `.non` format, e.g.
+//!
+//! ```text
+//! title "An L"
+//! width 5
+//! height 2
+//!
+//! rows
+//! 1
+//! 5
+//!
+//! columns
+//! 2
+//! 1
+//! 1
+//! 1
+//! 1
+//!
+//! goal "1000011111"
+//! ```
+//!
+//! A line of clues is comma separated, and an empty line or a lone `0` means
+//! the line has no shaded cells. Keys this solver doesn't care about (e.g.
+//! `catalogue`, `copyright`) are kept so they round-trip.
+use std::fmt;
+use std::str::FromStr;
+
+use itertools::Itertools;
+
+use crate::{GridCell, Nonograms};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct NonFile {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub row_clues: Vec<Vec<u8>>,
+    pub col_clues: Vec<Vec<u8>>,
+    /// The solution, if the file has one. Indexed `goal[row][col]`
+    pub goal: Option<Vec<Vec<bool>>>,
+    /// `(key, rest of line)` for every other key, in file order
+    pub other: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ParseNonError {
+    /// 1-indexed
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for ParseNonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseNonError {}
+
+impl NonFile {
+    pub fn from_nonograms(puzzle: &Nonograms) -> Self {
+        let goal = puzzle
+            .grid
+            .iter()
+            .flatten()
+            .all(|cell| cell.is_shaded().is_some())
+            .then(|| {
+                puzzle
+                    .grid
+                    .iter()
+                    .map(|row| row.iter().map(|cell| cell == &GridCell::Shaded).collect())
+                    .collect()
+            });
+
+        Self {
+            row_clues: puzzle.row_clues.clone(),
+            col_clues: puzzle.col_clues.clone(),
+            goal,
+            ..Default::default()
+        }
+    }
+
+    pub fn to_nonograms(&self) -> Nonograms {
+        Nonograms::new(self.row_clues.clone(), self.col_clues.clone())
+    }
+
+    fn parse_clues(line: &str) -> Result<Vec<u8>, String> {
+        let line = line.trim();
+        if line.is_empty() || line == "0" {
+            return Ok(vec![]);
+        }
+        line.split(',')
+            .map(|clue| {
+                clue.trim()
+                    .parse::<u8>()
+                    .map_err(|err| format!("bad clue {:?}: {}", clue, err))
+            })
+            .collect()
+    }
+
+    fn unquote(value: &str) -> String {
+        value.trim().trim_matches('"').to_owned()
+    }
+}
+
+/// The lengths of each run of shaded cells, or `None` if a run is too long
+/// for a clue
+pub(crate) fn clues_of(line: impl IntoIterator<Item = bool>) -> Option<Vec<u8>> {
+    line.into_iter()
+        .group_by(|shaded| *shaded)
+        .into_iter()
+        .filter_map(|(shaded, run)| shaded.then(|| u8::try_from(run.count()).ok()))
+        .collect()
+}
+
+impl FromStr for NonFile {
+    type Err = ParseNonError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut file = NonFile::default();
+        let mut width = None;
+        let mut height = None;
+        // Where each line of clues and the goal came from, for errors found
+        // once everything has been read
+        let mut row_lines = vec![];
+        let mut col_lines = vec![];
+        let mut goal_line = 0;
+        let mut lines = text.lines().enumerate();
+
+        let error = |line: usize, message: String| ParseNonError {
+            line: line + 1,
+            message,
+        };
+
+        while let Some((line_i, line)) = lines.next() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+            match key {
+                "width" | "height" => {
+                    let size = value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|err| error(line_i, format!("bad {}: {}", key, err)))?;
+                    if key == "width" {
+                        width = Some(size);
+                    } else {
+                        height = Some(size);
+                    }
+                }
+                "rows" | "columns" => {
+                    let count = if key == "rows" { height } else { width };
+                    let count = count.ok_or_else(|| {
+                        error(
+                            line_i,
+                            format!("`{}` must come after width and height", key),
+                        )
+                    })?;
+
+                    let mut clues = Vec::with_capacity(count);
+                    let mut clue_lines = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        let Some((clue_i, clue_line)) = lines.next() else {
+                            return Err(error(line_i, format!("expected {} {}", count, key)));
+                        };
+                        clues.push(Self::parse_clues(clue_line).map_err(|m| error(clue_i, m))?);
+                        clue_lines.push(clue_i);
+                    }
+                    if key == "rows" {
+                        (file.row_clues, row_lines) = (clues, clue_lines);
+                    } else {
+                        (file.col_clues, col_lines) = (clues, clue_lines);
+                    }
+                }
+                "goal" => {
+                    let (Some(width), Some(height)) = (width, height) else {
+                        return Err(error(
+                            line_i,
+                            "goal must come after width and height".into(),
+                        ));
+                    };
+                    let cells = Self::unquote(value);
+                    if cells.len() != width * height {
+                        return Err(error(
+                            line_i,
+                            format!(
+                                "goal has {} cells, expected {}",
+                                cells.len(),
+                                width * height
+                            ),
+                        ));
+                    }
+                    let cells = cells
+                        .chars()
+                        .map(|c| match c {
+                            '0' => Ok(false),
+                            '1' => Ok(true),
+                            _ => Err(error(line_i, format!("bad goal cell {:?}", c))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    file.goal = Some(cells.chunks(width).map(<[bool]>::to_vec).collect());
+                    goal_line = line_i;
+                }
+                "title" => file.title = Some(Self::unquote(value)),
+                "by" => file.author = Some(Self::unquote(value)),
+                _ => file.other.push((key.to_owned(), value.trim().to_owned())),
+            }
+        }
+
+        let end = text.lines().count();
+        let (Some(width), Some(height)) = (width, height) else {
+            return Err(error(end, "missing width or height".into()));
+        };
+        if file.col_clues.len() != width || file.row_clues.len() != height {
+            return Err(error(end, "missing rows or columns".into()));
+        }
+
+        // The solver assumes every line's clues fit, with a gap between each
+        let lines = [
+            ("row", &file.row_clues, &row_lines, width),
+            ("column", &file.col_clues, &col_lines, height),
+        ];
+        for (name, all_clues, clue_lines, size) in lines {
+            for (clues, line_i) in all_clues.iter().zip(clue_lines) {
+                let needed = clues.iter().map(|&clue| clue as usize).sum::<usize>()
+                    + clues.len().saturating_sub(1);
+                if needed > size {
+                    return Err(error(
+                        *line_i,
+                        format!("clues need {} cells, but a {} has {}", needed, name, size),
+                    ));
+                }
+            }
+        }
+
+        // Rows and columns each count every shaded cell once
+        let total = |all_clues: &[Vec<u8>]| {
+            all_clues
+                .iter()
+                .flatten()
+                .map(|&clue| clue as usize)
+                .sum::<usize>()
+        };
+        let (row_total, col_total) = (total(&file.row_clues), total(&file.col_clues));
+        if row_total != col_total {
+            return Err(error(
+                end,
+                format!(
+                    "rows shade {} cells, but columns shade {}",
+                    row_total, col_total
+                ),
+            ));
+        }
+
+        if let Some(goal) = &file.goal {
+            let goal_rows = goal.iter().map(|row| clues_of(row.iter().copied()));
+            let goal_cols = (0..width).map(|col| clues_of(goal.iter().map(|row| row[col])));
+            let lines = [
+                ("row", &file.row_clues, goal_rows.collect_vec()),
+                ("column", &file.col_clues, goal_cols.collect_vec()),
+            ];
+            for (name, all_clues, goal_clues) in lines {
+                for (i, (clues, goal_clues)) in all_clues.iter().zip(goal_clues).enumerate() {
+                    if goal_clues.as_ref() != Some(clues) {
+                        return Err(error(
+                            goal_line,
+                            format!("goal {} {} doesn't match clues {:?}", name, i + 1, clues),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(file)
+    }
+}
+
+impl fmt::Display for NonFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(title) = &self.title {
+            writeln!(f, "title \"{}\"", title)?;
+        }
+        if let Some(author) = &self.author {
+            writeln!(f, "by \"{}\"", author)?;
+        }
+        for (key, value) in &self.other {
+            writeln!(f, "{} {}", key, value)?;
+        }
+        writeln!(f, "width {}", self.col_clues.len())?;
+        writeln!(f, "height {}", self.row_clues.len())?;
+
+        for (name, clues) in [("rows", &self.row_clues), ("columns", &self.col_clues)] {
+            writeln!(f, "\n{}", name)?;
+            for line in clues {
+                if line.is_empty() {
+                    writeln!(f, "0")?;
+                } else {
+                    writeln!(f, "{}", line.iter().join(","))?;
+                }
+            }
+        }
+
+        if let Some(goal) = &self.goal {
+            let cells = goal
+                .iter()
+                .flatten()
+                .map(|shaded| if *shaded { '1' } else { '0' })
+                .collect::<String>();
+            writeln!(f, "\ngoal \"{}\"", cells)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::{clues_of, NonFile};
+    use crate::init_example_nonogram;
+
+    const L_SHAPE: &str = indoc! {r#"
+        catalogue "test #1"
+        title "An L"
+        width 5
+        height 2
+
+        rows
+        1
+        5
+
+        columns
+        2
+        1
+        1
+        1
+        1
+
+        goal "1000011111"
+    "#};
+
+    #[test]
+    fn parse() {
+        let file = L_SHAPE.parse::<NonFile>().unwrap();
+        assert_eq!(file.title.as_deref(), Some("An L"));
+        assert_eq!(file.row_clues, vec![vec![1], vec![5]]);
+        assert_eq!(
+            file.col_clues,
+            vec![vec![2], vec![1], vec![1], vec![1], vec![1]]
+        );
+        assert_eq!(
+            file.goal,
+            Some(vec![
+                vec![true, false, false, false, false],
+                vec![true, true, true, true, true],
+            ])
+        );
+        assert_eq!(file.other, vec![("catalogue".into(), "\"test #1\"".into())]);
+
+        let puzzle = file.to_nonograms();
+        assert_eq!((puzzle.rows, puzzle.cols), (2, 5));
+    }
+
+    #[test]
+    fn round_trip() {
+        let file = L_SHAPE.parse::<NonFile>().unwrap();
+        assert_eq!(file.to_string().parse::<NonFile>().unwrap(), file);
+
+        let example = NonFile::from_nonograms(&init_example_nonogram());
+        assert_eq!(example.goal, None);
+        let reparsed = example.to_string().parse::<NonFile>().unwrap();
+        assert_eq!(reparsed, example);
+    }
+
+    #[rstest::rstest]
+    #[case::missing_size("rows\n1\n")]
+    #[case::missing_columns("width 1\nheight 1\nrows\n1\n")]
+    #[case::too_few_rows("width 1\nheight 2\nrows\n1\n")]
+    #[case::bad_clue("width 1\nheight 1\nrows\nx\ncolumns\n1\n")]
+    #[case::bad_goal("width 1\nheight 1\nrows\n1\ncolumns\n1\ngoal \"10\"\n")]
+    #[case::row_too_long("width 3\nheight 1\nrows\n2,1\ncolumns\n1\n0\n1\n")]
+    #[case::column_too_long("width 1\nheight 2\nrows\n1\n1\ncolumns\n3\n")]
+    #[case::totals_differ("width 6\nheight 2\nrows\n6\n0\ncolumns\n2\n0\n0\n2\n1\n2\n")]
+    #[case::goal_disagrees("width 2\nheight 1\nrows\n1\ncolumns\n1\n0\ngoal \"01\"\n")]
+    fn parse_errors(#[case] text: &str) {
+        assert!(text.parse::<NonFile>().is_err());
+    }
+
+    #[rstest::rstest]
+    #[case(vec![], Some(vec![]))]
+    #[case(vec![false, false], Some(vec![]))]
+    #[case(vec![true, true, false, true], Some(vec![2, 1]))]
+    #[case(vec![false, true, true, true, false], Some(vec![3]))]
+    #[case(vec![true; 255], Some(vec![255]))]
+    #[case(vec![true; 256], None)]
+    fn clues(#[case] line: Vec<bool>, #[case] expected: Option<Vec<u8>>) {
+        assert_eq!(clues_of(line), expected);
+    }
+}
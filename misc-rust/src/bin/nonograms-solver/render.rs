@@ -0,0 +1,277 @@
+//! Render the grid after each solving step to PNGs and an animated GIF, with
+//! the clues in the margins and the cells settled by that step highlighted.
+//! Useful for reviewing how a technique progressed on large puzzles where
+//! `debug_print()` gets unwieldy.
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageResult, Rgba, RgbaImage};
+
+use crate::difficulty::DifficultyReport;
+use crate::{GridCell, Nonograms};
+
+/// Minimum size of a cell, grown to fit two digit clues
+const CELL: u32 = 16;
+/// Digits are drawn from a 3x5 pixel font at this scale
+const FONT_SCALE: u32 = 2;
+const FRAME_DELAY: Duration = Duration::from_millis(600);
+
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const UNKNOWN: Rgba<u8> = Rgba([215, 215, 215, 255]);
+const SHADED: Rgba<u8> = Rgba([20, 20, 20, 255]);
+const UNSHADED: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const NEW_SHADED: Rgba<u8> = Rgba([200, 30, 30, 255]);
+const NEW_UNSHADED: Rgba<u8> = Rgba([255, 210, 120, 255]);
+const GRID_LINE: Rgba<u8> = Rgba([150, 150, 150, 255]);
+const GRID_LINE_5: Rgba<u8> = Rgba([40, 40, 40, 255]);
+const TEXT: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// Rows of each glyph, most significant of the 3 bits is the left column
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+pub(crate) struct Renderer {
+    rows: u32,
+    cols: u32,
+    /// Margin widths in pixels, where the clues go
+    left: u32,
+    top: u32,
+    /// Cells and clues get the same square slot, sized to fit the widest clue
+    cell: u32,
+}
+
+impl Renderer {
+    pub fn new(puzzle: &Nonograms) -> Self {
+        let widest = puzzle
+            .row_clues
+            .iter()
+            .chain(&puzzle.col_clues)
+            .flatten()
+            .map(|clue| clue.to_string().len() as u32)
+            .max()
+            .unwrap_or(1);
+        let cell = CELL.max(widest * 4 * FONT_SCALE + 2);
+        let most_clues = |clues: &[Vec<u8>]| clues.iter().map(Vec::len).max().unwrap_or(0) as u32;
+
+        Self {
+            rows: puzzle.rows as u32,
+            cols: puzzle.cols as u32,
+            left: most_clues(&puzzle.row_clues).max(1) * cell,
+            top: most_clues(&puzzle.col_clues).max(1) * cell,
+            cell,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (
+            self.left + self.cols * self.cell + 1,
+            self.top + self.rows * self.cell + 1,
+        )
+    }
+
+    /// Draw the grid, highlighting cells that differ from `previous`
+    pub fn frame(&self, puzzle: &Nonograms, previous: Option<&[Vec<GridCell>]>) -> RgbaImage {
+        let (width, height) = self.size();
+        let mut image = RgbaImage::from_pixel(width, height, BACKGROUND);
+
+        for (row, clues) in puzzle.row_clues.iter().enumerate() {
+            let y = self.top + row as u32 * self.cell + (self.cell - 5 * FONT_SCALE) / 2;
+            let offset = self.left - clues.len() as u32 * self.cell;
+            for (i, clue) in clues.iter().enumerate() {
+                self.draw_number(
+                    &mut image,
+                    offset + i as u32 * self.cell,
+                    y,
+                    self.cell,
+                    *clue,
+                );
+            }
+        }
+        for (col, clues) in puzzle.col_clues.iter().enumerate() {
+            let x = self.left + col as u32 * self.cell;
+            let offset = self.top - clues.len() as u32 * self.cell;
+            for (i, clue) in clues.iter().enumerate() {
+                let y = offset + i as u32 * self.cell + (self.cell - 5 * FONT_SCALE) / 2;
+                self.draw_number(&mut image, x, y, self.cell, *clue);
+            }
+        }
+
+        for (row, cells) in puzzle.grid.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                let is_new = previous.is_some_and(|previous| &previous[row][col] != cell);
+                let color = match (cell, is_new) {
+                    (GridCell::Unknown, _) => UNKNOWN,
+                    (GridCell::Shaded, false) => SHADED,
+                    (GridCell::Shaded, true) => NEW_SHADED,
+                    (GridCell::Unshaded, false) => UNSHADED,
+                    (GridCell::Unshaded, true) => NEW_UNSHADED,
+                };
+                let x = self.left + col as u32 * self.cell;
+                let y = self.top + row as u32 * self.cell;
+                fill(&mut image, x, y, self.cell, self.cell, color);
+                if cell == &GridCell::Unshaded {
+                    // A dot so unshaded cells are distinguishable from the background
+                    fill(
+                        &mut image,
+                        x + self.cell / 2 - 1,
+                        y + self.cell / 2 - 1,
+                        2,
+                        2,
+                        GRID_LINE,
+                    );
+                }
+            }
+        }
+
+        for row in 0..=self.rows {
+            let color = if row % 5 == 0 { GRID_LINE_5 } else { GRID_LINE };
+            fill(
+                &mut image,
+                self.left,
+                self.top + row * self.cell,
+                self.cols * self.cell,
+                1,
+                color,
+            );
+        }
+        for col in 0..=self.cols {
+            let color = if col % 5 == 0 { GRID_LINE_5 } else { GRID_LINE };
+            fill(
+                &mut image,
+                self.left + col * self.cell,
+                self.top,
+                1,
+                self.rows * self.cell + 1,
+                color,
+            );
+        }
+
+        image
+    }
+
+    /// Right aligned within `width` pixels starting at `x`
+    fn draw_number(&self, image: &mut RgbaImage, x: u32, y: u32, width: u32, number: u8) {
+        let text = number.to_string();
+        let text_width = text.len() as u32 * 4 * FONT_SCALE - FONT_SCALE;
+        let mut x = (x + width).saturating_sub(text_width + 2);
+        for digit in text.bytes().map(|b| (b - b'0') as usize) {
+            for (dy, bits) in DIGITS[digit].iter().enumerate() {
+                for dx in 0..3 {
+                    if bits & (0b100 >> dx) != 0 {
+                        fill(
+                            image,
+                            x + dx * FONT_SCALE,
+                            y + dy as u32 * FONT_SCALE,
+                            FONT_SCALE,
+                            FONT_SCALE,
+                            TEXT,
+                        );
+                    }
+                }
+            }
+            x += 4 * FONT_SCALE;
+        }
+    }
+}
+
+fn fill(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for y in y..(y + height).min(image.height()) {
+        for x in x..(x + width).min(image.width()) {
+            image.put_pixel(x, y, color);
+        }
+    }
+}
+
+/// Solve `puzzle`, writing `step-NNN-<technique>.png` for every step plus
+/// `progress.gif` into `dir`
+pub(crate) fn render_solve(puzzle: &mut Nonograms, dir: &Path) -> ImageResult<DifficultyReport> {
+    std::fs::create_dir_all(dir)?;
+    let renderer = Renderer::new(puzzle);
+
+    let first = renderer.frame(puzzle, None);
+    first.save(dir.join("step-000-start.png"))?;
+    let mut frames = vec![first];
+
+    let mut previous = puzzle.grid.clone();
+    let mut result = Ok(());
+    let report = puzzle.solve_with_snapshots(|puzzle, step| {
+        if result.is_err() {
+            return;
+        }
+        let image = renderer.frame(puzzle, Some(&previous));
+        let name = format!("step-{:03}-{:?}.png", frames.len(), step.technique);
+        result = image.save(dir.join(name));
+        frames.push(image);
+        previous = puzzle.grid.clone();
+    });
+    result?;
+
+    let mut encoder = GifEncoder::new(File::create(dir.join("progress.gif"))?);
+    encoder.set_repeat(Repeat::Infinite)?;
+    encoder.encode_frames(frames.into_iter().map(|image| {
+        Frame::from_parts(image, 0, 0, Delay::from_saturating_duration(FRAME_DELAY))
+    }))?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_solve, Renderer, NEW_SHADED, SHADED, UNKNOWN};
+    use crate::{GridCell, Nonograms};
+
+    #[test]
+    fn highlights_new_cells() {
+        let mut puzzle = Nonograms::new(vec![vec![2], vec![]], vec![vec![1], vec![1]]);
+        let renderer = Renderer::new(&puzzle);
+        let before = puzzle.grid.clone();
+        let center = |row: u32, col: u32| {
+            (
+                renderer.left + col * renderer.cell + 3,
+                renderer.top + row * renderer.cell + 3,
+            )
+        };
+
+        let image = renderer.frame(&puzzle, None);
+        let (x, y) = center(0, 0);
+        assert_eq!(image.get_pixel(x, y), &UNKNOWN);
+
+        puzzle.grid[0][0] = GridCell::Shaded;
+        let image = renderer.frame(&puzzle, Some(&before));
+        assert_eq!(image.get_pixel(x, y), &NEW_SHADED);
+        let image = renderer.frame(&puzzle, Some(&puzzle.grid));
+        assert_eq!(image.get_pixel(x, y), &SHADED);
+    }
+
+    #[test]
+    fn writes_every_step() {
+        let dir = std::env::temp_dir().join("nonograms-solver-render");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // A plus sign
+        let image = (0..5)
+            .map(|row| (0..5).map(|col| row == 2 || col == 2).collect())
+            .collect();
+        let mut puzzle = Nonograms::from_bitmap(&image).unwrap();
+        let report = render_solve(&mut puzzle, &dir).unwrap();
+
+        let pngs = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().extension().unwrap() == "png")
+            .count();
+        assert_eq!(pngs, report.steps.len() + 1);
+        assert!(dir.join("progress.gif").exists());
+    }
+}
@@ -1,59 +1,25 @@
 // Source: https://www.youtube.com/watch?v=jUM_Dpt6yu0
-// This is going to (mostly) be implemented from memory
+// The generators themselves now live in `rust_misc::combinatorics`, this
+// prints a few of them side by side.
 
 use std::fmt::Display;
-use std::mem;
 
-// I really wish I could use recursive generators to gain an understanding of this before reimplementing it this way
-// I probably could enable generators using rust nightly, but I don't want to go down that rabbit hole yet...
-struct Permute<T> {
-    copy: Vec<T>,
-    indices: Vec<usize>,
-    // sub: Option<Box<Permute<'a, T>>>,
-}
-
-impl<T: Copy> Permute<T> {
-    fn from(vec: &Vec<T>) -> Self {
-        Permute {
-            // Why the @!(&%$#*#& do I have to copy and deref the values myself?!
-            copy: vec.into_iter().map(|x| *x).collect(),
-            indices: (0..vec.len()).collect(),
-            // sub: None,
-        }
-    }
-}
-
-impl<T> Permute<T> {
-    fn test(&mut self) -> Option<T> {
-        self.copy.pop()
-    }
-}
-// impl<T> Iterator for Permute<T> {
-//     type Item = Vec<T>;
-
-//     fn next(&mut self) -> Option<Self::Item> {
-//         self.copy.pop();
-//         // if self.copy.len() > 0 {
-//         //     mem::swap
-//         // }
-//     }
-// }
-
-// I need to read all of what I was doing, maybe make a note with all the in-progress things. Decide on to do, what not to, and in what order
+use rust_misc::combinatorics::{permutation_rank, Heap, Lexicographic};
 
 fn main() {
-    let mut v = vec![1, 2, 3];
-    let mut perm = Permute::from(&v);
-    println!("{};; {}", perm.test().unwrap(), v.pop().unwrap());
-    //     let v: Vec<i32> = (0..5).collect();
-    //     let per = Permute::from(&v);
-    //     for x in per {
-    //         println!("{}", format_vec(&x));
-    //     }
-    // }
+    let mut lexicographic = vec![1, 2, 3];
+    let mut heap = lexicographic.clone();
+    let mut lexicographic = Lexicographic::new(&mut lexicographic);
+    let mut heap = Heap::new(&mut heap);
+
+    println!("lexicographic\theap");
+    while let (Some(a), Some(b)) = (lexicographic.next(), heap.next()) {
+        let rank = permutation_rank(&a.iter().map(|x| x - 1).collect::<Vec<_>>());
+        println!("{} (rank {})\t{}", format_vec(a), rank, format_vec(b));
+    }
 }
 
-fn format_vec<T: Display>(vec: &Vec<T>) -> String {
+fn format_vec<T: Display>(vec: &[T]) -> String {
     format!(
         "vec![{}]",
         vec.iter()
@@ -63,11 +29,19 @@ fn format_vec<T: Display>(vec: &Vec<T>) -> String {
     )
 }
 
+#[cfg(test)]
 mod test {
-    use itertools::assert_equal;
+    use itertools::{assert_equal, Itertools};
+    use rust_misc::combinatorics::Lexicographic;
 
     #[test]
     fn compare_iters() {
-        assert_equal(vec![0, 1, 2, 3, 4], 0..=5);
+        let mut items = [0, 1, 2, 3];
+        let mut permutations = Lexicographic::new(&mut items);
+        let mut all = vec![];
+        while let Some(permutation) = permutations.next() {
+            all.push(permutation.to_vec());
+        }
+        assert_equal(all, (0..4).permutations(4));
     }
 }
@@ -0,0 +1,252 @@
+//! Bowyer–Watson: every point is inserted by removing the triangles whose
+//! circumcircle contains it, which leaves a star-shaped cavity, and joining
+//! the point to the cavity's boundary.
+//!
+//! Everything starts inside a huge "super triangle", so there is always a
+//! triangle to insert into. Its corners are hidden from the outside, and
+//! triangles touching them are only wrong near the hull, which is why the
+//! Voronoi diagram surrounds its sites with mirror images.
+use std::collections::{HashMap, HashSet};
+
+use crate::Point;
+
+/// Corners of the super triangle come first
+const SUPER: usize = 3;
+
+struct Triangle {
+    /// Counter-clockwise, with y up
+    vertices: [usize; 3],
+    center: Point,
+    radius2: f64,
+}
+
+impl Triangle {
+    fn new(vertices: [usize; 3], points: &[Point]) -> Self {
+        let [a, b, c] = vertices.map(|v| points[v]);
+        let d = 2.0 * (a.0 * (b.1 - c.1) + b.0 * (c.1 - a.1) + c.0 * (a.1 - b.1));
+        let (a2, b2, c2) = (norm2(a), norm2(b), norm2(c));
+        let center = (
+            (a2 * (b.1 - c.1) + b2 * (c.1 - a.1) + c2 * (a.1 - b.1)) / d,
+            (a2 * (c.0 - b.0) + b2 * (a.0 - c.0) + c2 * (b.0 - a.0)) / d,
+        );
+        Self {
+            vertices,
+            center,
+            radius2: distance2(center, a),
+        }
+    }
+
+    fn edges(&self) -> [(usize, usize); 3] {
+        let [a, b, c] = self.vertices;
+        [(a, b), (b, c), (c, a)]
+    }
+}
+
+fn norm2(p: Point) -> f64 {
+    p.0 * p.0 + p.1 * p.1
+}
+
+pub(crate) fn distance2(a: Point, b: Point) -> f64 {
+    norm2((a.0 - b.0, a.1 - b.1))
+}
+
+/// Positive if `p` is left of the line from `a` to `b`, with y up
+fn cross(a: Point, b: Point, p: Point) -> f64 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+pub(crate) struct Triangulation {
+    points: Vec<Point>,
+    /// Removed triangles leave a gap to be reused
+    triangles: Vec<Option<Triangle>>,
+    free: Vec<usize>,
+    /// The triangle left of each directed edge
+    edges: HashMap<(usize, usize), usize>,
+    neighbours: Vec<Vec<usize>>,
+    /// Where to start looking for the next point, as points tend to be near
+    /// the last one
+    last: usize,
+}
+
+impl Triangulation {
+    /// Points can only be inserted within `extent` of `center`
+    pub fn new(center: Point, extent: f64) -> Self {
+        let size = 10.0 * extent;
+        let mut triangulation = Self {
+            points: vec![
+                (center.0 - 2.0 * size, center.1 - size),
+                (center.0 + 2.0 * size, center.1 - size),
+                (center.0, center.1 + 2.0 * size),
+            ],
+            triangles: vec![],
+            free: vec![],
+            edges: HashMap::new(),
+            neighbours: vec![vec![]; SUPER],
+            last: 0,
+        };
+        triangulation.add_triangle([0, 1, 2]);
+        triangulation
+    }
+
+    pub fn point(&self, vertex: usize) -> Point {
+        self.points[vertex + SUPER]
+    }
+
+    /// Returns the new point's vertex
+    pub fn insert(&mut self, p: Point) -> usize {
+        let vertex = self.points.len();
+        self.points.push(p);
+        self.neighbours.push(vec![]);
+
+        let start = self.locate(p);
+        let mut cavity = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(t) = stack.pop() {
+            for (a, b) in self.triangle(t).edges() {
+                let Some(&other) = self.edges.get(&(b, a)) else {
+                    continue;
+                };
+                let triangle = self.triangle(other);
+                if !cavity.contains(&other) && distance2(triangle.center, p) < triangle.radius2 {
+                    cavity.insert(other);
+                    stack.push(other);
+                }
+            }
+        }
+
+        // In a fixed order, so the same points always make the same
+        // triangulation, down to the order of each vertex's neighbours
+        let mut removed = cavity.iter().copied().collect::<Vec<_>>();
+        removed.sort();
+        let mut boundary = vec![];
+        for &t in &removed {
+            for (a, b) in self.triangle(t).edges() {
+                match self.edges.get(&(b, a)) {
+                    Some(other) if cavity.contains(other) => {
+                        // Inside the cavity, so gone. Each is seen both ways
+                        if a < b {
+                            self.neighbours[a].retain(|&v| v != b);
+                            self.neighbours[b].retain(|&v| v != a);
+                        }
+                    }
+                    _ => boundary.push((a, b)),
+                }
+            }
+        }
+        for t in removed {
+            self.remove_triangle(t);
+        }
+        // Every vertex on the boundary starts exactly one boundary edge
+        for (a, b) in boundary {
+            self.neighbours[a].push(vertex);
+            self.neighbours[vertex].push(a);
+            self.add_triangle([a, b, vertex]);
+        }
+        vertex - SUPER
+    }
+
+    /// The triangle containing `p`, found by walking towards it
+    fn locate(&self, p: Point) -> usize {
+        let mut t = self.last;
+        // A walk can't loop in a Delaunay triangulation, but rounding could
+        // make it
+        for _ in 0..self.triangles.len() {
+            let next = self.triangle(t).edges().into_iter().find_map(|(a, b)| {
+                if cross(self.points[a], self.points[b], p) < 0.0 {
+                    self.edges.get(&(b, a)).copied()
+                } else {
+                    None
+                }
+            });
+            match next {
+                Some(next) => t = next,
+                None => return t,
+            }
+        }
+        self.triangles
+            .iter()
+            .position(|triangle| {
+                triangle.as_ref().is_some_and(|triangle| {
+                    triangle
+                        .edges()
+                        .iter()
+                        .all(|&(a, b)| cross(self.points[a], self.points[b], p) >= 0.0)
+                })
+            })
+            .expect("points must be inside the super triangle")
+    }
+
+    fn triangle(&self, t: usize) -> &Triangle {
+        self.triangles[t].as_ref().unwrap()
+    }
+
+    fn add_triangle(&mut self, vertices: [usize; 3]) {
+        let triangle = Triangle::new(vertices, &self.points);
+        let t = match self.free.pop() {
+            Some(t) => t,
+            None => {
+                self.triangles.push(None);
+                self.triangles.len() - 1
+            }
+        };
+        for edge in triangle.edges() {
+            self.edges.insert(edge, t);
+        }
+        self.triangles[t] = Some(triangle);
+        self.last = t;
+    }
+
+    fn remove_triangle(&mut self, t: usize) {
+        let triangle = self.triangles[t].take().unwrap();
+        for edge in triangle.edges() {
+            self.edges.remove(&edge);
+        }
+        self.free.push(t);
+    }
+
+    /// Vertices sharing an edge with `vertex`
+    pub fn neighbours(&self, vertex: usize) -> impl Iterator<Item = usize> + '_ {
+        self.neighbours[vertex + SUPER]
+            .iter()
+            .filter(|&&v| v >= SUPER)
+            .map(|v| v - SUPER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::{distance2, Triangulation, SUPER};
+    use crate::Point;
+
+    #[test]
+    fn empty_circumcircles() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut triangulation = Triangulation::new((0.5, 0.5), 1.0);
+        let points = (0..300).map(|_| rng.gen::<Point>()).collect::<Vec<_>>();
+        for (i, p) in points.iter().enumerate() {
+            assert_eq!(triangulation.insert(*p), i);
+        }
+
+        // Including the super triangle's, 2n + 1 triangles
+        assert_eq!(triangulation.triangles.iter().flatten().count(), 601);
+        for triangle in triangulation.triangles.iter().flatten() {
+            for p in &points {
+                assert!(distance2(triangle.center, *p) >= triangle.radius2 * (1.0 - 1e-9));
+            }
+        }
+
+        // Every edge between points is in two triangles, so seen both ways
+        let directed = triangulation
+            .edges
+            .keys()
+            .filter(|&&(a, b)| a >= SUPER && b >= SUPER)
+            .count();
+        let degrees = (0..points.len())
+            .map(|v| triangulation.neighbours(v).count())
+            .sum::<usize>();
+        assert_eq!(degrees, directed);
+    }
+}
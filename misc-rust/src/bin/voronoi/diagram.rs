@@ -0,0 +1,251 @@
+//! Voronoi cells from the Delaunay triangulation: a site's cell is the
+//! bounds, cut by the perpendicular bisector with each of its Delaunay
+//! neighbours.
+//!
+//! Each site is triangulated along with its mirror images across the four
+//! sides of the bounds. Those make every site's neighbours correct up to the
+//! bounds, instead of depending on the super triangle near the hull, and
+//! their bisectors are the sides themselves.
+use crate::delaunay::Triangulation;
+use crate::{assert_within_bounds, Point, BOUNDS};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cell {
+    pub site: Point,
+    /// Corners in order, clipped to `BOUNDS`
+    pub polygon: Vec<Point>,
+}
+
+impl Cell {
+    pub fn area(&self) -> f64 {
+        let twice = self
+            .edges()
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum::<f64>();
+        twice.abs() / 2.0
+    }
+
+    pub fn perimeter(&self) -> f64 {
+        self.edges()
+            .map(|(a, b)| ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt())
+            .sum()
+    }
+
+    /// Sides along the bounds count too
+    pub fn sides(&self) -> usize {
+        self.polygon.len()
+    }
+
+    pub fn touches_bounds(&self) -> bool {
+        self.polygon
+            .iter()
+            .any(|p| [p.0, p.1].iter().any(|x| *x == BOUNDS.0 || *x == BOUNDS.1))
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let next = self.polygon.iter().cycle().skip(1);
+        self.polygon.iter().copied().zip(next.copied())
+    }
+}
+
+/// The part of `polygon` at least as close to `a` as to `b`
+fn clip(polygon: &[Point], a: Point, b: Point) -> Vec<Point> {
+    let middle = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+    let towards_b = (b.0 - a.0, b.1 - a.1);
+    // Negative on `a`'s side
+    let side = |p: Point| (p.0 - middle.0) * towards_b.0 + (p.1 - middle.1) * towards_b.1;
+
+    let mut clipped = vec![];
+    for (i, &p) in polygon.iter().enumerate() {
+        let q = polygon[(i + 1) % polygon.len()];
+        let (p_side, q_side) = (side(p), side(q));
+        if p_side <= 0.0 {
+            clipped.push(p);
+        }
+        if (p_side < 0.0 && q_side > 0.0) || (p_side > 0.0 && q_side < 0.0) {
+            let t = p_side / (p_side - q_side);
+            clipped.push((p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1)));
+        }
+    }
+    clipped
+}
+
+fn mirrors(p: Point) -> [Point; 4] {
+    let (low, high) = BOUNDS;
+    [
+        (2.0 * low - p.0, p.1),
+        (2.0 * high - p.0, p.1),
+        (p.0, 2.0 * low - p.1),
+        (p.0, 2.0 * high - p.1),
+    ]
+}
+
+pub(crate) struct Voronoi {
+    triangulation: Triangulation,
+    /// The site each vertex of the triangulation is, or is a mirror of
+    owners: Vec<usize>,
+    /// Each site's own vertex
+    vertices: Vec<usize>,
+    cells: Vec<Cell>,
+    perimeter_sum: f64,
+}
+
+impl Voronoi {
+    pub fn new() -> Self {
+        let middle = (BOUNDS.0 + BOUNDS.1) / 2.0;
+        // Mirrors are up to one more width out on each side
+        let extent = 1.5 * (BOUNDS.1 - BOUNDS.0);
+        Self {
+            triangulation: Triangulation::new((middle, middle), extent),
+            owners: vec![],
+            vertices: vec![],
+            cells: vec![],
+            perimeter_sum: 0.0,
+        }
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Of every cell, kept up to date as points are added
+    pub fn perimeter_sum(&self) -> f64 {
+        self.perimeter_sum
+    }
+
+    /// Only the cells of the new site's neighbours change, so only those are
+    /// recalculated. Returns the new site's index
+    pub fn add_point(&mut self, p: Point) -> usize {
+        assert_within_bounds(p);
+        assert!(
+            self.cells.iter().all(|cell| cell.site != p),
+            "{:?} is already a site",
+            p
+        );
+        let site = self.cells.len();
+        let vertex = self.triangulation.insert(p);
+        self.owners.push(site);
+        self.vertices.push(vertex);
+        for mirror in mirrors(p) {
+            // A site on the bounds is its own mirror
+            if mirror != p {
+                self.triangulation.insert(mirror);
+                self.owners.push(site);
+            }
+        }
+        self.cells.push(Cell {
+            site: p,
+            polygon: vec![],
+        });
+
+        let changed = self
+            .triangulation
+            .neighbours(vertex)
+            .filter_map(|v| self.site_at(v))
+            .chain([site])
+            .collect::<Vec<_>>();
+        for site in changed {
+            self.perimeter_sum -= self.cells[site].perimeter();
+            self.cells[site].polygon = self.clip_cell(site);
+            self.perimeter_sum += self.cells[site].perimeter();
+        }
+        site
+    }
+
+    /// Pairs of sites whose cells share an edge, each once. Within the bounds
+    /// these are the Delaunay triangulation's edges, but sites on the hull
+    /// whose cells only meet outside the bounds aren't joined
+    pub fn delaunay_edges(&self) -> Vec<(usize, usize)> {
+        (0..self.cells.len())
+            .flat_map(|site| {
+                self.triangulation
+                    .neighbours(self.vertices[site])
+                    .filter_map(|v| self.site_at(v))
+                    .filter(move |&other| other > site)
+                    .map(move |other| (site, other))
+            })
+            .collect()
+    }
+
+    /// The site at `vertex`, unless it's a mirror image
+    fn site_at(&self, vertex: usize) -> Option<usize> {
+        let site = self.owners[vertex];
+        (self.vertices[site] == vertex).then_some(site)
+    }
+
+    fn clip_cell(&self, site: usize) -> Vec<Point> {
+        let (low, high) = BOUNDS;
+        let p = self.cells[site].site;
+        let mut polygon = vec![(low, low), (high, low), (high, high), (low, high)];
+        for other in self.triangulation.neighbours(self.vertices[site]) {
+            polygon = clip(&polygon, p, self.triangulation.point(other));
+        }
+        polygon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::{Cell, Voronoi};
+    use crate::delaunay::distance2;
+    use crate::Point;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[rstest::rstest]
+    #[case::one_site(&[(0.3, 0.6)], &[(1.0, 4.0)])]
+    #[case::halves(&[(0.25, 0.5), (0.75, 0.5)], &[(0.5, 3.0), (0.5, 3.0)])]
+    #[case::on_the_bounds(&[(0.0, 0.0), (1.0, 0.0)], &[(0.5, 3.0), (0.5, 3.0)])]
+    #[case::corner(&[(0.5, 0.5), (1.0, 1.0)], &[(0.875, 3.0 + 0.5f64.sqrt()), (0.125, 1.0 + 0.5f64.sqrt())])]
+    fn cells(#[case] sites: &[Point], #[case] expected: &[(f64, f64)]) {
+        let mut voronoi = Voronoi::new();
+        for site in sites {
+            voronoi.add_point(*site);
+        }
+        for (cell, (area, perimeter)) in voronoi.cells().iter().zip(expected) {
+            assert!(close(cell.area(), *area), "{:?}", cell);
+            assert!(close(cell.perimeter(), *perimeter), "{:?}", cell);
+        }
+    }
+
+    #[test]
+    fn incremental() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut voronoi = Voronoi::new();
+        for i in 0..500 {
+            assert_eq!(voronoi.add_point(rng.gen()), i);
+        }
+
+        let cells = voronoi.cells();
+        let area = cells.iter().map(Cell::area).sum::<f64>();
+        assert!(close(area, 1.0), "{}", area);
+        let perimeter = cells.iter().map(Cell::perimeter).sum::<f64>();
+        assert!(close(voronoi.perimeter_sum(), perimeter));
+
+        // Planar, and every site has a neighbour
+        let edges = voronoi.delaunay_edges();
+        assert!(edges.len() <= 3 * cells.len() - 6);
+        assert!(edges.iter().all(|(a, b)| a < b));
+        let mut joined = edges.iter().flat_map(|(a, b)| [*a, *b]).collect::<Vec<_>>();
+        joined.sort();
+        joined.dedup();
+        assert_eq!(joined.len(), cells.len());
+
+        // Every corner is as close to its own site as to any other
+        for cell in cells {
+            for corner in &cell.polygon {
+                let own = distance2(*corner, cell.site);
+                let nearest = cells
+                    .iter()
+                    .map(|other| distance2(*corner, other.site))
+                    .fold(f64::INFINITY, f64::min);
+                assert!(own - nearest < 1e-12, "{:?}", cell);
+            }
+        }
+    }
+}
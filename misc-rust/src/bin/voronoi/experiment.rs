@@ -0,0 +1,242 @@
+//! Repeat the random diagram many times to estimate the distributions of
+//! its cells' sides, areas and perimeters.
+//!
+//! Every trial has its own `Rng`, seeded with the trial's draw from an `Rng`
+//! seeded with the experiment's seed, so results don't depend on how trials
+//! were split between threads. Cells of one diagram aren't independent of each other, but
+//! separate trials are, so confidence intervals come from each trial's mean.
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::diagram::Voronoi;
+use crate::random_point;
+
+/// Standard normal quantile for a two-sided 95% interval
+const Z_95: f64 = 1.96;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Trial {
+    pub sides: Vec<usize>,
+    pub areas: Vec<f64>,
+    pub perimeters: Vec<f64>,
+    /// Of every cell, even with `interior`
+    pub perimeter_sum: f64,
+}
+
+/// With `interior`, cells touching the bounds are left out
+pub(crate) fn run_trial(points: usize, interior: bool, seed: u64) -> Trial {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut voronoi = Voronoi::new();
+    for _ in 0..points {
+        voronoi.add_point(random_point(&mut rng));
+    }
+
+    let cells = voronoi
+        .cells()
+        .iter()
+        .filter(|cell| !interior || !cell.touches_bounds())
+        .collect::<Vec<_>>();
+    Trial {
+        sides: cells.iter().map(|cell| cell.sides()).collect(),
+        areas: cells.iter().map(|cell| cell.area()).collect(),
+        perimeters: cells.iter().map(|cell| cell.perimeter()).collect(),
+        perimeter_sum: voronoi.perimeter_sum(),
+    }
+}
+
+/// Every trial, in order, on `workers` threads
+pub(crate) fn run(
+    points: usize,
+    trials: usize,
+    seed: u64,
+    workers: usize,
+    interior: bool,
+) -> Vec<Trial> {
+    let workers = workers.clamp(1, trials.max(1));
+    // Not `seed + trial`, which would give nearby seeds the same trials
+    let mut rng = StdRng::seed_from_u64(seed);
+    let seeds = (0..trials).map(|_| rng.gen()).collect::<Vec<u64>>();
+    let seeds = &seeds;
+    let mut results = vec![None; trials];
+    std::thread::scope(|s| {
+        for chunk in split(&mut results, workers) {
+            s.spawn(move || {
+                for (trial, result) in chunk {
+                    *result = Some(run_trial(points, interior, seeds[trial]));
+                }
+            });
+        }
+    });
+    results.into_iter().map(Option::unwrap).collect()
+}
+
+/// Every `n`th item with its index, for each of the `n` offsets, so that
+/// slow trials late on are spread out too
+fn split<T>(items: &mut [T], n: usize) -> Vec<Vec<(usize, &mut T)>> {
+    let mut chunks = (0..n).map(|_| vec![]).collect::<Vec<_>>();
+    for (i, item) in items.iter_mut().enumerate() {
+        chunks[i % n].push((i, item));
+    }
+    chunks
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Summary {
+    pub count: usize,
+    /// Of the trials' means
+    pub mean: f64,
+    /// Half the width of the 95% confidence interval of `mean`
+    pub ci_95: f64,
+    /// The rest are of every value together
+    pub std_dev: f64,
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+}
+
+/// `per_trial` holds each trial's values
+pub(crate) fn summarize(per_trial: &[Vec<f64>]) -> Summary {
+    let means = per_trial
+        .iter()
+        .filter(|values| !values.is_empty())
+        .map(|values| mean(values))
+        .collect::<Vec<_>>();
+    let mut all = per_trial.concat();
+    all.sort_by(f64::total_cmp);
+
+    let ci_95 = if means.len() > 1 {
+        Z_95 * std_dev(&means) / (means.len() as f64).sqrt()
+    } else {
+        f64::NAN
+    };
+    Summary {
+        count: all.len(),
+        mean: mean(&means),
+        ci_95,
+        std_dev: std_dev(&all),
+        min: all.first().copied().unwrap_or(f64::NAN),
+        median: all.get(all.len() / 2).copied().unwrap_or(f64::NAN),
+        max: all.last().copied().unwrap_or(f64::NAN),
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample standard deviation
+fn std_dev(values: &[f64]) -> f64 {
+    let mean = mean(values);
+    let squares = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+    (squares / (values.len() as f64 - 1.0)).sqrt()
+}
+
+pub(crate) fn write_report(trials: &[Trial], mut out: impl Write) -> io::Result<()> {
+    let per_trial = |f: fn(&Trial) -> Vec<f64>| trials.iter().map(f).collect::<Vec<_>>();
+    let statistics = [
+        (
+            "sides",
+            per_trial(|trial| trial.sides.iter().map(|&sides| sides as f64).collect()),
+        ),
+        ("area", per_trial(|trial| trial.areas.clone())),
+        ("perimeter", per_trial(|trial| trial.perimeters.clone())),
+        (
+            "perimeter sum",
+            per_trial(|trial| vec![trial.perimeter_sum]),
+        ),
+    ];
+
+    writeln!(
+        out,
+        "{:<14}{:>8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}",
+        "", "count", "mean", "± 95% CI", "std dev", "min", "median", "max"
+    )?;
+    for (name, values) in statistics {
+        let summary = summarize(&values);
+        writeln!(
+            out,
+            "{:<14}{:>8}{:>12.6}{:>12.6}{:>12.6}{:>12.6}{:>12.6}{:>12.6}",
+            name,
+            summary.count,
+            summary.mean,
+            summary.ci_95,
+            summary.std_dev,
+            summary.min,
+            summary.median,
+            summary.max
+        )?;
+    }
+
+    let mut sides = BTreeMap::<usize, usize>::new();
+    for trial in trials {
+        for side in &trial.sides {
+            *sides.entry(*side).or_default() += 1;
+        }
+    }
+    let cells = sides.values().sum::<usize>();
+    writeln!(out, "\n{:<6}{:>10}{:>10}", "sides", "cells", "fraction")?;
+    for (side, count) in sides {
+        let fraction = count as f64 / cells as f64;
+        writeln!(out, "{:<6}{:>10}{:>10.4}", side, count, fraction)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run, summarize, write_report};
+
+    fn close(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() < tolerance
+    }
+
+    #[test]
+    fn summaries() {
+        let summary = summarize(&[vec![1.0, 2.0, 3.0], vec![5.0], vec![]]);
+        assert_eq!(summary.count, 4);
+        // Of 2 and 5, not of all 4 values
+        assert_eq!(summary.mean, 3.5);
+        assert!(close(
+            summary.ci_95,
+            1.96 * 4.5f64.sqrt() / 2f64.sqrt(),
+            1e-12
+        ));
+        assert_eq!((summary.min, summary.median, summary.max), (1.0, 3.0, 5.0));
+        assert!(summarize(&[vec![1.0]]).ci_95.is_nan());
+    }
+
+    #[test]
+    fn independent_of_threads() {
+        let trials = run(30, 7, 5, 1, false);
+        assert_eq!(trials, run(30, 7, 5, 3, false));
+        // Not even shifted along by one
+        let next_seed = run(30, 7, 6, 3, false);
+        assert!(trials.iter().all(|trial| !next_seed.contains(trial)));
+
+        for trial in &trials {
+            assert_eq!(trial.areas.len(), 30);
+            assert!(close(trial.areas.iter().sum(), 1.0, 1e-9));
+        }
+        let mut report = vec![];
+        write_report(&trials, &mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.lines().nth(2).unwrap().starts_with("area"));
+    }
+
+    #[test]
+    fn interior_cells_have_six_sides() {
+        // Every side is shared by two cells, and Euler's formula gives 6 on
+        // average away from the bounds
+        let trials = run(300, 8, 1, 4, true);
+        let sides = trials
+            .iter()
+            .map(|trial| trial.sides.iter().map(|&sides| sides as f64).collect())
+            .collect::<Vec<_>>();
+        let summary = summarize(&sides);
+        assert!(close(summary.mean, 6.0, 0.1), "{:?}", summary);
+        assert!(summary.count < 8 * 300);
+    }
+}
@@ -0,0 +1,181 @@
+use std::io;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+mod delaunay;
+mod diagram;
+mod experiment;
+mod render;
+
+use diagram::Voronoi;
+use render::{Coloring, Style};
+
+type Point = (f64, f64);
+/// The lowest and highest coordinate on either axis
+const BOUNDS: Point = (0.0, 1.0);
+
+fn assert_within_bounds(p: Point) {
+    assert!(p.0 >= BOUNDS.0 && p.0 <= BOUNDS.1 && p.1 >= BOUNDS.0 && p.1 <= BOUNDS.1);
+}
+
+fn random_point(rng: &mut impl Rng) -> Point {
+    (
+        rng.gen_range(BOUNDS.0..BOUNDS.1),
+        rng.gen_range(BOUNDS.0..BOUNDS.1),
+    )
+}
+
+/// Average sum of the cells' perimeters after each of `points` uniformly
+/// random points is added
+fn expected_perimeter_sum(points: usize, trials: usize, rng: &mut impl Rng) -> Vec<f64> {
+    let mut sums = vec![0.0; points];
+    for _ in 0..trials {
+        let mut voronoi = Voronoi::new();
+        for sum in sums.iter_mut() {
+            voronoi.add_point(random_point(rng));
+            *sum += voronoi.perimeter_sum();
+        }
+    }
+    sums.into_iter().map(|sum| sum / trials as f64).collect()
+}
+
+/// Printed when random, so the results can be reproduced
+fn seed_or_random(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(|| {
+        let seed = rand::thread_rng().gen();
+        eprintln!("Seed {}", seed);
+        seed
+    })
+}
+
+/// Voronoi diagrams of uniformly random points
+#[derive(Parser, Debug)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Average sum of the cells' perimeters as points are added
+    Perimeter {
+        #[arg(short, long, default_value_t = 1000)]
+        points: usize,
+        #[arg(short, long, default_value_t = 100)]
+        trials: usize,
+    },
+    /// Summaries of the cells' sides, areas and perimeters over many
+    /// diagrams
+    Experiment {
+        #[arg(short, long, default_value_t = 1000)]
+        points: usize,
+        #[arg(short, long, default_value_t = 100)]
+        trials: usize,
+        /// The same seed gives the same results, random if not given
+        #[arg(short, long)]
+        seed: Option<u64>,
+        /// Threads running trials, defaults to the number of CPUs
+        #[arg(short, long)]
+        workers: Option<usize>,
+        /// Leave out cells touching the bounds, which are cut short
+        #[arg(short, long)]
+        interior: bool,
+    },
+    /// Draw a diagram, as a PNG if `output` ends in `.png` and an SVG
+    /// otherwise
+    Render {
+        output: PathBuf,
+        #[arg(short, long, default_value_t = 100)]
+        points: usize,
+        /// The same seed draws the same picture, random if not given
+        #[arg(short, long)]
+        seed: Option<u64>,
+        /// Width and height in pixels
+        #[arg(long, default_value_t = 800)]
+        size: u32,
+        #[arg(short, long, value_enum, default_value_t = Coloring::Random)]
+        coloring: Coloring,
+        #[arg(long)]
+        no_cells: bool,
+        #[arg(long)]
+        no_sites: bool,
+        /// Also draw the Delaunay triangulation
+        #[arg(short, long)]
+        delaunay: bool,
+    },
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // NOTE: All coordinate math is based on graphics, i.e. top-left is origin
+    // and down is positive y-axis.
+
+    match Args::parse().command {
+        Command::Perimeter { points, trials } => {
+            let sums = expected_perimeter_sum(points, trials, &mut rand::thread_rng());
+
+            // Away from the bounds, n random points have about 2 sqrt(n) length
+            // of edges per unit area, and every edge is on two cells
+            let side = BOUNDS.1 - BOUNDS.0;
+            println!("points\tperimeter sum\tunbounded estimate");
+            for n in [
+                1, 2, 3, 5, 10, 20, 50, 100, 200, 500, 1000, 2000, 5000, 10000,
+            ] {
+                if n > points {
+                    break;
+                }
+                let estimate = 4.0 * (n as f64).sqrt() * side + 4.0 * side;
+                println!("{}\t{:.4}\t{:.4}", n, sums[n - 1], estimate);
+            }
+        }
+        Command::Experiment {
+            points,
+            trials,
+            seed,
+            workers,
+            interior,
+        } => {
+            let seed = seed_or_random(seed);
+            let workers = workers.unwrap_or_else(|| {
+                std::thread::available_parallelism().map_or(4, std::num::NonZeroUsize::get)
+            });
+            let results = experiment::run(points, trials, seed, workers, interior);
+            println!("{} trials of {} points, seed {}\n", trials, points, seed);
+            experiment::write_report(&results, io::stdout().lock())?;
+        }
+        Command::Render {
+            output,
+            points,
+            seed,
+            size,
+            coloring,
+            no_cells,
+            no_sites,
+            delaunay,
+        } => {
+            let mut rng = StdRng::seed_from_u64(seed_or_random(seed));
+            let mut voronoi = Voronoi::new();
+            for _ in 0..points {
+                voronoi.add_point(random_point(&mut rng));
+            }
+            let colors = render::cell_colors(&voronoi, coloring, &mut rng);
+            let style = Style {
+                size,
+                cells: !no_cells,
+                sites: !no_sites,
+                delaunay,
+            };
+            if output
+                .extension()
+                .is_some_and(|extension| extension == "png")
+            {
+                render::png(&voronoi, &style, &colors).save(&output)?;
+            } else {
+                std::fs::write(&output, render::svg(&voronoi, &style, &colors))?;
+            }
+        }
+    }
+    Ok(())
+}
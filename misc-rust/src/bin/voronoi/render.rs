@@ -0,0 +1,324 @@
+//! Draw a diagram as an SVG, with the cells, Delaunay edges and sites as
+//! layers that can each be left out, or as a PNG of the same picture.
+//!
+//! Nothing here draws from an `Rng` except the random cell colours, so a
+//! diagram and its colours made from one seeded `Rng` always render the same.
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use image::{Rgba, RgbaImage};
+use rand::Rng;
+
+use crate::diagram::{Cell, Voronoi};
+use crate::{Point, BOUNDS};
+
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const EDGE: Rgba<u8> = Rgba([40, 40, 40, 255]);
+const DELAUNAY: Rgba<u8> = Rgba([200, 30, 30, 255]);
+const SITE: Rgba<u8> = Rgba([0, 0, 0, 255]);
+/// Colours of the smallest and largest cells when colouring by area, with
+/// the rest in between
+const SMALLEST: [u8; 3] = [255, 240, 170];
+const LARGEST: [u8; 3] = [40, 70, 150];
+/// In pixels
+const SITE_RADIUS: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Coloring {
+    Random,
+    Area,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Style {
+    /// Width and height in pixels
+    pub size: u32,
+    pub cells: bool,
+    pub sites: bool,
+    pub delaunay: bool,
+}
+
+/// A colour for each cell
+pub(crate) fn cell_colors(
+    voronoi: &Voronoi,
+    coloring: Coloring,
+    rng: &mut impl Rng,
+) -> Vec<[u8; 3]> {
+    let cells = voronoi.cells();
+    match coloring {
+        // Light enough for the edges and sites to stand out
+        Coloring::Random => cells
+            .iter()
+            .map(|_| [(); 3].map(|_| rng.gen_range(100..=255)))
+            .collect(),
+        Coloring::Area => {
+            let areas = cells.iter().map(Cell::area).collect::<Vec<_>>();
+            let smallest = areas.iter().copied().fold(f64::INFINITY, f64::min);
+            let largest = areas.iter().copied().fold(0.0, f64::max);
+            let range = (largest - smallest).max(f64::EPSILON);
+            areas
+                .iter()
+                .map(|area| {
+                    let t = (area - smallest) / range;
+                    [0, 1, 2].map(|i| {
+                        let (from, to) = (SMALLEST[i] as f64, LARGEST[i] as f64);
+                        (from + t * (to - from)).round() as u8
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
+fn to_pixels(p: Point, size: u32) -> (f64, f64) {
+    let scale = size as f64 / (BOUNDS.1 - BOUNDS.0);
+    ((p.0 - BOUNDS.0) * scale, (p.1 - BOUNDS.0) * scale)
+}
+
+pub(crate) fn svg(voronoi: &Voronoi, style: &Style, colors: &[[u8; 3]]) -> String {
+    let size = style.size;
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+    );
+    svg.push('\n');
+    let color = |c: Rgba<u8>| format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2]);
+
+    // Writing to a `String` can't fail
+    if style.cells {
+        writeln!(
+            svg,
+            r#"<g id="cells" stroke="{}" stroke-width="1">"#,
+            color(EDGE)
+        )
+        .unwrap();
+        for (cell, [r, g, b]) in voronoi.cells().iter().zip(colors) {
+            let points = cell
+                .polygon
+                .iter()
+                .map(|p| {
+                    let (x, y) = to_pixels(*p, size);
+                    format!("{:.2},{:.2}", x, y)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(
+                svg,
+                r##"<polygon points="{}" fill="#{:02x}{:02x}{:02x}"/>"##,
+                points, r, g, b
+            )
+            .unwrap();
+        }
+        svg.push_str("</g>\n");
+    }
+    if style.delaunay {
+        writeln!(
+            svg,
+            r#"<g id="delaunay" stroke="{}" stroke-width="1">"#,
+            color(DELAUNAY)
+        )
+        .unwrap();
+        let cells = voronoi.cells();
+        for (a, b) in voronoi.delaunay_edges() {
+            let (a, b) = (
+                to_pixels(cells[a].site, size),
+                to_pixels(cells[b].site, size),
+            );
+            writeln!(
+                svg,
+                r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}"/>"#,
+                a.0, a.1, b.0, b.1
+            )
+            .unwrap();
+        }
+        svg.push_str("</g>\n");
+    }
+    if style.sites {
+        writeln!(svg, r#"<g id="sites" fill="{}">"#, color(SITE)).unwrap();
+        for cell in voronoi.cells() {
+            let (x, y) = to_pixels(cell.site, size);
+            writeln!(
+                svg,
+                r#"<circle cx="{:.2}" cy="{:.2}" r="{}"/>"#,
+                x, y, SITE_RADIUS
+            )
+            .unwrap();
+        }
+        svg.push_str("</g>\n");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub(crate) fn png(voronoi: &Voronoi, style: &Style, colors: &[[u8; 3]]) -> RgbaImage {
+    let size = style.size;
+    let mut image = RgbaImage::from_pixel(size, size, BACKGROUND);
+    let cells = voronoi.cells();
+
+    if style.cells {
+        for (cell, [r, g, b]) in cells.iter().zip(colors) {
+            let polygon = cell
+                .polygon
+                .iter()
+                .map(|p| to_pixels(*p, size))
+                .collect::<Vec<_>>();
+            fill_convex(&mut image, &polygon, Rgba([*r, *g, *b, 255]));
+        }
+        for cell in cells {
+            for (i, a) in cell.polygon.iter().enumerate() {
+                let b = cell.polygon[(i + 1) % cell.polygon.len()];
+                line(&mut image, to_pixels(*a, size), to_pixels(b, size), EDGE);
+            }
+        }
+    }
+    if style.delaunay {
+        for (a, b) in voronoi.delaunay_edges() {
+            let (a, b) = (
+                to_pixels(cells[a].site, size),
+                to_pixels(cells[b].site, size),
+            );
+            line(&mut image, a, b, DELAUNAY);
+        }
+    }
+    if style.sites {
+        for cell in cells {
+            let (x, y) = to_pixels(cell.site, size);
+            let (x, y) = (x as i64, y as i64);
+            let radius = SITE_RADIUS as i64;
+            for y in y - radius..=y + radius {
+                for x in x - radius..=x + radius {
+                    put(&mut image, x, y, SITE);
+                }
+            }
+        }
+    }
+    image
+}
+
+fn put(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>) {
+    if (0..image.width() as i64).contains(&x) && (0..image.height() as i64).contains(&y) {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+/// Every pixel whose center is inside `polygon`, in either winding order
+fn fill_convex(image: &mut RgbaImage, polygon: &[(f64, f64)], color: Rgba<u8>) {
+    let bound = |axis: fn(&(f64, f64)) -> f64, f: fn(f64, f64) -> f64, start: f64| {
+        polygon.iter().map(axis).fold(start, f)
+    };
+    let (left, right) = (
+        bound(|p| p.0, f64::min, f64::INFINITY),
+        bound(|p| p.0, f64::max, 0.0),
+    );
+    let (top, bottom) = (
+        bound(|p| p.1, f64::min, f64::INFINITY),
+        bound(|p| p.1, f64::max, 0.0),
+    );
+
+    for y in top.floor() as i64..=bottom.ceil() as i64 {
+        for x in left.floor() as i64..=right.ceil() as i64 {
+            let center = (x as f64 + 0.5, y as f64 + 0.5);
+            let sides = polygon.iter().enumerate().map(|(i, a)| {
+                let b = polygon[(i + 1) % polygon.len()];
+                (b.0 - a.0) * (center.1 - a.1) - (b.1 - a.1) * (center.0 - a.0)
+            });
+            let (mut left_of, mut right_of) = (false, false);
+            for side in sides {
+                left_of |= side > 0.0;
+                right_of |= side < 0.0;
+            }
+            if !(left_of && right_of) {
+                put(image, x, y, color);
+            }
+        }
+    }
+}
+
+fn line(image: &mut RgbaImage, a: (f64, f64), b: (f64, f64), color: Rgba<u8>) {
+    let steps = (b.0 - a.0).abs().max((b.1 - a.1).abs()).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+        let t = step as f64 / steps as f64;
+        let (x, y) = (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1));
+        put(image, x as i64, y as i64, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+    use indoc::indoc;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::{cell_colors, png, svg, Coloring, Style, SITE, SMALLEST};
+    use crate::diagram::Voronoi;
+    use crate::random_point;
+
+    fn style() -> Style {
+        Style {
+            size: 100,
+            cells: true,
+            sites: true,
+            delaunay: true,
+        }
+    }
+
+    #[test]
+    fn layers() {
+        let mut voronoi = Voronoi::new();
+        voronoi.add_point((0.25, 0.5));
+        voronoi.add_point((0.75, 0.5));
+        // The same area, so both the smallest
+        let colors = cell_colors(&voronoi, Coloring::Area, &mut StdRng::seed_from_u64(0));
+        assert_eq!(colors, vec![SMALLEST; 2]);
+
+        assert_eq!(
+            svg(&voronoi, &style(), &colors),
+            indoc! {r##"
+                <svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" viewBox="0 0 100 100">
+                <g id="cells" stroke="#282828" stroke-width="1">
+                <polygon points="0.00,0.00 50.00,0.00 50.00,100.00 0.00,100.00" fill="#fff0aa"/>
+                <polygon points="50.00,0.00 100.00,0.00 100.00,100.00 50.00,100.00" fill="#fff0aa"/>
+                </g>
+                <g id="delaunay" stroke="#c81e1e" stroke-width="1">
+                <line x1="25.00" y1="50.00" x2="75.00" y2="50.00"/>
+                </g>
+                <g id="sites" fill="#000000">
+                <circle cx="25.00" cy="50.00" r="2"/>
+                <circle cx="75.00" cy="50.00" r="2"/>
+                </g>
+                </svg>
+            "##}
+        );
+        let only_sites = Style {
+            cells: false,
+            delaunay: false,
+            ..style()
+        };
+        assert!(!svg(&voronoi, &only_sites, &colors).contains("polygon"));
+
+        let image = png(&voronoi, &style(), &colors);
+        let [r, g, b] = SMALLEST;
+        assert_eq!(image.get_pixel(10, 10), &Rgba([r, g, b, 255]));
+        assert_eq!(image.get_pixel(25, 50), &SITE);
+        // Nothing gets through between the cells
+        assert!((0..100).all(|y| image.get_pixel(50, y)[0] < 255));
+    }
+
+    #[rstest::rstest]
+    fn deterministic(#[values(Coloring::Random, Coloring::Area)] coloring: Coloring) {
+        let render = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut voronoi = Voronoi::new();
+            for _ in 0..50 {
+                voronoi.add_point(random_point(&mut rng));
+            }
+            let colors = cell_colors(&voronoi, coloring, &mut rng);
+            (
+                svg(&voronoi, &style(), &colors),
+                png(&voronoi, &style(), &colors).into_raw(),
+            )
+        };
+        assert_eq!(render(1), render(1));
+        assert_ne!(render(1), render(2));
+    }
+}
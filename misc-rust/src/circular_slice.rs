@@ -0,0 +1,272 @@
+//! Slices of a ring of items, where every index wraps around, so a range can
+//! start before 0, end past the last item, or go round more than once.
+//!
+//! Ranges work like Python's `range(start, stop, step)`, except that each
+//! index is then taken modulo the length rather than clamped. A negative
+//! step counts down from `start`, and a range the wrong way round for its
+//! step is empty.
+//!
+//! ```
+//! use rust_misc::circular_slice::CircularSliceTrait;
+//!
+//! // A dial numbered 0 to 99, turned 8 to the left from 5
+//! let (dial, turns) = (5, 8);
+//! let numbers = (0..100).collect::<Vec<_>>();
+//! let passed = numbers.cycle_slice_by(dial..dial - turns - 1, -1);
+//! let passed = passed.copied().collect::<Vec<_>>();
+//! assert_eq!(passed, vec![5, 4, 3, 2, 1, 0, 99, 98, 97]);
+//! ```
+use std::ops::Range;
+
+/// The indices of a range into a ring of `len` items, each wrapped into
+/// `0..len`
+#[derive(Debug, Clone)]
+pub struct Wrapped {
+    len: usize,
+    /// The next index, before wrapping
+    front: isize,
+    remaining: usize,
+    step: isize,
+}
+
+impl Wrapped {
+    pub fn new(len: usize, range: Range<isize>, step: isize) -> Self {
+        assert_ne!(step, 0, "The step can't be 0");
+        let distance = if step > 0 {
+            range.end - range.start
+        } else {
+            range.start - range.end
+        };
+        let remaining = if len == 0 || distance <= 0 {
+            0
+        } else {
+            (distance as usize).div_ceil(step.unsigned_abs())
+        };
+        Self {
+            len,
+            front: range.start,
+            remaining,
+            step,
+        }
+    }
+
+    fn wrap(&self, index: isize) -> usize {
+        index.rem_euclid(self.len as isize) as usize
+    }
+}
+
+impl Iterator for Wrapped {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let index = self.wrap(self.front);
+        self.front += self.step;
+        self.remaining -= 1;
+        Some(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<usize> {
+        let skipped = n.min(self.remaining);
+        self.front += skipped as isize * self.step;
+        self.remaining -= skipped;
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for Wrapped {
+    fn next_back(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(self.wrap(self.front + self.remaining as isize * self.step))
+    }
+}
+
+impl ExactSizeIterator for Wrapped {}
+
+/// The items of a slice at each index of a wrapped range
+#[derive(Debug, Clone)]
+pub struct CircularSlice<'a, T> {
+    items: &'a [T],
+    indices: Wrapped,
+}
+
+impl<'a, T> Iterator for CircularSlice<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.indices.next().map(|i| &self.items[i])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<&'a T> {
+        self.indices.nth(n).map(|i| &self.items[i])
+    }
+}
+
+impl<T> DoubleEndedIterator for CircularSlice<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.indices.next_back().map(|i| &self.items[i])
+    }
+}
+
+impl<T> ExactSizeIterator for CircularSlice<'_, T> {}
+
+pub trait CircularSliceTrait<T> {
+    fn cycle_slice(&self, range: Range<isize>) -> CircularSlice<'_, T> {
+        self.cycle_slice_by(range, 1)
+    }
+
+    fn cycle_slice_by(&self, range: Range<isize>, step: isize) -> CircularSlice<'_, T>;
+}
+
+impl<T> CircularSliceTrait<T> for [T] {
+    fn cycle_slice_by(&self, range: Range<isize>, step: isize) -> CircularSlice<'_, T> {
+        CircularSlice {
+            items: self,
+            indices: Wrapped::new(self.len(), range, step),
+        }
+    }
+}
+
+/// The items of an iterator at each index of a wrapped range. There's no
+/// random access, so items come from a clone of the original iterator,
+/// which is only walked forwards. Going backwards, or wrapping round, starts
+/// over from a fresh clone, so slices are quicker when there's a choice.
+#[derive(Debug, Clone)]
+pub struct CircularIter<I> {
+    source: I,
+    current: I,
+    /// The index `current` is at
+    position: usize,
+    indices: Wrapped,
+}
+
+impl<I: Iterator + Clone> Iterator for CircularIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let index = self.indices.next()?;
+        if index < self.position {
+            self.current = self.source.clone();
+            self.position = 0;
+        }
+        let item = self.current.nth(index - self.position);
+        self.position = index + 1;
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+impl<I: Iterator + Clone> DoubleEndedIterator for CircularIter<I> {
+    fn next_back(&mut self) -> Option<I::Item> {
+        let index = self.indices.next_back()?;
+        self.source.clone().nth(index)
+    }
+}
+
+impl<I: Iterator + Clone> ExactSizeIterator for CircularIter<I> {}
+
+pub trait CircularIterTrait: Iterator + Clone {
+    fn cycle_slice(self, range: Range<isize>) -> CircularIter<Self> {
+        self.cycle_slice_by(range, 1)
+    }
+
+    /// Counts the items with a clone first
+    fn cycle_slice_by(self, range: Range<isize>, step: isize) -> CircularIter<Self> {
+        let len = self.clone().count();
+        CircularIter {
+            source: self.clone(),
+            current: self,
+            position: 0,
+            indices: Wrapped::new(len, range, step),
+        }
+    }
+}
+
+impl<I: Iterator + Clone> CircularIterTrait for I {}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+
+    /// Python's `range`, one index at a time
+    fn naive(len: usize, range: Range<isize>, step: isize) -> Vec<usize> {
+        let mut indices = vec![];
+        let mut i = range.start;
+        while (step > 0 && i < range.end) || (step < 0 && i > range.end) {
+            indices.push(i.rem_euclid(len as isize) as usize);
+            i += step;
+        }
+        indices
+    }
+
+    #[rstest::rstest]
+    fn matches_python(
+        #[values((-7, -2), (-2, 3), (0, 5), (3, 8), (1, 13), (4, 4), (5, 1), (8, -8))] range: (
+            isize,
+            isize,
+        ),
+        #[values(1, 2, 3, -1, -3)] step: isize,
+    ) {
+        // Written as pairs, as literal ranges going down are linted
+        let range = range.0..range.1;
+        let items = ['a', 'b', 'c', 'd', 'e'];
+        let expected = naive(items.len(), range.clone(), step)
+            .into_iter()
+            .map(|i| items[i])
+            .collect_vec();
+
+        let slice = items.cycle_slice_by(range.clone(), step);
+        assert_eq!(slice.len(), expected.len());
+        assert_eq!(slice.clone().copied().collect_vec(), expected);
+        assert_eq!(
+            slice.clone().rev().copied().collect_vec(),
+            expected.iter().rev().copied().collect_vec()
+        );
+        assert_eq!(slice.clone().nth(2).copied(), expected.get(2).copied());
+
+        let iter = items.iter().copied().cycle_slice_by(range, step);
+        assert_eq!(iter.len(), expected.len());
+        assert_eq!(iter.clone().collect_vec(), expected);
+        assert_eq!(
+            iter.rev().collect_vec(),
+            expected.iter().rev().copied().collect_vec()
+        );
+    }
+
+    #[test]
+    fn from_both_ends() {
+        let items = [1, 2, 3];
+        let mut slice = items.cycle_slice(-1..5);
+        assert_eq!(slice.next(), Some(&3));
+        assert_eq!(slice.next_back(), Some(&2));
+        assert_eq!(slice.next(), Some(&1));
+        assert_eq!(slice.next_back(), Some(&1));
+        assert_eq!(slice.len(), 2);
+        assert_eq!(slice.collect_vec(), vec![&2, &3]);
+    }
+
+    #[test]
+    fn empty_ring() {
+        let items: [u8; 0] = [];
+        assert_eq!(items.cycle_slice(-3..3).count(), 0);
+        assert_eq!((0..0).cycle_slice(0..3).count(), 0);
+    }
+}
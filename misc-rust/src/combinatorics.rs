@@ -0,0 +1,374 @@
+//! Permutations, k-permutations and combinations, generated in place.
+//!
+//! The generators rearrange a slice (or a buffer of indices they allocate
+//! once) and lend it out, so nothing is allocated per item. That means they
+//! can't be `Iterator`s, as each item borrows the generator until the next
+//! call, so they're used with `while let`:
+//!
+//! ```
+//! let mut items = [1, 2, 3];
+//! let mut permutations = rust_misc::combinatorics::Lexicographic::new(&mut items);
+//! while let Some(permutation) = permutations.next() {
+//!     println!("{:?}", permutation);
+//! }
+//! ```
+//!
+//! Ranks are positions in lexicographic order, counting from 0.
+
+/// Rearrange `slice` into the next greater permutation, returning false and
+/// leaving it sorted once it was the greatest. Equal items are only
+/// permuted once.
+pub fn next_permutation<T: Ord>(slice: &mut [T]) -> bool {
+    // The longest non-increasing suffix can't get any greater by itself
+    let Some(pivot) = slice.windows(2).rposition(|pair| pair[0] < pair[1]) else {
+        slice.reverse();
+        return false;
+    };
+    let successor = slice
+        .iter()
+        .rposition(|item| *item > slice[pivot])
+        .expect("the item after the pivot is greater");
+    slice.swap(pivot, successor);
+    slice[pivot + 1..].reverse();
+    true
+}
+
+/// Rearrange `slice` into the next greater k-permutation of its items,
+/// taking the first `k` as the selection. The rest must stay sorted, which
+/// this keeps up. Returns false and leaves it sorted after the greatest.
+pub fn next_k_permutation<T: Ord>(slice: &mut [T], k: usize) -> bool {
+    // The rest reversed is the greatest arrangement of them, so the next
+    // permutation of the whole slice moves on to the next prefix
+    slice[k..].reverse();
+    next_permutation(slice)
+}
+
+/// Every permutation of a slice in lexicographic order, starting from
+/// however it's arranged. Starting sorted gives every one.
+pub struct Lexicographic<'a, T> {
+    slice: &'a mut [T],
+    started: bool,
+}
+
+impl<'a, T: Ord> Lexicographic<'a, T> {
+    pub fn new(slice: &'a mut [T]) -> Self {
+        Self {
+            slice,
+            started: false,
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&[T]> {
+        if self.started && !next_permutation(self.slice) {
+            return None;
+        }
+        self.started = true;
+        Some(self.slice)
+    }
+}
+
+/// Every ordered selection of `k` items of a sorted slice, in lexicographic
+/// order. The selection is the start of the slice.
+pub struct KPermutations<'a, T> {
+    slice: &'a mut [T],
+    k: usize,
+    started: bool,
+}
+
+impl<'a, T: Ord> KPermutations<'a, T> {
+    pub fn new(slice: &'a mut [T], k: usize) -> Self {
+        assert!(k <= slice.len(), "can't choose {} of {}", k, slice.len());
+        Self {
+            slice,
+            k,
+            started: false,
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&[T]> {
+        if self.started && !next_k_permutation(self.slice, self.k) {
+            return None;
+        }
+        self.started = true;
+        Some(&self.slice[..self.k])
+    }
+}
+
+/// The swaps that take a slice of `n` items through every permutation with
+/// Heap's algorithm, one swap per permutation after the first
+#[derive(Debug, Clone)]
+pub struct HeapSwaps {
+    /// How far each level of the (unrolled) recursion has got
+    counters: Vec<usize>,
+    level: usize,
+}
+
+impl HeapSwaps {
+    pub fn new(n: usize) -> Self {
+        Self {
+            counters: vec![0; n],
+            level: 1,
+        }
+    }
+}
+
+impl Iterator for HeapSwaps {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.level < self.counters.len() {
+            let counter = self.counters[self.level];
+            if counter < self.level {
+                let swap = if self.level.is_multiple_of(2) {
+                    (0, self.level)
+                } else {
+                    (counter, self.level)
+                };
+                self.counters[self.level] += 1;
+                self.level = 1;
+                return Some(swap);
+            }
+            self.counters[self.level] = 0;
+            self.level += 1;
+        }
+        None
+    }
+}
+
+/// Every permutation of a slice by Heap's algorithm, which makes each one
+/// from the last with a single swap. They aren't in any useful order, and
+/// equal items give repeats, but it's the fastest way through all `n!`.
+pub struct Heap<'a, T> {
+    slice: &'a mut [T],
+    swaps: HeapSwaps,
+    started: bool,
+}
+
+impl<'a, T> Heap<'a, T> {
+    pub fn new(slice: &'a mut [T]) -> Self {
+        let swaps = HeapSwaps::new(slice.len());
+        Self {
+            slice,
+            swaps,
+            started: false,
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&[T]> {
+        if self.started {
+            let (a, b) = self.swaps.next()?;
+            self.slice.swap(a, b);
+        }
+        self.started = true;
+        Some(self.slice)
+    }
+}
+
+/// Every `k` of the indices `0..n`, in increasing order, in lexicographic
+/// order
+pub struct Combinations {
+    n: usize,
+    indices: Vec<usize>,
+    started: bool,
+}
+
+impl Combinations {
+    pub fn new(n: usize, k: usize) -> Self {
+        Self {
+            n,
+            // Ends straight away if there are none
+            indices: if k <= n { (0..k).collect() } else { vec![] },
+            started: k > n,
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&[usize]> {
+        if self.started {
+            let k = self.indices.len();
+            // The last index that isn't as high as it can go
+            let i = (0..k).rposition(|i| self.indices[i] < self.n - k + i)?;
+            self.indices[i] += 1;
+            for j in i + 1..k {
+                self.indices[j] = self.indices[j - 1] + 1;
+            }
+        }
+        self.started = true;
+        Some(&self.indices)
+    }
+}
+
+/// `n!`, which overflows past 34
+pub fn factorial(n: usize) -> u128 {
+    (1..=n as u128).product()
+}
+
+/// `n` choose `k`
+pub fn binomial(n: usize, k: usize) -> u128 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k) as u128;
+    // Each partial product is itself a binomial, so divides exactly
+    (0..k).fold(1, |result, i| result * (n as u128 - i) / (i + 1))
+}
+
+/// Of a permutation of `0..n`, via its Lehmer code
+pub fn permutation_rank(permutation: &[usize]) -> u128 {
+    let n = permutation.len();
+    let mut rank = 0;
+    for (i, item) in permutation.iter().enumerate() {
+        let smaller_later = permutation[i + 1..].iter().filter(|x| *x < item).count();
+        rank += smaller_later as u128 * factorial(n - 1 - i);
+    }
+    rank
+}
+
+/// The permutation of `0..n` with `rank`
+pub fn permutation_unrank(n: usize, mut rank: u128) -> Vec<usize> {
+    assert!(rank < factorial(n), "{} is past the last permutation", rank);
+    let mut left = (0..n).collect::<Vec<_>>();
+    let mut permutation = Vec::with_capacity(n);
+    for i in (0..n).rev() {
+        let place = factorial(i);
+        permutation.push(left.remove((rank / place) as usize));
+        rank %= place;
+    }
+    permutation
+}
+
+/// Of increasing indices out of `0..n`, in the order `Combinations` gives
+pub fn combination_rank(n: usize, combination: &[usize]) -> u128 {
+    let k = combination.len();
+    let mut rank = 0;
+    let mut next = 0;
+    for (i, &index) in combination.iter().enumerate() {
+        // All the combinations with something lower here come first
+        for skipped in next..index {
+            rank += binomial(n - 1 - skipped, k - 1 - i);
+        }
+        next = index + 1;
+    }
+    rank
+}
+
+/// The combination of `k` indices out of `0..n` with `rank`
+pub fn combination_unrank(n: usize, k: usize, mut rank: u128) -> Vec<usize> {
+    assert!(
+        rank < binomial(n, k),
+        "{} is past the last combination",
+        rank
+    );
+    let mut combination = Vec::with_capacity(k);
+    let mut index = 0;
+    for i in 0..k {
+        loop {
+            let starting_here = binomial(n - 1 - index, k - 1 - i);
+            if rank < starting_here {
+                break;
+            }
+            rank -= starting_here;
+            index += 1;
+        }
+        combination.push(index);
+        index += 1;
+    }
+    combination
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use itertools::Itertools;
+
+    use super::*;
+
+    /// Everything a generator lends out, copied
+    macro_rules! collect {
+        ($generator:expr) => {{
+            let mut generator = $generator;
+            let mut items = vec![];
+            while let Some(item) = generator.next() {
+                items.push(item.to_vec());
+            }
+            items
+        }};
+    }
+
+    #[rstest::rstest]
+    fn lexicographic_matches_itertools(#[values(0, 1, 2, 3, 5)] n: usize) {
+        let mut items = (0..n).collect_vec();
+        let expected = (0..n).permutations(n).collect_vec();
+        assert_eq!(collect!(Lexicographic::new(&mut items)), expected);
+        // Back where it started
+        assert_eq!(items, (0..n).collect_vec());
+    }
+
+    #[test]
+    fn repeated_items_are_permuted_once() {
+        let mut items = [1, 1, 2];
+        assert_eq!(
+            collect!(Lexicographic::new(&mut items)),
+            vec![vec![1, 1, 2], vec![1, 2, 1], vec![2, 1, 1]]
+        );
+    }
+
+    #[rstest::rstest]
+    fn heap_gives_every_permutation_once(#[values(0, 1, 2, 3, 4, 6)] n: usize) {
+        let mut items = (0..n).collect_vec();
+        let permutations = collect!(Heap::new(&mut items));
+        assert_eq!(permutations.len() as u128, factorial(n));
+        assert_eq!(
+            permutations.into_iter().collect::<HashSet<_>>(),
+            (0..n).permutations(n).collect()
+        );
+        assert_eq!(HeapSwaps::new(n).count() as u128, factorial(n).max(1) - 1);
+    }
+
+    #[rstest::rstest]
+    #[case(4, 0)]
+    #[case(4, 2)]
+    #[case(4, 4)]
+    #[case(5, 3)]
+    fn k_permutations(#[case] n: usize, #[case] k: usize) {
+        let mut items = (0..n).collect_vec();
+        assert_eq!(
+            collect!(KPermutations::new(&mut items, k)),
+            (0..n).permutations(k).collect_vec()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(5, 0)]
+    #[case(5, 2)]
+    #[case(5, 5)]
+    #[case(6, 3)]
+    #[case(2, 3)]
+    fn combinations(#[case] n: usize, #[case] k: usize) {
+        let combinations = collect!(Combinations::new(n, k));
+        assert_eq!(combinations, (0..n).combinations(k).collect_vec());
+        assert_eq!(combinations.len() as u128, binomial(n, k));
+
+        for (rank, combination) in combinations.iter().enumerate() {
+            assert_eq!(combination_rank(n, combination), rank as u128);
+            assert_eq!(combination_unrank(n, k, rank as u128), *combination);
+        }
+    }
+
+    #[test]
+    fn permutation_ranks() {
+        for (rank, permutation) in (0..5).permutations(5).enumerate() {
+            assert_eq!(permutation_rank(&permutation), rank as u128);
+            assert_eq!(permutation_unrank(5, rank as u128), permutation);
+        }
+        // Too big to enumerate
+        let last = (0..30).rev().collect_vec();
+        assert_eq!(permutation_rank(&last), factorial(30) - 1);
+        assert_eq!(permutation_unrank(30, factorial(30) - 1), last);
+        assert_eq!(binomial(60, 30), 118_264_581_564_861_424);
+    }
+}
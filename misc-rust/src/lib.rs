@@ -0,0 +1,5 @@
+//! Utilities shared between the binaries
+pub mod bezier;
+pub mod circular_slice;
+pub mod combinatorics;
+pub mod one_or_both;
@@ -0,0 +1,286 @@
+//! One value, the other, or both, e.g. for the items of two sorted lists
+//! lined up against each other with [`merge_join`].
+use std::cmp::Ordering;
+use std::iter::{FusedIterator, Peekable};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OneOrBoth<A, B> {
+    A(A),
+    B(B),
+    AB(A, B),
+}
+
+impl<A, B> OneOrBoth<A, B> {
+    pub fn try_new(a: Option<A>, b: Option<B>) -> Option<Self> {
+        match (a, b) {
+            (None, None) => None,
+            (None, Some(b)) => Some(OneOrBoth::B(b)),
+            (Some(a), None) => Some(OneOrBoth::A(a)),
+            (Some(a), Some(b)) => Some(OneOrBoth::AB(a, b)),
+        }
+    }
+
+    pub fn left(&self) -> Option<&A> {
+        match self {
+            OneOrBoth::A(a) | OneOrBoth::AB(a, _) => Some(a),
+            OneOrBoth::B(_) => None,
+        }
+    }
+
+    pub fn right(&self) -> Option<&B> {
+        match self {
+            OneOrBoth::B(b) | OneOrBoth::AB(_, b) => Some(b),
+            OneOrBoth::A(_) => None,
+        }
+    }
+
+    pub fn map_left<C>(self, f: impl FnOnce(A) -> C) -> OneOrBoth<C, B> {
+        match self {
+            OneOrBoth::A(a) => OneOrBoth::A(f(a)),
+            OneOrBoth::B(b) => OneOrBoth::B(b),
+            OneOrBoth::AB(a, b) => OneOrBoth::AB(f(a), b),
+        }
+    }
+
+    pub fn map_right<C>(self, f: impl FnOnce(B) -> C) -> OneOrBoth<A, C> {
+        match self {
+            OneOrBoth::A(a) => OneOrBoth::A(a),
+            OneOrBoth::B(b) => OneOrBoth::B(f(b)),
+            OneOrBoth::AB(a, b) => OneOrBoth::AB(a, f(b)),
+        }
+    }
+
+    /// The opposite of `try_new`
+    pub fn unzip(self) -> (Option<A>, Option<B>) {
+        match self {
+            OneOrBoth::A(a) => (Some(a), None),
+            OneOrBoth::B(b) => (None, Some(b)),
+            OneOrBoth::AB(a, b) => (Some(a), Some(b)),
+        }
+    }
+}
+
+impl<T> OneOrBoth<T, T> {
+    /// The one value, or both combined with `f`
+    pub fn reduce(self, f: impl FnOnce(T, T) -> T) -> T {
+        match self {
+            OneOrBoth::A(a) => a,
+            OneOrBoth::B(b) => b,
+            OneOrBoth::AB(a, b) => f(a, b),
+        }
+    }
+}
+
+impl<'a, I: 'a> OneOrBoth<I, I> {
+    pub fn prefer_first<T>(&'a self) -> &'a T
+    where
+        &'a T: From<&'a I>,
+    {
+        match self {
+            OneOrBoth::A(a) => a.into(),
+            OneOrBoth::B(b) => b.into(),
+            OneOrBoth::AB(a, _) => a.into(),
+        }
+    }
+
+    pub fn prefer_second<T>(&'a self) -> &'a T
+    where
+        &'a T: From<&'a I>,
+    {
+        match self {
+            OneOrBoth::A(a) => a.into(),
+            OneOrBoth::B(b) => b.into(),
+            OneOrBoth::AB(_, b) => b.into(),
+        }
+    }
+}
+
+// TODO: I bet there's some macro magic that allows you to easily access nested
+// versions of this: `OneOrBoth<A, OneOrBoth<B, OneOrBoth<C, ...>>>`
+//
+// I know that to prefer C, you could do
+// `val.prefer_second().prefer_second().prefer_first()`, but that doesn't allow
+// setting preference of any of the other options. Eh. That sounds too complex anyways, honestly...
+
+/// Lines up two sorted iterators, pairing items that compare equal and
+/// passing the rest through on their own side, in order. Equal items within
+/// one side are paired off one at a time, so `[1, 1]` against `[1]` gives
+/// `AB(1, 1)` then `A(1)`.
+pub struct MergeJoin<L: Iterator, R: Iterator, F> {
+    left: Peekable<L>,
+    right: Peekable<R>,
+    compare: F,
+}
+
+impl<L, R, F> Iterator for MergeJoin<L, R, F>
+where
+    L: Iterator,
+    R: Iterator,
+    F: FnMut(&L::Item, &R::Item) -> Ordering,
+{
+    type Item = OneOrBoth<L::Item, R::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ordering = match (self.left.peek(), self.right.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(l), Some(r)) => (self.compare)(l, r),
+        };
+        match ordering {
+            Ordering::Less => self.left.next().map(OneOrBoth::A),
+            Ordering::Greater => self.right.next().map(OneOrBoth::B),
+            Ordering::Equal => OneOrBoth::try_new(self.left.next(), self.right.next()),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (left_low, left_high) = self.left.size_hint();
+        let (right_low, right_high) = self.right.size_hint();
+        let high = left_high
+            .zip(right_high)
+            .and_then(|(l, r)| l.checked_add(r));
+        (left_low.max(right_low), high)
+    }
+}
+
+impl<L, R, F> FusedIterator for MergeJoin<L, R, F>
+where
+    L: FusedIterator,
+    R: FusedIterator,
+    F: FnMut(&L::Item, &R::Item) -> Ordering,
+{
+}
+
+type Compare<T> = fn(&T, &T) -> Ordering;
+
+/// [`merge_join_by`] with both sides' natural order
+pub fn merge_join<T, L, R>(left: L, right: R) -> MergeJoin<L::IntoIter, R::IntoIter, Compare<T>>
+where
+    T: Ord,
+    L: IntoIterator<Item = T>,
+    R: IntoIterator<Item = T>,
+{
+    merge_join_by(left, right, T::cmp)
+}
+
+/// Both sides must already be sorted by `compare`, which is given an item
+/// from the left then one from the right
+pub fn merge_join_by<L, R, F>(
+    left: L,
+    right: R,
+    compare: F,
+) -> MergeJoin<L::IntoIter, R::IntoIter, F>
+where
+    L: IntoIterator,
+    R: IntoIterator,
+    F: FnMut(&L::Item, &R::Item) -> Ordering,
+{
+    MergeJoin {
+        left: left.into_iter().peekable(),
+        right: right.into_iter().peekable(),
+        compare,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::left(OneOrBoth::A(3), (Some(3), None))]
+    #[case::right(OneOrBoth::B(4), (None, Some(4)))]
+    #[case::both(OneOrBoth::AB(3, 4), (Some(3), Some(4)))]
+    fn round_trip(
+        #[case] value: OneOrBoth<i32, i32>,
+        #[case] unzipped: (Option<i32>, Option<i32>),
+    ) {
+        assert_eq!(value.unzip(), unzipped);
+        assert_eq!(OneOrBoth::try_new(unzipped.0, unzipped.1), Some(value));
+        assert_eq!(value.left(), unzipped.0.as_ref());
+        assert_eq!(value.right(), unzipped.1.as_ref());
+    }
+
+    #[rstest]
+    #[case::left(OneOrBoth::A(3), 6)]
+    #[case::right(OneOrBoth::B(4), 4)]
+    #[case::both(OneOrBoth::AB(3, 4), 10)]
+    fn map_and_reduce(#[case] value: OneOrBoth<i32, i32>, #[case] expected: i32) {
+        let mapped = value.map_left(|a| a * 2).map_right(i64::from);
+        assert_eq!(
+            mapped.map_right(|b| b as i32).reduce(|a, b| a + b),
+            expected
+        );
+    }
+
+    #[test]
+    fn prefer() {
+        let both = OneOrBoth::AB(1, 2);
+        assert_eq!(*both.prefer_first::<i32>(), 1);
+        assert_eq!(*both.prefer_second::<i32>(), 2);
+        assert_eq!(*OneOrBoth::<i32, i32>::B(2).prefer_first::<i32>(), 2);
+    }
+
+    #[test]
+    fn merge_sorted_lists() {
+        // 2024 day 1's example lists, sorted
+        let left = [1, 2, 3, 3, 3, 4];
+        let right = [3, 3, 3, 4, 5, 9];
+        let joined = merge_join(left, right).collect_vec();
+        assert_eq!(
+            joined,
+            vec![
+                OneOrBoth::A(1),
+                OneOrBoth::A(2),
+                OneOrBoth::AB(3, 3),
+                OneOrBoth::AB(3, 3),
+                OneOrBoth::AB(3, 3),
+                OneOrBoth::AB(4, 4),
+                OneOrBoth::B(5),
+                OneOrBoth::B(9),
+            ]
+        );
+        let (only_left, only_right): (Vec<_>, Vec<_>) = joined
+            .into_iter()
+            .filter(|pair| !matches!(pair, OneOrBoth::AB(..)))
+            .map(OneOrBoth::unzip)
+            .unzip();
+        let only_left = only_left.into_iter().flatten().collect_vec();
+        let only_right = only_right.into_iter().flatten().collect_vec();
+        assert_eq!(only_left, vec![1, 2]);
+        assert_eq!(only_right, vec![5, 9]);
+    }
+
+    #[test]
+    fn merge_by_key() {
+        // Comparing two scans of (path, size), where one file changed size
+        let before = [("a", 1), ("b", 2), ("c", 3)];
+        let after = [("b", 2), ("c", 4), ("d", 5)];
+        let changes = merge_join_by(before, after, |l, r| l.0.cmp(r.0))
+            .filter(|pair| !matches!(pair, OneOrBoth::AB(l, r) if l == r))
+            .map(|pair| pair.map_left(|l| l.1).map_right(|r| r.1))
+            .collect_vec();
+        assert_eq!(
+            changes,
+            vec![OneOrBoth::A(1), OneOrBoth::AB(3, 4), OneOrBoth::B(5)]
+        );
+    }
+
+    #[rstest]
+    fn merge_matches_counts(
+        #[values(vec![], vec![1, 1, 2], vec![0, 5, 5, 5])] left: Vec<u8>,
+        #[values(vec![], vec![1], vec![1, 1, 1, 5, 7])] right: Vec<u8>,
+    ) {
+        let joined = merge_join(left.clone(), right.clone()).collect_vec();
+        let (lefts, rights): (Vec<_>, Vec<_>) = joined.iter().map(|pair| pair.unzip()).unzip();
+        assert_eq!(lefts.into_iter().flatten().collect_vec(), left);
+        assert_eq!(rights.into_iter().flatten().collect_vec(), right);
+        assert!(joined.iter().all(|pair| match pair {
+            OneOrBoth::AB(a, b) => a == b,
+            _ => true,
+        }));
+    }
+}